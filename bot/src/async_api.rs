@@ -7,7 +7,8 @@ use crate::{
 
 pub use crate::async_framework::*;
 pub use crate::blocking_api::{
-    console_log, csv, get_time_us, set_motors_pwm, write_csv_file, write_plain_file,
+    console_log, csv, get_time_us, set_motors_brake, set_motors_pwm, write_csv_file,
+    write_plain_file,
 };
 
 /// Get the current values of all line sensors.
@@ -35,7 +36,17 @@ pub async fn get_motor_angles() -> (u16, u16) {
     (left, right)
 }
 
-/// Get the current values of the gyro (returns pitch, roll, and yaw speed values in deg/s).
+/// Get the encoder counts since the start (returns left and right counts, they do not wrap every revolution).
+pub async fn read_encoder_ticks() -> (i32, i32) {
+    let values = device_operation_async(DeviceOperation::ReadEncoderTicks)
+        .into_future()
+        .await;
+    let left = values.get_i32(0);
+    let right = values.get_i32(1);
+    (left, right)
+}
+
+/// Get the current values of the gyro (returns pitch, roll, and yaw speed values in deg/s, saturating at the configured full scale).
 pub async fn read_gyro() -> (i16, i16, i16) {
     let values = device_operation_async(DeviceOperation::ReadGyro)
         .into_future()
@@ -46,6 +57,17 @@ pub async fn read_gyro() -> (i16, i16, i16) {
     (pitch, roll, yaw)
 }
 
+/// Get the current values of the accelerometer (returns right, forward and up acceleration values including gravity, with ±32767 at the configured full scale).
+pub async fn read_accel() -> (i16, i16, i16) {
+    let values = device_operation_async(DeviceOperation::ReadAccel)
+        .into_future()
+        .await;
+    let right = values.get_i16(0);
+    let forward = values.get_i16(1);
+    let up = values.get_i16(2);
+    (right, forward, up)
+}
+
 /// Get the current absolute euler angles (returns pitch, roll, and yaw values in deg).
 pub async fn get_imu_fused_data() -> (i16, i16, i16) {
     let values = device_operation_async(DeviceOperation::ReadImuFusedData)
@@ -57,6 +79,17 @@ pub async fn get_imu_fused_data() -> (i16, i16, i16) {
     (pitch, roll, yaw)
 }
 
+/// Get the current battery status (returns voltage in mV, current draw in mA, negative when regenerating, and remaining charge in mAh).
+pub async fn read_battery() -> (u16, i16, u16) {
+    let values = device_operation_async(DeviceOperation::ReadBattery)
+        .into_future()
+        .await;
+    let voltage = values.get_u16(0);
+    let current = values.get_i16(1);
+    let charge = values.get_u16(2);
+    (voltage, current, charge)
+}
+
 /// Sleep for the given time in microseconds.
 pub async fn sleep_for(time_us: u32) {
     device_operation_async(DeviceOperation::SleepFor(time_us))
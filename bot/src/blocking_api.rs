@@ -1,8 +1,8 @@
 use crate::{
     wasm_bindings::{
         devices::{
-            DeviceOperation, device_operation_blocking, device_operation_immediate,
-            set_motors_power,
+            DeviceOperation, MotorIdleMode, device_operation_blocking, device_operation_immediate,
+            set_motors_idle_mode, set_motors_power,
         },
         diagnostics::{CsvColumn, write_file, write_line},
     },
@@ -31,7 +31,15 @@ pub fn get_motor_angles() -> (u16, u16) {
     (left, right)
 }
 
-/// Get the current values of the gyro (returns pitch, roll, and yaw speed values in deg/s).
+/// Get the encoder counts since the start (returns left and right counts, they do not wrap every revolution).
+pub fn read_encoder_ticks() -> (i32, i32) {
+    let values = device_operation_immediate(DeviceOperation::ReadEncoderTicks);
+    let left = values.get_i32(0);
+    let right = values.get_i32(1);
+    (left, right)
+}
+
+/// Get the current values of the gyro (returns pitch, roll, and yaw speed values in deg/s, saturating at the configured full scale).
 pub fn read_gyro() -> (i16, i16, i16) {
     let values = device_operation_immediate(DeviceOperation::ReadGyro);
     let pitch = values.get_i16(0);
@@ -40,6 +48,15 @@ pub fn read_gyro() -> (i16, i16, i16) {
     (pitch, roll, yaw)
 }
 
+/// Get the current values of the accelerometer (returns right, forward and up acceleration values including gravity, with ±32767 at the configured full scale).
+pub fn read_accel() -> (i16, i16, i16) {
+    let values = device_operation_immediate(DeviceOperation::ReadAccel);
+    let right = values.get_i16(0);
+    let forward = values.get_i16(1);
+    let up = values.get_i16(2);
+    (right, forward, up)
+}
+
 /// Get the current absolute euler angles (returns pitch, roll, and yaw values in deg).
 pub fn get_imu_fused_data() -> (i16, i16, i16) {
     let values = device_operation_immediate(DeviceOperation::ReadImuFusedData);
@@ -49,6 +66,15 @@ pub fn get_imu_fused_data() -> (i16, i16, i16) {
     (pitch, roll, yaw)
 }
 
+/// Get the current battery status (returns voltage in mV, current draw in mA, negative when regenerating, and remaining charge in mAh).
+pub fn read_battery() -> (u16, i16, u16) {
+    let values = device_operation_immediate(DeviceOperation::ReadBattery);
+    let voltage = values.get_u16(0);
+    let current = values.get_i16(1);
+    let charge = values.get_u16(2);
+    (voltage, current, charge)
+}
+
 /// Get the current time in microseconds.
 pub fn get_time_us() -> u32 {
     device_operation_immediate(DeviceOperation::GetTime).get_u32(0)
@@ -107,6 +133,18 @@ pub fn set_motors_pwm(left: i16, right: i16) {
     set_motors_power(left, right);
 }
 
+/// Set whether the motor drivers brake or coast when not powering the motors.
+pub fn set_motors_brake(left: bool, right: bool) {
+    let mode = |brake| {
+        if brake {
+            MotorIdleMode::Brake
+        } else {
+            MotorIdleMode::Coast
+        }
+    };
+    set_motors_idle_mode(mode(left), mode(right));
+}
+
 pub mod csv {
     pub use crate::wasm_bindings::diagnostics::{CsvColumn, NamedValue, ValueKind};
     pub const C_I8: ValueKind = ValueKind::Int8;
@@ -6,7 +6,9 @@ pub mod examples;
 pub mod wasm_bindings;
 pub mod wasm_bindings_ext;
 
-use wasm_bindings::exports::robot::{Color, Configuration, Guest};
+use wasm_bindings::exports::robot::{
+    Color, Configuration, Encoders, Guest, InertialSensor, Motor, MotorDriver,
+};
 
 struct Component;
 
@@ -23,6 +25,30 @@ impl Guest for Component {
             wheel_diameter: 25.0,
             gear_ratio_num: 1,
             gear_ratio_den: 20,
+            motor: Motor::Catalogue("generic".to_string()),
+            battery: None,
+            driver: MotorDriver {
+                dead_band: 0,
+                slew_rate: 0,
+                brake_when_idle: false,
+            },
+            encoders: Encoders {
+                counts_per_revolution: 12,
+                motor_side: true,
+                jitter: 0.0,
+            },
+            gyro: InertialSensor {
+                full_scale: 500.0,
+                bias: 0.0,
+                drift: 0.0,
+                noise: 0.0,
+            },
+            accel: InertialSensor {
+                full_scale: 4.0,
+                bias: 0.0,
+                drift: 0.0,
+                noise: 0.0,
+            },
             front_sensors_spacing: 4.0,
             front_sensors_height: 4.0,
         }
@@ -62,7 +62,8 @@ pub mod devices {
     /// Read angle position of motors (2 u16 values),
     /// ready every period
     ReadMotorAngles,
-    /// Read gyroscope (3 i16 values: roll, pitch and yaw angular velocity),
+    /// Read gyroscope (3 i16 values: roll, pitch and yaw angular velocity
+    /// in deg/s, saturating at the configured full scale),
     /// ready every 2 periods
     ReadGyro,
     /// Read IMU fused data (3 i16 values: : roll, pitch and yaw angles),
@@ -82,6 +83,17 @@ pub mod devices {
     WaitEnabled,
     /// Wait until the status of the `enabled` signal is 0
     WaitDisabled,
+    /// Read battery status (voltage in mV as u16, current draw in mA as s16,
+    /// negative when the motors charge the battery back, and remaining charge
+    /// in mAh as u16, 65535 for an ideal supply), ready every period
+    ReadBattery,
+    /// Read the encoder counts of both motors since the start (2 s32 values),
+    /// ready every period
+    ReadEncoderTicks,
+    /// Read accelerometer (3 i16 values: right, forward and up acceleration
+    /// in the robot frame, gravity included, from -32767 to 32767 over the
+    /// configured full scale), ready every 2 periods
+    ReadAccel,
   }
   impl ::core::fmt::Debug for DeviceOperation {
     fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
@@ -122,6 +134,15 @@ pub mod devices {
         DeviceOperation::WaitDisabled => {
           f.debug_tuple("DeviceOperation::WaitDisabled").finish()
         }
+        DeviceOperation::ReadBattery => {
+          f.debug_tuple("DeviceOperation::ReadBattery").finish()
+        }
+        DeviceOperation::ReadEncoderTicks => {
+          f.debug_tuple("DeviceOperation::ReadEncoderTicks").finish()
+        }
+        DeviceOperation::ReadAccel => {
+          f.debug_tuple("DeviceOperation::ReadAccel").finish()
+        }
       }
     }
   }
@@ -147,6 +168,44 @@ pub mod devices {
   }
   /// The PWM duty cycle associated with the power of one motor driver
   pub type MotorPower = i16;
+  /// What a motor driver does with its motor when not powering it
+  #[repr(u8)]
+  #[derive(Clone, Copy, Eq, Ord, PartialEq, PartialOrd)]
+  pub enum MotorIdleMode {
+    /// Leave the motor terminals floating (the motor spins freely)
+    Coast,
+    /// Short the motor terminals (the motor brakes)
+    Brake,
+  }
+  impl ::core::fmt::Debug for MotorIdleMode {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+      match self {
+        MotorIdleMode::Coast => {
+          f.debug_tuple("MotorIdleMode::Coast").finish()
+        }
+        MotorIdleMode::Brake => {
+          f.debug_tuple("MotorIdleMode::Brake").finish()
+        }
+      }
+    }
+  }
+
+  impl MotorIdleMode{
+    #[doc(hidden)]
+    pub unsafe fn _lift(val: u8) -> MotorIdleMode{
+      if !cfg!(debug_assertions) {
+        return unsafe { ::core::mem::transmute(val) };
+      }
+
+      match val {
+        0 => MotorIdleMode::Coast,
+        1 => MotorIdleMode::Brake,
+
+        _ => panic!("invalid enum discriminant"),
+      }
+    }
+  }
+
   #[allow(unused_unsafe, clippy::all)]
   /// Perform a device operation (returns immediately the current value if possible, not for sleep or wait operations)
   #[allow(async_fn_in_trait)]
@@ -189,6 +248,15 @@ pub mod devices {
         DeviceOperation::WaitDisabled=> {
           (11i32, 0i32)
         }
+        DeviceOperation::ReadBattery=> {
+          (12i32, 0i32)
+        }
+        DeviceOperation::ReadEncoderTicks=> {
+          (13i32, 0i32)
+        }
+        DeviceOperation::ReadAccel=> {
+          (14i32, 0i32)
+        }
       };
       let ptr1 = ret_area.0.as_mut_ptr().cast::<u8>();
       #[cfg(target_arch = "wasm32")]
@@ -264,6 +332,15 @@ pub mod devices {
         DeviceOperation::WaitDisabled=> {
           (11i32, 0i32)
         }
+        DeviceOperation::ReadBattery=> {
+          (12i32, 0i32)
+        }
+        DeviceOperation::ReadEncoderTicks=> {
+          (13i32, 0i32)
+        }
+        DeviceOperation::ReadAccel=> {
+          (14i32, 0i32)
+        }
       };
       let ptr1 = ret_area.0.as_mut_ptr().cast::<u8>();
       #[cfg(target_arch = "wasm32")]
@@ -339,6 +416,15 @@ pub mod devices {
         DeviceOperation::WaitDisabled=> {
           (11i32, 0i32)
         }
+        DeviceOperation::ReadBattery=> {
+          (12i32, 0i32)
+        }
+        DeviceOperation::ReadEncoderTicks=> {
+          (13i32, 0i32)
+        }
+        DeviceOperation::ReadAccel=> {
+          (14i32, 0i32)
+        }
       };
       let ptr1 = ret_area.0.as_mut_ptr().cast::<u8>();
       #[cfg(target_arch = "wasm32")]
@@ -472,6 +558,25 @@ pub mod devices {
       wit_import0(_rt::as_i32(left), _rt::as_i32(right));
     }
   }
+  #[allow(unused_unsafe, clippy::all)]
+  /// Set the idle mode of both motor drivers, used at zero power and
+  /// in the off phase of the PWM cycle
+  #[allow(async_fn_in_trait)]
+  pub fn set_motors_idle_mode(left: MotorIdleMode,right: MotorIdleMode,) -> (){
+    unsafe {
+
+      #[cfg(target_arch = "wasm32")]
+      #[link(wasm_import_module = "devices")]
+      unsafe extern "C" {
+        #[link_name = "set-motors-idle-mode"]
+        fn wit_import0(_: i32, _: i32, );
+      }
+
+      #[cfg(not(target_arch = "wasm32"))]
+      unsafe extern "C" fn wit_import0(_: i32, _: i32, ) { unreachable!() }
+      wit_import0(left.clone() as i32, right.clone() as i32);
+    }
+  }
 
 }
 
@@ -729,6 +834,115 @@ pub mod exports {
         f.debug_struct("Color").field("r", &self.r).field("g", &self.g).field("b", &self.b).finish()
       }
     }
+    /// Parameters of a DC motor, at its shaft (before the transmission gears)
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct MotorParameters {
+      /// Shaft speed with no load at full power (in RPM, 1000 to 100000)
+      pub no_load_rpm: f32,
+      /// Shaft torque when stalled at full power (in mN·m, 0.1 to 100)
+      pub stall_torque: f32,
+      /// Voltage of the speed and torque ratings (in V, 1 to 24)
+      pub rated_voltage: f32,
+      /// Current drawn when stalled at the rated voltage (in A, 0.05 to 10)
+      pub stall_current: f32,
+    }
+    impl ::core::fmt::Debug for MotorParameters {
+      fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        f.debug_struct("MotorParameters").field("no-load-rpm", &self.no_load_rpm).field("stall-torque", &self.stall_torque).field("rated-voltage", &self.rated_voltage).field("stall-current", &self.stall_current).finish()
+      }
+    }
+    /// The DC motors driving the wheels
+    #[derive(Clone)]
+    pub enum Motor {
+      /// A motor from the simulator catalogue, by name (like "pololu-hp-6v")
+      Catalogue(_rt::String),
+      /// A motor with custom parameters
+      Custom(MotorParameters),
+    }
+    impl ::core::fmt::Debug for Motor {
+      fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        match self {
+          Motor::Catalogue(e) => {
+            f.debug_tuple("Motor::Catalogue").field(e).finish()
+          }
+          Motor::Custom(e) => {
+            f.debug_tuple("Motor::Custom").field(e).finish()
+          }
+        }
+      }
+    }
+    /// The battery powering the motors
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct BatteryParameters {
+      /// Nominal voltage (in V, 3 to 15)
+      pub voltage: f32,
+      /// Internal resistance (in Ω, 0.01 to 2)
+      pub internal_resistance: f32,
+      /// Charge when full (in mAh, 50 to 5000)
+      pub capacity: f32,
+    }
+    impl ::core::fmt::Debug for BatteryParameters {
+      fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        f.debug_struct("BatteryParameters").field("voltage", &self.voltage).field("internal-resistance", &self.internal_resistance).field("capacity", &self.capacity).finish()
+      }
+    }
+    /// The H-bridge drivers of the motors
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct MotorDriver {
+      /// Smallest power moving the motors (in PWM units, 0 to 200), lower ones act as zero
+      pub dead_band: u16,
+      /// Largest power change (in PWM units per millisecond, 1 to 2000), 0 for no limit
+      pub slew_rate: u16,
+      /// Brake instead of coasting when idle (until the robot sets the idle mode)
+      pub brake_when_idle: bool,
+    }
+    impl ::core::fmt::Debug for MotorDriver {
+      fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        f.debug_struct("MotorDriver").field("dead-band", &self.dead_band).field("slew-rate", &self.slew_rate).field("brake-when-idle", &self.brake_when_idle).finish()
+      }
+    }
+    /// The rotary encoders measuring the motors
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct Encoders {
+      /// Counts per revolution of the measured shaft (1 to 10000)
+      pub counts_per_revolution: u32,
+      /// Measure the motor shaft (before the gearbox) instead of the wheel
+      pub motor_side: bool,
+      /// Jitter of the readings (standard deviation in counts, 0 to 5)
+      pub jitter: f32,
+    }
+    impl ::core::fmt::Debug for Encoders {
+      fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        f.debug_struct("Encoders").field("counts-per-revolution", &self.counts_per_revolution).field("motor-side", &self.motor_side).field("jitter", &self.jitter).finish()
+      }
+    }
+    /// Range and error model of an inertial sensor, in deg/s for the gyroscope
+    /// and in g for the accelerometer
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct InertialSensor {
+      /// Largest measurable value, readings saturate beyond it
+      /// (gyroscope 125 to 4000, accelerometer 2 to 32)
+      pub full_scale: f32,
+      /// Standard deviation of the constant offset of each axis, drawn at power on
+      /// (gyroscope 0 to 20, accelerometer 0 to 0.5)
+      pub bias: f32,
+      /// Random walk of the offsets, per square root of second
+      /// (gyroscope 0 to 1, accelerometer 0 to 0.05)
+      pub drift: f32,
+      /// Standard deviation of the white noise of each reading
+      /// (gyroscope 0 to 5, accelerometer 0 to 0.5)
+      pub noise: f32,
+    }
+    impl ::core::fmt::Debug for InertialSensor {
+      fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        f.debug_struct("InertialSensor").field("full-scale", &self.full_scale).field("bias", &self.bias).field("drift", &self.drift).field("noise", &self.noise).finish()
+      }
+    }
     /// The appearence and build configuration of a robot
     #[derive(Clone)]
     pub struct Configuration {
@@ -752,6 +966,18 @@ pub mod exports {
       pub gear_ratio_num: u32,
       /// Transmission gear ratio denumerator (from 1 to 100)
       pub gear_ratio_den: u32,
+      /// Motors driving the wheels through the transmission
+      pub motor: Motor,
+      /// Battery powering the motors (none for an ideal supply at the motor rated voltage)
+      pub battery: Option<BatteryParameters>,
+      /// Drivers of the motors
+      pub driver: MotorDriver,
+      /// Encoders of the motors
+      pub encoders: Encoders,
+      /// Gyroscope of the IMU
+      pub gyro: InertialSensor,
+      /// Accelerometer of the IMU
+      pub accel: InertialSensor,
       /// Spacing of line sensors (in mm, from 1 to 15)
       pub front_sensors_spacing: f32,
       /// Height of line sensors from the ground (in mm, from 1 to wheels radius)
@@ -759,7 +985,7 @@ pub mod exports {
     }
     impl ::core::fmt::Debug for Configuration {
       fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
-        f.debug_struct("Configuration").field("name", &self.name).field("color-main", &self.color_main).field("color-secondary", &self.color_secondary).field("width-axle", &self.width_axle).field("length-front", &self.length_front).field("length-back", &self.length_back).field("clearing-back", &self.clearing_back).field("wheel-diameter", &self.wheel_diameter).field("gear-ratio-num", &self.gear_ratio_num).field("gear-ratio-den", &self.gear_ratio_den).field("front-sensors-spacing", &self.front_sensors_spacing).field("front-sensors-height", &self.front_sensors_height).finish()
+        f.debug_struct("Configuration").field("name", &self.name).field("color-main", &self.color_main).field("color-secondary", &self.color_secondary).field("width-axle", &self.width_axle).field("length-front", &self.length_front).field("length-back", &self.length_back).field("clearing-back", &self.clearing_back).field("wheel-diameter", &self.wheel_diameter).field("gear-ratio-num", &self.gear_ratio_num).field("gear-ratio-den", &self.gear_ratio_den).field("motor", &self.motor).field("battery", &self.battery).field("driver", &self.driver).field("encoders", &self.encoders).field("gyro", &self.gyro).field("accel", &self.accel).field("front-sensors-spacing", &self.front_sensors_spacing).field("front-sensors-height", &self.front_sensors_height).finish()
       }
     }
     #[doc(hidden)]
@@ -769,7 +995,7 @@ pub mod exports {
       T::setup()
     };
     let ptr1 = (&raw mut _RET_AREA.0).cast::<u8>();
-    let Configuration{ name:name2, color_main:color_main2, color_secondary:color_secondary2, width_axle:width_axle2, length_front:length_front2, length_back:length_back2, clearing_back:clearing_back2, wheel_diameter:wheel_diameter2, gear_ratio_num:gear_ratio_num2, gear_ratio_den:gear_ratio_den2, front_sensors_spacing:front_sensors_spacing2, front_sensors_height:front_sensors_height2, } = result0;
+    let Configuration{ name:name2, color_main:color_main2, color_secondary:color_secondary2, width_axle:width_axle2, length_front:length_front2, length_back:length_back2, clearing_back:clearing_back2, wheel_diameter:wheel_diameter2, gear_ratio_num:gear_ratio_num2, gear_ratio_den:gear_ratio_den2, motor:motor2, battery:battery2, driver:driver2, encoders:encoders2, gyro:gyro2, accel:accel2, front_sensors_spacing:front_sensors_spacing2, front_sensors_height:front_sensors_height2, } = result0;
     let vec3 = (name2.into_bytes()).into_boxed_slice();
     let ptr3 = vec3.as_ptr().cast::<u8>();
     let len3 = vec3.len();
@@ -791,8 +1017,58 @@ pub mod exports {
     *ptr1.add(24+2*::core::mem::size_of::<*const u8>()).cast::<f32>() = _rt::as_f32(wheel_diameter2);
     *ptr1.add(28+2*::core::mem::size_of::<*const u8>()).cast::<i32>() = _rt::as_i32(gear_ratio_num2);
     *ptr1.add(32+2*::core::mem::size_of::<*const u8>()).cast::<i32>() = _rt::as_i32(gear_ratio_den2);
-    *ptr1.add(36+2*::core::mem::size_of::<*const u8>()).cast::<f32>() = _rt::as_f32(front_sensors_spacing2);
-    *ptr1.add(40+2*::core::mem::size_of::<*const u8>()).cast::<f32>() = _rt::as_f32(front_sensors_height2);
+    match motor2 {
+      Motor::Catalogue(e) => {
+        *ptr1.add(32+3*::core::mem::size_of::<*const u8>()).cast::<u8>() = (0i32) as u8;
+        let vec6 = (e.into_bytes()).into_boxed_slice();
+        let ptr6 = vec6.as_ptr().cast::<u8>();
+        let len6 = vec6.len();
+        ::core::mem::forget(vec6);
+        *ptr1.add(32+5*::core::mem::size_of::<*const u8>()).cast::<usize>() = len6;
+        *ptr1.add(32+4*::core::mem::size_of::<*const u8>()).cast::<*mut u8>() = ptr6.cast_mut();
+      },
+      Motor::Custom(e) => {
+        *ptr1.add(32+3*::core::mem::size_of::<*const u8>()).cast::<u8>() = (1i32) as u8;
+        let MotorParameters{ no_load_rpm:no_load_rpm7, stall_torque:stall_torque7, rated_voltage:rated_voltage7, stall_current:stall_current7, } = e;
+        *ptr1.add(32+4*::core::mem::size_of::<*const u8>()).cast::<f32>() = _rt::as_f32(no_load_rpm7);
+        *ptr1.add(36+4*::core::mem::size_of::<*const u8>()).cast::<f32>() = _rt::as_f32(stall_torque7);
+        *ptr1.add(40+4*::core::mem::size_of::<*const u8>()).cast::<f32>() = _rt::as_f32(rated_voltage7);
+        *ptr1.add(44+4*::core::mem::size_of::<*const u8>()).cast::<f32>() = _rt::as_f32(stall_current7);
+      },
+    }
+    match battery2 {
+      Some(e) => {
+        *ptr1.add(48+4*::core::mem::size_of::<*const u8>()).cast::<u8>() = (1i32) as u8;
+        let BatteryParameters{ voltage:voltage8, internal_resistance:internal_resistance8, capacity:capacity8, } = e;
+        *ptr1.add(52+4*::core::mem::size_of::<*const u8>()).cast::<f32>() = _rt::as_f32(voltage8);
+        *ptr1.add(56+4*::core::mem::size_of::<*const u8>()).cast::<f32>() = _rt::as_f32(internal_resistance8);
+        *ptr1.add(60+4*::core::mem::size_of::<*const u8>()).cast::<f32>() = _rt::as_f32(capacity8);
+      },
+      None => {
+        {
+          *ptr1.add(48+4*::core::mem::size_of::<*const u8>()).cast::<u8>() = (0i32) as u8;
+        }
+      },
+    };let MotorDriver{ dead_band:dead_band9, slew_rate:slew_rate9, brake_when_idle:brake_when_idle9, } = driver2;
+    *ptr1.add(64+4*::core::mem::size_of::<*const u8>()).cast::<u16>() = (_rt::as_i32(dead_band9)) as u16;
+    *ptr1.add(66+4*::core::mem::size_of::<*const u8>()).cast::<u16>() = (_rt::as_i32(slew_rate9)) as u16;
+    *ptr1.add(68+4*::core::mem::size_of::<*const u8>()).cast::<u8>() = (match brake_when_idle9 { true => 1, false => 0 }) as u8;
+    let Encoders{ counts_per_revolution:counts_per_revolution10, motor_side:motor_side10, jitter:jitter10, } = encoders2;
+    *ptr1.add(72+4*::core::mem::size_of::<*const u8>()).cast::<i32>() = _rt::as_i32(counts_per_revolution10);
+    *ptr1.add(76+4*::core::mem::size_of::<*const u8>()).cast::<u8>() = (match motor_side10 { true => 1, false => 0 }) as u8;
+    *ptr1.add(80+4*::core::mem::size_of::<*const u8>()).cast::<f32>() = _rt::as_f32(jitter10);
+    let InertialSensor{ full_scale:full_scale11, bias:bias11, drift:drift11, noise:noise11, } = gyro2;
+    *ptr1.add(84+4*::core::mem::size_of::<*const u8>()).cast::<f32>() = _rt::as_f32(full_scale11);
+    *ptr1.add(88+4*::core::mem::size_of::<*const u8>()).cast::<f32>() = _rt::as_f32(bias11);
+    *ptr1.add(92+4*::core::mem::size_of::<*const u8>()).cast::<f32>() = _rt::as_f32(drift11);
+    *ptr1.add(96+4*::core::mem::size_of::<*const u8>()).cast::<f32>() = _rt::as_f32(noise11);
+    let InertialSensor{ full_scale:full_scale12, bias:bias12, drift:drift12, noise:noise12, } = accel2;
+    *ptr1.add(100+4*::core::mem::size_of::<*const u8>()).cast::<f32>() = _rt::as_f32(full_scale12);
+    *ptr1.add(104+4*::core::mem::size_of::<*const u8>()).cast::<f32>() = _rt::as_f32(bias12);
+    *ptr1.add(108+4*::core::mem::size_of::<*const u8>()).cast::<f32>() = _rt::as_f32(drift12);
+    *ptr1.add(112+4*::core::mem::size_of::<*const u8>()).cast::<f32>() = _rt::as_f32(noise12);
+    *ptr1.add(116+4*::core::mem::size_of::<*const u8>()).cast::<f32>() = _rt::as_f32(front_sensors_spacing2);
+    *ptr1.add(120+4*::core::mem::size_of::<*const u8>()).cast::<f32>() = _rt::as_f32(front_sensors_height2);
     ptr1
   } }
   #[doc(hidden)]
@@ -801,6 +1077,15 @@ pub mod exports {
     let l0 = *arg0.add(0).cast::<*mut u8>();
     let l1 = *arg0.add(::core::mem::size_of::<*const u8>()).cast::<usize>();
     _rt::cabi_dealloc(l0, l1, 1);
+    let l2 = i32::from(*arg0.add(32+3*::core::mem::size_of::<*const u8>()).cast::<u8>());
+    match l2 {
+      0 => {
+        let l3 = *arg0.add(32+4*::core::mem::size_of::<*const u8>()).cast::<*mut u8>();
+        let l4 = *arg0.add(32+5*::core::mem::size_of::<*const u8>()).cast::<usize>();
+        _rt::cabi_dealloc(l3, l4, 1);
+      },
+      _ => (),
+    }
   } }
   #[doc(hidden)]
   #[allow(non_snake_case, unused_unsafe)]
@@ -841,8 +1126,8 @@ pub(crate) use __export_robot_cabi;
 
 #[cfg_attr(target_pointer_width="64", repr(align(8)))]
 #[cfg_attr(target_pointer_width="32", repr(align(4)))]
-struct _RetArea([::core::mem::MaybeUninit::<u8>; 40+3*::core::mem::size_of::<*const u8>()]);
-static mut _RET_AREA: _RetArea = _RetArea([::core::mem::MaybeUninit::uninit(); 40+3*::core::mem::size_of::<*const u8>()]);
+struct _RetArea([::core::mem::MaybeUninit::<u8>; 120+5*::core::mem::size_of::<*const u8>()]);
+static mut _RET_AREA: _RetArea = _RetArea([::core::mem::MaybeUninit::uninit(); 120+5*::core::mem::size_of::<*const u8>()]);
 
 }
 
@@ -992,36 +1277,46 @@ pub(crate) use __export_line_follower_robot_impl as export;
 #[unsafe(link_section = "component-type:wit-bindgen:0.45.0:component:line-follower-robot:line-follower-robot:encoded world")]
 #[doc(hidden)]
 #[allow(clippy::octal_escapes)]
-pub static __WIT_BINDGEN_COMPONENT_TYPE: [u8; 1359] = *b"\
-\0asm\x0d\0\x01\0\0\x19\x16wit-component-encoding\x04\0\x07\xc5\x09\x01A\x02\x01\
-A\x06\x01B\x19\x01y\x04\0\x07time-us\x03\0\0\x01r\x02\x02idy\x08ready-at\x01\x04\
+pub static __WIT_BINDGEN_COMPONENT_TYPE: [u8; 1899] = *b"\
+\0asm\x0d\0\x01\0\0\x19\x16wit-component-encoding\x04\0\x07\xe1\x0d\x01A\x02\x01\
+A\x06\x01B\x1d\x01y\x04\0\x07time-us\x03\0\0\x01r\x02\x02idy\x08ready-at\x01\x04\
 \0\x0dfuture-handle\x03\0\x02\x01r\x08\x02v0}\x02v1}\x02v2}\x02v3}\x02v4}\x02v5}\
-\x02v6}\x02v7}\x04\0\x0cdevice-value\x03\0\x04\x01q\x0c\x0eread-line-left\0\0\x0f\
-read-line-right\0\0\x11read-motor-angles\0\0\x09read-gyro\0\0\x13read-imu-fused-\
-data\0\0\x08get-time\0\0\x0aget-period\0\0\x09sleep-for\x01\x01\0\x0bsleep-until\
-\x01\x01\0\x0bget-enabled\0\0\x0cwait-enabled\0\0\x0dwait-disabled\0\0\x04\0\x10\
-device-operation\x03\0\x06\x01q\x02\x07pending\0\0\x05ready\x01\x05\0\x04\0\x15p\
-oll-operation-status\x03\0\x08\x01|\x04\0\x0bmotor-power\x03\0\x0a\x01@\x01\x09o\
-peration\x07\0\x05\x04\0\x1adevice-operation-immediate\x01\x0c\x04\0\x19device-o\
-peration-blocking\x01\x0c\x01@\x01\x09operation\x07\0\x03\x04\0\x16device-operat\
-ion-async\x01\x0d\x01@\x01\x06handle\x03\0\x09\x04\0\x0bdevice-poll\x01\x0e\x01@\
-\x01\x05start\x7f\x01\0\x04\0\x09poll-loop\x01\x0f\x01@\x01\x06handle\x03\x01\0\x04\
-\0\x0dforget-handle\x01\x10\x01@\x02\x04left\x0b\x05right\x0b\x01\0\x04\0\x10set\
--motors-power\x01\x11\x03\0\x07devices\x05\0\x01B\x0e\x01r\x02\x04names\x05value\
-z\x04\0\x0bnamed-value\x03\0\0\x01p\x01\x01q\x09\x04int8\0\0\x05int16\0\0\x05int\
-32\0\0\x05uint8\0\0\x06uint16\0\0\x06uint32\0\0\x05named\x01\x02\0\x04pad8\0\0\x05\
-pad16\0\0\x04\0\x0avalue-kind\x03\0\x03\x01r\x02\x04names\x04kind\x04\x04\0\x0ac\
-sv-column\x03\0\x05\x01@\x01\x04texts\x01\0\x04\0\x0awrite-line\x01\x07\x01p}\x01\
-p\x06\x01k\x09\x01@\x03\x04names\x04data\x08\x03csv\x0a\x01\0\x04\0\x0awrite-fil\
-e\x01\x0b\x03\0\x0bdiagnostics\x05\x01\x01B\x08\x01r\x03\x01r}\x01g}\x01b}\x04\0\
-\x05color\x03\0\0\x01r\x0c\x04names\x0acolor-main\x01\x0fcolor-secondary\x01\x0a\
-width-axlev\x0clength-frontv\x0blength-backv\x0dclearing-backv\x0ewheel-diameter\
-v\x0egear-ratio-numy\x0egear-ratio-deny\x15front-sensors-spacingv\x14front-senso\
-rs-heightv\x04\0\x0dconfiguration\x03\0\x02\x01@\0\0\x03\x04\0\x05setup\x01\x04\x01\
-@\0\x01\0\x04\0\x03run\x01\x05\x04\0\x05robot\x05\x02\x04\01component:line-follo\
-wer-robot/line-follower-robot\x04\0\x0b\x19\x01\0\x13line-follower-robot\x03\0\0\
-\0G\x09producers\x01\x0cprocessed-by\x02\x0dwit-component\x070.238.0\x10wit-bind\
-gen-rust\x060.45.0";
+\x02v6}\x02v7}\x04\0\x0cdevice-value\x03\0\x04\x01q\x0f\x0eread-line-left\0\0\x0f\
+read-line-right\0\0\x11read-motor-angles\0\0\x12read-encoder-ticks\0\0\x09read-g\
+yro\0\0\x0aread-accel\0\0\x13read-imu-fused-data\0\0\x0cread-battery\0\0\x08get-\
+time\0\0\x0aget-period\0\0\x09sleep-for\x01\x01\0\x0bsleep-until\x01\x01\0\x0bge\
+t-enabled\0\0\x0cwait-enabled\0\0\x0dwait-disabled\0\0\x04\0\x10device-operation\
+\x03\0\x06\x01q\x02\x07pending\0\0\x05ready\x01\x05\0\x04\0\x15poll-operation-st\
+atus\x03\0\x08\x01|\x04\0\x0bmotor-power\x03\0\x0a\x01m\x02\x05coast\x05brake\x04\
+\0\x0fmotor-idle-mode\x03\0\x0c\x01@\x01\x09operation\x07\0\x05\x04\0\x1adevice-\
+operation-immediate\x01\x0e\x04\0\x19device-operation-blocking\x01\x0e\x01@\x01\x09\
+operation\x07\0\x03\x04\0\x16device-operation-async\x01\x0f\x01@\x01\x06handle\x03\
+\0\x09\x04\0\x0bdevice-poll\x01\x10\x01@\x01\x05start\x7f\x01\0\x04\0\x09poll-lo\
+op\x01\x11\x01@\x01\x06handle\x03\x01\0\x04\0\x0dforget-handle\x01\x12\x01@\x02\x04\
+left\x0b\x05right\x0b\x01\0\x04\0\x10set-motors-power\x01\x13\x01@\x02\x04left\x0d\
+\x05right\x0d\x01\0\x04\0\x14set-motors-idle-mode\x01\x14\x03\0\x07devices\x05\0\
+\x01B\x0e\x01r\x02\x04names\x05valuez\x04\0\x0bnamed-value\x03\0\0\x01p\x01\x01q\
+\x09\x04int8\0\0\x05int16\0\0\x05int32\0\0\x05uint8\0\0\x06uint16\0\0\x06uint32\0\
+\0\x05named\x01\x02\0\x04pad8\0\0\x05pad16\0\0\x04\0\x0avalue-kind\x03\0\x03\x01\
+r\x02\x04names\x04kind\x04\x04\0\x0acsv-column\x03\0\x05\x01@\x01\x04texts\x01\0\
+\x04\0\x0awrite-line\x01\x07\x01p}\x01p\x06\x01k\x09\x01@\x03\x04names\x04data\x08\
+\x03csv\x0a\x01\0\x04\0\x0awrite-file\x01\x0b\x03\0\x0bdiagnostics\x05\x01\x01B\x15\
+\x01r\x03\x01r}\x01g}\x01b}\x04\0\x05color\x03\0\0\x01r\x04\x0bno-load-rpmv\x0cs\
+tall-torquev\x0drated-voltagev\x0dstall-currentv\x04\0\x10motor-parameters\x03\0\
+\x02\x01q\x02\x09catalogue\x01s\0\x06custom\x01\x03\0\x04\0\x05motor\x03\0\x04\x01\
+r\x03\x07voltagev\x13internal-resistancev\x08capacityv\x04\0\x12battery-paramete\
+rs\x03\0\x06\x01r\x03\x09dead-band{\x09slew-rate{\x0fbrake-when-idle\x7f\x04\0\x0c\
+motor-driver\x03\0\x08\x01r\x03\x15counts-per-revolutiony\x0amotor-side\x7f\x06j\
+itterv\x04\0\x08encoders\x03\0\x0a\x01r\x04\x0afull-scalev\x04biasv\x05driftv\x05\
+noisev\x04\0\x0finertial-sensor\x03\0\x0c\x01k\x07\x01r\x12\x04names\x0acolor-ma\
+in\x01\x0fcolor-secondary\x01\x0awidth-axlev\x0clength-frontv\x0blength-backv\x0d\
+clearing-backv\x0ewheel-diameterv\x0egear-ratio-numy\x0egear-ratio-deny\x05motor\
+\x05\x07battery\x0e\x06driver\x09\x08encoders\x0b\x04gyro\x0d\x05accel\x0d\x15fr\
+ont-sensors-spacingv\x14front-sensors-heightv\x04\0\x0dconfiguration\x03\0\x0f\x01\
+@\0\0\x10\x04\0\x05setup\x01\x11\x01@\0\x01\0\x04\0\x03run\x01\x12\x04\0\x05robo\
+t\x05\x02\x04\01component:line-follower-robot/line-follower-robot\x04\0\x0b\x19\x01\
+\0\x13line-follower-robot\x03\0\0\0G\x09producers\x01\x0cprocessed-by\x02\x0dwit\
+-component\x070.238.0\x10wit-bindgen-rust\x060.45.0";
 
 #[inline(never)]
 #[doc(hidden)]
@@ -99,11 +99,32 @@ impl WheelExecutionData {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct ActivityData {
     pub start_time_us: Option<u32>,
     pub out_time_us: Option<u32>,
     pub end_time_us: Option<u32>,
+    /// When the bot hit an obstacle (which disqualifies it)
+    pub collision_time_us: Option<u32>,
+    /// Times when each lap was completed (closed loop tracks only)
+    pub lap_end_times_us: Vec<u32>,
+    /// Times when each track checkpoint was reached (in order)
+    pub checkpoint_times_us: Vec<u32>,
+    /// Times when the maximum distance covered along the track grew, with
+    /// the distance in meters
+    pub progress_us: Vec<(u32, f32)>,
+}
+
+/// The maximum progress is recorded only when it grows by at least this
+/// distance (in meters)
+pub const PROGRESS_RECORD_STEP: f32 = 0.01;
+
+/// Lap progress of a bot on a closed loop track.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LapInfo {
+    pub completed_laps: usize,
+    pub last_lap_secs: Option<f32>,
+    pub best_lap_secs: Option<f32>,
 }
 
 #[derive(Clone, Copy)]
@@ -112,6 +133,7 @@ pub enum BotStatus {
     Racing { time_secs: f32 },
     EndedAt { time_secs: f32 },
     OutAt { time_secs: f32 },
+    CollidedAt { time_secs: f32 },
 }
 
 impl BotStatus {
@@ -121,6 +143,7 @@ impl BotStatus {
             BotStatus::Racing { time_secs } => *time_secs,
             BotStatus::EndedAt { time_secs } => *time_secs,
             BotStatus::OutAt { time_secs } => *time_secs,
+            BotStatus::CollidedAt { time_secs } => *time_secs,
         }
     }
 }
@@ -128,36 +151,79 @@ impl BotStatus {
 #[derive(Clone, Copy, PartialEq)]
 pub enum BotFinalStatus {
     NotStarted,
-    NotEnded,
-    EndedAt { time_secs: f32 },
-    OutAt { time_secs: f32 },
+    NotEnded {
+        progress: f32,
+    },
+    /// The bot reached the end (or completed all the laps of a closed loop)
+    EndedAt {
+        time_secs: f32,
+        best_lap_secs: Option<f32>,
+    },
+    /// The bot completed some laps of a closed loop but not all of them
+    LapsDone {
+        laps: usize,
+        best_lap_secs: f32,
+    },
+    OutAt {
+        time_secs: f32,
+        progress: f32,
+    },
+    /// The bot hit an obstacle and is disqualified
+    CollidedAt {
+        time_secs: f32,
+        progress: f32,
+    },
 }
 
 impl BotFinalStatus {
     pub fn end_time(&self) -> Option<f32> {
         match self {
             BotFinalStatus::NotStarted => None,
-            BotFinalStatus::NotEnded => None,
-            BotFinalStatus::EndedAt { time_secs } => Some(*time_secs),
-            BotFinalStatus::OutAt { time_secs } => Some(*time_secs),
+            BotFinalStatus::NotEnded { .. } => None,
+            BotFinalStatus::EndedAt { time_secs, .. } => Some(*time_secs),
+            BotFinalStatus::LapsDone { .. } => None,
+            BotFinalStatus::OutAt { time_secs, .. } => Some(*time_secs),
+            BotFinalStatus::CollidedAt { time_secs, .. } => Some(*time_secs),
         }
     }
 
     fn kind_rank(&self) -> usize {
         match self {
-            BotFinalStatus::NotStarted => 3,
-            BotFinalStatus::NotEnded => 2,
+            BotFinalStatus::NotStarted => 4,
+            // Bots that went out and bots that did not finish are ranked
+            // together by their progress
+            BotFinalStatus::NotEnded { .. } => 2,
             BotFinalStatus::EndedAt { .. } => 0,
-            BotFinalStatus::OutAt { .. } => 1,
+            BotFinalStatus::LapsDone { .. } => 1,
+            BotFinalStatus::OutAt { .. } => 2,
+            BotFinalStatus::CollidedAt { .. } => 3,
         }
     }
 
-    fn kind_value(&self) -> f32 {
+    /// Values compared (in order) between statuses of the same kind
+    fn kind_values(&self) -> [f32; 2] {
         match self {
-            BotFinalStatus::NotStarted => 0.0,
-            BotFinalStatus::NotEnded => 0.0,
-            BotFinalStatus::EndedAt { time_secs } => *time_secs,
-            BotFinalStatus::OutAt { time_secs } => *time_secs,
+            BotFinalStatus::NotStarted => [0.0, 0.0],
+            // Still racing when the time ran out, so ahead of any bot that
+            // went out at the same progress
+            BotFinalStatus::NotEnded { progress } => [-*progress, 0.0],
+            BotFinalStatus::EndedAt {
+                time_secs,
+                best_lap_secs,
+            } => [*time_secs, best_lap_secs.unwrap_or(0.0)],
+            BotFinalStatus::LapsDone {
+                laps,
+                best_lap_secs,
+            } => [-(*laps as f32), *best_lap_secs],
+            // The further along the track the better, whatever the time
+            BotFinalStatus::OutAt {
+                time_secs,
+                progress,
+            } => [-*progress, *time_secs],
+            BotFinalStatus::CollidedAt {
+                time_secs,
+                progress,
+            } => [-*progress, *time_secs],
         }
     }
 }
@@ -167,7 +233,7 @@ impl std::cmp::Eq for BotFinalStatus {}
 impl std::cmp::PartialOrd for BotFinalStatus {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         match self.kind_rank().cmp(&other.kind_rank()) {
-            std::cmp::Ordering::Equal => self.kind_value().partial_cmp(&other.kind_value()),
+            std::cmp::Ordering::Equal => self.kind_values().partial_cmp(&other.kind_values()),
             ord => Some(ord),
         }
     }
@@ -177,9 +243,11 @@ impl std::cmp::Ord for BotFinalStatus {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         match self.kind_rank().cmp(&other.kind_rank()) {
             std::cmp::Ordering::Equal => {
-                let self_value = self.kind_value();
-                let other_value = other.kind_value();
-                self_value.total_cmp(&other_value)
+                let self_values = self.kind_values();
+                let other_values = other.kind_values();
+                self_values[0]
+                    .total_cmp(&other_values[0])
+                    .then(self_values[1].total_cmp(&other_values[1]))
             }
             ord => ord,
         }
@@ -196,11 +264,83 @@ impl ActivityData {
             },
             out_time_us: None,
             end_time_us: None,
+            collision_time_us: None,
+            lap_end_times_us: Vec::new(),
+            checkpoint_times_us: Vec::new(),
+            progress_us: Vec::new(),
+        }
+    }
+
+    /// Maximum distance covered along the track (in meters)
+    pub fn max_progress(&self) -> f32 {
+        self.progress_us
+            .last()
+            .map(|&(_, progress)| progress)
+            .unwrap_or(0.0)
+    }
+
+    /// Records the current distance covered along the track if it is a new
+    /// maximum
+    pub fn record_progress(&mut self, time_us: u32, progress: f32) {
+        if progress >= self.max_progress() + PROGRESS_RECORD_STEP {
+            self.progress_us.push((time_us, progress));
+        }
+    }
+
+    pub fn progress_at_time(&self, time_secs: f32) -> f32 {
+        let time_us: u32 = (time_secs * 1_000_000.0) as u32;
+        self.progress_us
+            .iter()
+            .take_while(|&&(progress_time_us, _)| progress_time_us <= time_us)
+            .last()
+            .map(|&(_, progress)| progress)
+            .unwrap_or(0.0)
+    }
+
+    /// Lap durations in seconds (the first lap starts at the start time)
+    pub fn lap_times_secs(&self) -> Vec<f32> {
+        let Some(start_us) = self.start_time_us else {
+            return Vec::new();
+        };
+        let mut lap_start_us = start_us;
+        self.lap_end_times_us
+            .iter()
+            .map(|&lap_end_us| {
+                let lap_us = lap_end_us.saturating_sub(lap_start_us);
+                lap_start_us = lap_end_us;
+                lap_us as f32 / 1_000_000.0
+            })
+            .collect()
+    }
+
+    pub fn checkpoints_at_time(&self, time_secs: f32) -> usize {
+        let time_us: u32 = (time_secs * 1_000_000.0) as u32;
+        self.checkpoint_times_us
+            .iter()
+            .take_while(|&&checkpoint_us| checkpoint_us <= time_us)
+            .count()
+    }
+
+    pub fn lap_info_at_time(&self, time_secs: f32) -> LapInfo {
+        let time_us: u32 = (time_secs * 1_000_000.0) as u32;
+        let completed_laps = self
+            .lap_end_times_us
+            .iter()
+            .take_while(|&&lap_end_us| lap_end_us <= time_us)
+            .count();
+        let lap_times = &self.lap_times_secs()[..completed_laps];
+        LapInfo {
+            completed_laps,
+            last_lap_secs: lap_times.last().copied(),
+            best_lap_secs: lap_times.iter().copied().reduce(f32::min),
         }
     }
 
     pub fn is_active_now(&self) -> bool {
-        self.start_time_us.is_some() && self.out_time_us.is_none() && self.end_time_us.is_none()
+        self.start_time_us.is_some()
+            && self.out_time_us.is_none()
+            && self.end_time_us.is_none()
+            && self.collision_time_us.is_none()
     }
 
     pub fn status_at_time(&self, time_secs: f32) -> BotStatus {
@@ -237,6 +377,15 @@ impl ActivityData {
             }
         }
 
+        if let Some(collision_us) = self.collision_time_us
+            && time_us > collision_us
+        {
+            let collision_secs = collision_us as f32 / 1_000_000.0;
+            return BotStatus::CollidedAt {
+                time_secs: (collision_secs - start_secs).max(0.0),
+            };
+        }
+
         BotStatus::Racing {
             time_secs: time_secs - start_secs,
         }
@@ -256,6 +405,22 @@ impl ActivityData {
             };
             return BotFinalStatus::EndedAt {
                 time_secs: racing_us as f32 / 1_000_000.0,
+                best_lap_secs: self.lap_times_secs().into_iter().reduce(f32::min),
+            };
+        }
+
+        // Hitting an obstacle disqualifies the bot, whatever it did before
+        if let Some(collision_us) = self.collision_time_us {
+            return BotFinalStatus::CollidedAt {
+                time_secs: collision_us.saturating_sub(start_us) as f32 / 1_000_000.0,
+                progress: self.max_progress(),
+            };
+        }
+
+        if let Some(best_lap_secs) = self.lap_times_secs().into_iter().reduce(f32::min) {
+            return BotFinalStatus::LapsDone {
+                laps: self.lap_end_times_us.len(),
+                best_lap_secs,
             };
         }
 
@@ -267,10 +432,13 @@ impl ActivityData {
             };
             return BotFinalStatus::OutAt {
                 time_secs: racing_us as f32 / 1_000_000.0,
+                progress: self.max_progress(),
             };
         }
 
-        BotFinalStatus::NotEnded
+        BotFinalStatus::NotEnded {
+            progress: self.max_progress(),
+        }
     }
 }
 
@@ -303,6 +471,23 @@ pub struct MotorDriversDutyCycles {
     pub right: i16,
 }
 
+/// What a motor driver does with its motor when not powering it.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum MotorIdleMode {
+    /// The motor spins freely
+    #[default]
+    Coast,
+    /// The motor terminals are shorted
+    Brake,
+}
+
+/// Motor drivers idle modes.
+#[derive(Clone, Copy, Resource, Default)]
+pub struct MotorDriversIdleModes {
+    pub left: MotorIdleMode,
+    pub right: MotorIdleMode,
+}
+
 /// Motor angles in radians.
 #[derive(Clone, Copy, Resource, Default)]
 pub struct MotorAngles {
@@ -310,20 +495,53 @@ pub struct MotorAngles {
     pub right: f32,
 }
 
+/// Encoder counts since the start.
+#[derive(Clone, Copy, Default)]
+pub struct EncoderTicks {
+    pub left: i32,
+    pub right: i32,
+}
+
 /// Gyroscope data in rad/s.
 #[derive(Clone, Copy, Default)]
 pub struct GyroData {
     pub roll_angular_speed: f32,
     pub pitch_angular_speed: f32,
     pub yaw_angular_speed: f32,
+    /// Largest measurable angular speed
+    pub full_scale: f32,
 }
 
-impl From<Vec3> for GyroData {
-    fn from(value: Vec3) -> Self {
+impl GyroData {
+    /// Builds the data from an angular velocity in the bot frame.
+    pub fn new(value: Vec3, full_scale: f32) -> Self {
         Self {
             roll_angular_speed: value.y,
             pitch_angular_speed: value.x,
             yaw_angular_speed: value.z,
+            full_scale,
+        }
+    }
+}
+
+/// Accelerometer data in m/s², gravity included.
+#[derive(Clone, Copy, Default)]
+pub struct AccelData {
+    pub right: f32,
+    pub forward: f32,
+    pub up: f32,
+    /// Largest measurable acceleration
+    pub full_scale: f32,
+}
+
+impl AccelData {
+    /// Builds the data from an acceleration in the bot frame.
+    pub fn new(value: Vec3, full_scale: f32) -> Self {
+        Self {
+            right: value.x,
+            forward: value.y,
+            up: value.z,
+            full_scale,
         }
     }
 }
@@ -346,6 +564,19 @@ impl From<Vec3> for ImuFusedData {
     }
 }
 
+/// Battery status.
+#[derive(Clone, Copy, Default)]
+pub struct BatteryData {
+    /// Voltage at the battery terminals (in V)
+    pub voltage: f32,
+    /// Current drawn by the motor drivers (in A, negative when regenerating)
+    pub current: f32,
+    /// Charge left (in mAh)
+    pub remaining_charge: f32,
+    /// The voltage fell below the motor drivers brown-out threshold
+    pub is_browned_out: bool,
+}
+
 /// Bot logical positions
 #[derive(Debug, Clone, Copy, Default)]
 pub enum BotPosition {
@@ -381,13 +612,25 @@ impl std::fmt::Display for BotPhysicalPosition {
 #[derive(Clone, Copy, Resource, Default)]
 pub struct SensorsData {
     pub motor_angles: MotorAngles,
+    pub encoder_ticks: EncoderTicks,
     pub gyro: GyroData,
+    pub accel: AccelData,
     pub imu_fused: ImuFusedData,
+    pub battery: BatteryData,
     pub line_sensors: [f32; 16],
     pub bot_position: BotPosition,
     pub bot_physical_position: BotPhysicalPosition,
     pub is_out_of_track: bool,
     pub is_over_track_end: bool,
+    /// Start/finish line crossings on closed loop tracks
+    pub completed_laps: usize,
+    /// Track checkpoints visited in order (on closed loops this keeps
+    /// counting across laps)
+    pub checkpoints_reached: usize,
+    /// Distance covered along the track center line (in meters)
+    pub track_progress: f32,
+    /// The bot is touching an obstacle
+    pub is_hitting_obstacle: bool,
 }
 
 pub trait SimulationStepper {
@@ -435,10 +678,16 @@ pub trait SimulationStepper {
     fn get_line_sensors_right(&self) -> [f32; 8];
     /// Get the current motor angles.
     fn get_motor_angles(&self) -> MotorAngles;
+    /// Get the current encoder counts.
+    fn get_encoder_ticks(&self) -> EncoderTicks;
     /// Get the current gyroscope data.
     fn get_gyro(&self) -> GyroData;
+    /// Get the current accelerometer data.
+    fn get_accel(&self) -> AccelData;
     /// Get the current IMU fused data.
     fn get_imu_fused_data(&self) -> ImuFusedData;
+    /// Get the current battery status.
+    fn get_battery(&self) -> BatteryData;
 
     /// Get absolute bot position
     fn get_absolute_bot_position(&self) -> BotPhysicalPosition;
@@ -446,8 +695,73 @@ pub trait SimulationStepper {
     /// Set motor drivers duty cycles.
     fn set_motor_drivers_duty_cycles(&mut self, duty_cycles: MotorDriversDutyCycles);
 
+    /// Set motor drivers idle modes.
+    fn set_motor_drivers_idle_modes(&mut self, idle_modes: MotorDriversIdleModes);
+
     /// Get the collected execution data.
     fn get_data(&mut self) -> ExecutionData;
 
     fn is_active(&self) -> bool;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_beats_survival_time() {
+        let further = BotFinalStatus::OutAt {
+            time_secs: 2.0,
+            progress: 5.0,
+        };
+        let longer = BotFinalStatus::OutAt {
+            time_secs: 10.0,
+            progress: 3.0,
+        };
+        assert!(further < longer);
+
+        let same_progress_faster = BotFinalStatus::OutAt {
+            time_secs: 1.0,
+            progress: 5.0,
+        };
+        assert!(same_progress_faster < further);
+    }
+
+    #[test]
+    fn final_status_ranking() {
+        let ranking = [
+            BotFinalStatus::EndedAt {
+                time_secs: 10.0,
+                best_lap_secs: None,
+            },
+            BotFinalStatus::EndedAt {
+                time_secs: 12.0,
+                best_lap_secs: None,
+            },
+            BotFinalStatus::LapsDone {
+                laps: 2,
+                best_lap_secs: 5.0,
+            },
+            BotFinalStatus::LapsDone {
+                laps: 1,
+                best_lap_secs: 4.0,
+            },
+            BotFinalStatus::NotEnded { progress: 6.0 },
+            BotFinalStatus::NotEnded { progress: 4.0 },
+            BotFinalStatus::OutAt {
+                time_secs: 3.0,
+                progress: 4.0,
+            },
+            BotFinalStatus::NotEnded { progress: 2.0 },
+            BotFinalStatus::CollidedAt {
+                time_secs: 1.0,
+                progress: 8.0,
+            },
+            BotFinalStatus::NotStarted,
+        ];
+        let mut sorted = ranking;
+        sorted.reverse();
+        sorted.sort();
+        assert!(sorted == ranking);
+    }
+}
@@ -56,11 +56,30 @@ impl execution_data::SimulationStepper for MockStepper {
         }
     }
 
+    fn get_encoder_ticks(&self) -> execution_data::EncoderTicks {
+        // One revolution per second, 1000 counts per revolution
+        execution_data::EncoderTicks {
+            left: (self.time_s() * 1000.0) as i32,
+            right: (self.time_s() * 1000.0) as i32,
+        }
+    }
+
     fn get_gyro(&self) -> execution_data::GyroData {
         execution_data::GyroData {
             roll_angular_speed: 0.0,
             pitch_angular_speed: 0.0,
             yaw_angular_speed: 0.0,
+            full_scale: 500f32.to_radians(),
+        }
+    }
+
+    fn get_accel(&self) -> execution_data::AccelData {
+        // At rest, 1 g upwards
+        execution_data::AccelData {
+            right: 0.0,
+            forward: 0.0,
+            up: 9.81,
+            full_scale: 4.0 * 9.81,
         }
     }
 
@@ -72,6 +91,15 @@ impl execution_data::SimulationStepper for MockStepper {
         }
     }
 
+    fn get_battery(&self) -> execution_data::BatteryData {
+        execution_data::BatteryData {
+            voltage: 6.0,
+            current: 0.0,
+            remaining_charge: 500.0,
+            is_browned_out: false,
+        }
+    }
+
     fn get_absolute_bot_position(&self) -> BotPhysicalPosition {
         BotPhysicalPosition {
             pos: Vec3::ZERO,
@@ -86,6 +114,10 @@ impl execution_data::SimulationStepper for MockStepper {
         // Do nothing
     }
 
+    fn set_motor_drivers_idle_modes(&mut self, _idle_modes: execution_data::MotorDriversIdleModes) {
+        // Do nothing
+    }
+
     fn get_data(&mut self) -> execution_data::ExecutionData {
         execution_data::ExecutionData::empty(self.step_period_us, true)
     }
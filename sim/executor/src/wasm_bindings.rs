@@ -319,7 +319,8 @@ pub mod devices {
         /// ready every period
         #[component(name = "read-motor-angles")]
         ReadMotorAngles,
-        /// Read gyroscope (3 i16 values: roll, pitch and yaw angular velocity),
+        /// Read gyroscope (3 i16 values: roll, pitch and yaw angular velocity
+        /// in deg/s, saturating at the configured full scale),
         /// ready every 2 periods
         #[component(name = "read-gyro")]
         ReadGyro,
@@ -348,6 +349,20 @@ pub mod devices {
         /// Wait until the status of the `enabled` signal is 0
         #[component(name = "wait-disabled")]
         WaitDisabled,
+        /// Read battery status (voltage in mV as u16, current draw in mA as s16,
+        /// negative when the motors charge the battery back, and remaining charge
+        /// in mAh as u16, 65535 for an ideal supply), ready every period
+        #[component(name = "read-battery")]
+        ReadBattery,
+        /// Read the encoder counts of both motors since the start (2 s32 values),
+        /// ready every period
+        #[component(name = "read-encoder-ticks")]
+        ReadEncoderTicks,
+        /// Read accelerometer (3 i16 values: right, forward and up acceleration
+        /// in the robot frame, gravity included, from -32767 to 32767 over the
+        /// configured full scale), ready every 2 periods
+        #[component(name = "read-accel")]
+        ReadAccel,
     }
     const _: () = {
         #[doc(hidden)]
@@ -374,6 +389,9 @@ pub mod devices {
             GetEnabled: [wasmtime::ValRaw; 0],
             WaitEnabled: [wasmtime::ValRaw; 0],
             WaitDisabled: [wasmtime::ValRaw; 0],
+            ReadBattery: [wasmtime::ValRaw; 0],
+            ReadEncoderTicks: [wasmtime::ValRaw; 0],
+            ReadAccel: [wasmtime::ValRaw; 0],
         }
     };
     const _: () = {
@@ -442,6 +460,30 @@ pub mod devices {
             )
         }
     };
+    /// What a motor driver does with its motor when not powering it
+    #[derive(Debug, ComponentType, Lower, Lift, Clone, Copy, Eq, PartialEq)]
+    #[component(enum)]
+    #[repr(u8)]
+    pub enum MotorIdleMode {
+        /// Leave the motor terminals floating (the motor spins freely)
+        #[component(name = "coast")]
+        Coast,
+        /// Short the motor terminals (the motor brakes)
+        #[component(name = "brake")]
+        Brake,
+    }
+    const _: () = {
+        if !(1 == <MotorIdleMode as wasmtime::component::ComponentType>::SIZE32) {
+            panic!(
+                "assertion failed: 1 == <MotorIdleMode as wasmtime::component::ComponentType>::SIZE32",
+            )
+        }
+        if !(1 == <MotorIdleMode as wasmtime::component::ComponentType>::ALIGN32) {
+            panic!(
+                "assertion failed: 1 == <MotorIdleMode as wasmtime::component::ComponentType>::ALIGN32",
+            )
+        }
+    };
     pub trait HostWithStore: wasmtime::component::HasData {}
     impl<_T: ?Sized> HostWithStore for _T where _T: wasmtime::component::HasData {}
     pub trait Host: Sized {
@@ -481,6 +523,14 @@ pub mod devices {
             left: MotorPower,
             right: MotorPower,
         ) -> wasmtime::Result<()>;
+        /// Set the idle mode of both motor drivers, used at zero power and
+        /// in the off phase of the PWM cycle
+        fn set_motors_idle_mode(
+            &mut self,
+            current_fuel: u64,
+            left: MotorIdleMode,
+            right: MotorIdleMode,
+        ) -> wasmtime::Result<()>;
     }
     impl<_T: Host + ?Sized> Host for &mut _T {
         /// Perform a device operation (returns immediately the current value if possible, not for sleep or wait operations)
@@ -533,6 +583,16 @@ pub mod devices {
         ) -> wasmtime::Result<()> {
             Host::set_motors_power(*self, current_fuel, left, right)
         }
+        /// Set the idle mode of both motor drivers, used at zero power and
+        /// in the off phase of the PWM cycle
+        fn set_motors_idle_mode(
+            &mut self,
+            current_fuel: u64,
+            left: MotorIdleMode,
+            right: MotorIdleMode,
+        ) -> wasmtime::Result<()> {
+            Host::set_motors_idle_mode(*self, current_fuel, left, right)
+        }
     }
     pub fn add_to_linker<T, D>(
         linker: &mut wasmtime::component::Linker<T>,
@@ -607,6 +667,16 @@ pub mod devices {
                 Ok(r)
             },
         )?;
+        inst.func_wrap(
+            "set-motors-idle-mode",
+            move |mut caller: wasmtime::StoreContextMut<'_, T>,
+                  (arg0, arg1): (MotorIdleMode, MotorIdleMode)| {
+                let current_fuel = caller.get_fuel()?;
+                let host = &mut host_getter(caller.data_mut());
+                let r = Host::set_motors_idle_mode(host, current_fuel, arg0, arg1)?;
+                Ok(r)
+            },
+        )?;
         Ok(())
     }
 }
@@ -874,6 +944,244 @@ pub mod exports {
                 )
             }
         };
+        /// Parameters of a DC motor, at its shaft (before the transmission gears)
+        #[derive(Debug, Clone, Copy, ComponentType, Lower, Lift)]
+        #[component(record)]
+        pub struct MotorParameters {
+            /// Shaft speed with no load at full power (in RPM, 1000 to 100000)
+            #[component(name = "no-load-rpm")]
+            pub no_load_rpm: f32,
+            /// Shaft torque when stalled at full power (in mN·m, 0.1 to 100)
+            #[component(name = "stall-torque")]
+            pub stall_torque: f32,
+            /// Voltage of the speed and torque ratings (in V, 1 to 24)
+            #[component(name = "rated-voltage")]
+            pub rated_voltage: f32,
+            /// Current drawn when stalled at the rated voltage (in A, 0.05 to 10)
+            #[component(name = "stall-current")]
+            pub stall_current: f32,
+        }
+        const _: () = {
+            #[doc(hidden)]
+            #[repr(C)]
+            #[derive(Clone, Copy)]
+            pub struct LowerMotorParameters<T0: Copy, T1: Copy, T2: Copy, T3: Copy> {
+                no_load_rpm: T0,
+                stall_torque: T1,
+                rated_voltage: T2,
+                stall_current: T3,
+                _align: [wasmtime::ValRaw; 0],
+            }
+        };
+        const _: () = {
+            if !(16 == <MotorParameters as wasmtime::component::ComponentType>::SIZE32) {
+                panic!(
+                    "assertion failed: 16 == <MotorParameters as wasmtime::component::ComponentType>::SIZE32",
+                )
+            }
+            if !(4 == <MotorParameters as wasmtime::component::ComponentType>::ALIGN32) {
+                panic!(
+                    "assertion failed: 4 == <MotorParameters as wasmtime::component::ComponentType>::ALIGN32",
+                )
+            }
+        };
+        /// The DC motors driving the wheels
+        #[derive(Debug, ComponentType, Lower, Lift, Clone)]
+        #[component(variant)]
+        pub enum Motor {
+            /// A motor from the simulator catalogue, by name (like "pololu-hp-6v")
+            #[component(name = "catalogue")]
+            Catalogue(wasmtime::component::__internal::String),
+            /// A motor with custom parameters
+            #[component(name = "custom")]
+            Custom(MotorParameters),
+        }
+        const _: () = {
+            #[doc(hidden)]
+            #[repr(C)]
+            #[derive(Clone, Copy)]
+            pub struct LowerMotor<T0: Copy, T1: Copy> {
+                tag: wasmtime::ValRaw,
+                payload: LowerPayloadMotor<T0, T1>,
+            }
+            #[doc(hidden)]
+            #[allow(non_snake_case)]
+            #[repr(C)]
+            #[derive(Clone, Copy)]
+            union LowerPayloadMotor<T0: Copy, T1: Copy> {
+                Catalogue: T0,
+                Custom: T1,
+            }
+        };
+        const _: () = {
+            if !(20 == <Motor as wasmtime::component::ComponentType>::SIZE32) {
+                panic!(
+                    "assertion failed: 20 == <Motor as wasmtime::component::ComponentType>::SIZE32",
+                )
+            }
+            if !(4 == <Motor as wasmtime::component::ComponentType>::ALIGN32) {
+                panic!(
+                    "assertion failed: 4 == <Motor as wasmtime::component::ComponentType>::ALIGN32",
+                )
+            }
+        };
+        /// The battery powering the motors
+        #[derive(Debug, Clone, Copy, ComponentType, Lower, Lift)]
+        #[component(record)]
+        pub struct BatteryParameters {
+            /// Nominal voltage (in V, 3 to 15)
+            #[component(name = "voltage")]
+            pub voltage: f32,
+            /// Internal resistance (in Ω, 0.01 to 2)
+            #[component(name = "internal-resistance")]
+            pub internal_resistance: f32,
+            /// Charge when full (in mAh, 50 to 5000)
+            #[component(name = "capacity")]
+            pub capacity: f32,
+        }
+        const _: () = {
+            #[doc(hidden)]
+            #[repr(C)]
+            #[derive(Clone, Copy)]
+            pub struct LowerBatteryParameters<T0: Copy, T1: Copy, T2: Copy> {
+                voltage: T0,
+                internal_resistance: T1,
+                capacity: T2,
+                _align: [wasmtime::ValRaw; 0],
+            }
+        };
+        const _: () = {
+            if !(12 == <BatteryParameters as wasmtime::component::ComponentType>::SIZE32) {
+                panic!(
+                    "assertion failed: 12 == <BatteryParameters as wasmtime::component::ComponentType>::SIZE32",
+                )
+            }
+            if !(4 == <BatteryParameters as wasmtime::component::ComponentType>::ALIGN32) {
+                panic!(
+                    "assertion failed: 4 == <BatteryParameters as wasmtime::component::ComponentType>::ALIGN32",
+                )
+            }
+        };
+        /// The H-bridge drivers of the motors
+        #[derive(Debug, Clone, Copy, ComponentType, Lower, Lift)]
+        #[component(record)]
+        pub struct MotorDriver {
+            /// Smallest power moving the motors (in PWM units, 0 to 200), lower ones act as zero
+            #[component(name = "dead-band")]
+            pub dead_band: u16,
+            /// Largest power change (in PWM units per millisecond, 1 to 2000), 0 for no limit
+            #[component(name = "slew-rate")]
+            pub slew_rate: u16,
+            /// Brake instead of coasting when idle (until the robot sets the idle mode)
+            #[component(name = "brake-when-idle")]
+            pub brake_when_idle: bool,
+        }
+        const _: () = {
+            #[doc(hidden)]
+            #[repr(C)]
+            #[derive(Clone, Copy)]
+            pub struct LowerMotorDriver<T0: Copy, T1: Copy, T2: Copy> {
+                dead_band: T0,
+                slew_rate: T1,
+                brake_when_idle: T2,
+                _align: [wasmtime::ValRaw; 0],
+            }
+        };
+        const _: () = {
+            if !(6 == <MotorDriver as wasmtime::component::ComponentType>::SIZE32) {
+                panic!(
+                    "assertion failed: 6 == <MotorDriver as wasmtime::component::ComponentType>::SIZE32",
+                )
+            }
+            if !(2 == <MotorDriver as wasmtime::component::ComponentType>::ALIGN32) {
+                panic!(
+                    "assertion failed: 2 == <MotorDriver as wasmtime::component::ComponentType>::ALIGN32",
+                )
+            }
+        };
+        /// The rotary encoders measuring the motors
+        #[derive(Debug, Clone, Copy, ComponentType, Lower, Lift)]
+        #[component(record)]
+        pub struct Encoders {
+            /// Counts per revolution of the measured shaft (1 to 10000)
+            #[component(name = "counts-per-revolution")]
+            pub counts_per_revolution: u32,
+            /// Measure the motor shaft (before the gearbox) instead of the wheel
+            #[component(name = "motor-side")]
+            pub motor_side: bool,
+            /// Jitter of the readings (standard deviation in counts, 0 to 5)
+            #[component(name = "jitter")]
+            pub jitter: f32,
+        }
+        const _: () = {
+            #[doc(hidden)]
+            #[repr(C)]
+            #[derive(Clone, Copy)]
+            pub struct LowerEncoders<T0: Copy, T1: Copy, T2: Copy> {
+                counts_per_revolution: T0,
+                motor_side: T1,
+                jitter: T2,
+                _align: [wasmtime::ValRaw; 0],
+            }
+        };
+        const _: () = {
+            if !(12 == <Encoders as wasmtime::component::ComponentType>::SIZE32) {
+                panic!(
+                    "assertion failed: 12 == <Encoders as wasmtime::component::ComponentType>::SIZE32",
+                )
+            }
+            if !(4 == <Encoders as wasmtime::component::ComponentType>::ALIGN32) {
+                panic!(
+                    "assertion failed: 4 == <Encoders as wasmtime::component::ComponentType>::ALIGN32",
+                )
+            }
+        };
+        /// Range and error model of an inertial sensor, in deg/s for the gyroscope
+        /// and in g for the accelerometer
+        #[derive(Debug, Clone, Copy, ComponentType, Lower, Lift)]
+        #[component(record)]
+        pub struct InertialSensor {
+            /// Largest measurable value, readings saturate beyond it
+            /// (gyroscope 125 to 4000, accelerometer 2 to 32)
+            #[component(name = "full-scale")]
+            pub full_scale: f32,
+            /// Standard deviation of the constant offset of each axis, drawn at power on
+            /// (gyroscope 0 to 20, accelerometer 0 to 0.5)
+            #[component(name = "bias")]
+            pub bias: f32,
+            /// Random walk of the offsets, per square root of second
+            /// (gyroscope 0 to 1, accelerometer 0 to 0.05)
+            #[component(name = "drift")]
+            pub drift: f32,
+            /// Standard deviation of the white noise of each reading
+            /// (gyroscope 0 to 5, accelerometer 0 to 0.5)
+            #[component(name = "noise")]
+            pub noise: f32,
+        }
+        const _: () = {
+            #[doc(hidden)]
+            #[repr(C)]
+            #[derive(Clone, Copy)]
+            pub struct LowerInertialSensor<T0: Copy, T1: Copy, T2: Copy, T3: Copy> {
+                full_scale: T0,
+                bias: T1,
+                drift: T2,
+                noise: T3,
+                _align: [wasmtime::ValRaw; 0],
+            }
+        };
+        const _: () = {
+            if !(16 == <InertialSensor as wasmtime::component::ComponentType>::SIZE32) {
+                panic!(
+                    "assertion failed: 16 == <InertialSensor as wasmtime::component::ComponentType>::SIZE32",
+                )
+            }
+            if !(4 == <InertialSensor as wasmtime::component::ComponentType>::ALIGN32) {
+                panic!(
+                    "assertion failed: 4 == <InertialSensor as wasmtime::component::ComponentType>::ALIGN32",
+                )
+            }
+        };
         /// The appearence and build configuration of a robot
         #[derive(Debug, ComponentType, Lower, Lift, Clone)]
         #[component(record)]
@@ -908,6 +1216,24 @@ pub mod exports {
             /// Transmission gear ratio denumerator (from 1 to 100)
             #[component(name = "gear-ratio-den")]
             pub gear_ratio_den: u32,
+            /// Motors driving the wheels through the transmission
+            #[component(name = "motor")]
+            pub motor: Motor,
+            /// Battery powering the motors (none for an ideal supply at the motor rated voltage)
+            #[component(name = "battery")]
+            pub battery: Option<BatteryParameters>,
+            /// Drivers of the motors
+            #[component(name = "driver")]
+            pub driver: MotorDriver,
+            /// Encoders of the motors
+            #[component(name = "encoders")]
+            pub encoders: Encoders,
+            /// Gyroscope of the IMU
+            #[component(name = "gyro")]
+            pub gyro: InertialSensor,
+            /// Accelerometer of the IMU
+            #[component(name = "accel")]
+            pub accel: InertialSensor,
             /// Spacing of line sensors (in mm, from 1 to 15)
             #[component(name = "front-sensors-spacing")]
             pub front_sensors_spacing: f32,
@@ -932,6 +1258,12 @@ pub mod exports {
                 T9: Copy,
                 T10: Copy,
                 T11: Copy,
+                T12: Copy,
+                T13: Copy,
+                T14: Copy,
+                T15: Copy,
+                T16: Copy,
+                T17: Copy,
             > {
                 name: T0,
                 color_main: T1,
@@ -943,15 +1275,21 @@ pub mod exports {
                 wheel_diameter: T7,
                 gear_ratio_num: T8,
                 gear_ratio_den: T9,
-                front_sensors_spacing: T10,
-                front_sensors_height: T11,
+                motor: T10,
+                battery: T11,
+                driver: T12,
+                encoders: T13,
+                gyro: T14,
+                accel: T15,
+                front_sensors_spacing: T16,
+                front_sensors_height: T17,
                 _align: [wasmtime::ValRaw; 0],
             }
         };
         const _: () = {
-            if !(52 == <Configuration as wasmtime::component::ComponentType>::SIZE32) {
+            if !(140 == <Configuration as wasmtime::component::ComponentType>::SIZE32) {
                 panic!(
-                    "assertion failed: 52 == <Configuration as wasmtime::component::ComponentType>::SIZE32",
+                    "assertion failed: 140 == <Configuration as wasmtime::component::ComponentType>::SIZE32",
                 )
             }
             if !(4 == <Configuration as wasmtime::component::ComponentType>::ALIGN32) {
@@ -6,13 +6,15 @@ use std::{
 };
 
 use execution_data::{
-    ExecutionData, GyroData, ImuFusedData, MotorAngles, MotorDriversDutyCycles, SimulationStepper,
+    AccelData, BatteryData, EncoderTicks, ExecutionData, GyroData, ImuFusedData, MotorAngles,
+    MotorDriversDutyCycles, MotorDriversIdleModes, SimulationStepper,
 };
 
 use crate::wasm_bindings::{
     self,
     devices::{
-        DeviceOperation, DeviceValue, FutureHandle, MotorPower, PollOperationStatus, TimeUs,
+        DeviceOperation, DeviceValue, FutureHandle, MotorIdleMode, MotorPower, PollOperationStatus,
+        TimeUs,
     },
     diagnostics::CsvColumn,
 };
@@ -279,13 +281,25 @@ impl DeviceValueExt for DeviceValue {
     }
 }
 
+impl From<MotorIdleMode> for execution_data::MotorIdleMode {
+    fn from(value: MotorIdleMode) -> Self {
+        match value {
+            MotorIdleMode::Coast => execution_data::MotorIdleMode::Coast,
+            MotorIdleMode::Brake => execution_data::MotorIdleMode::Brake,
+        }
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum FutureOperation {
     ReadLineLeft,
     ReadLineRight,
     ReadMotorAngles,
+    ReadEncoderTicks,
     ReadGyro,
+    ReadAccel,
     ReadImuFusedData,
+    ReadBattery,
     GetTime,
     GetPeriod,
     Sleep,
@@ -300,8 +314,11 @@ impl From<DeviceOperation> for FutureOperation {
             DeviceOperation::ReadLineLeft => FutureOperation::ReadLineLeft,
             DeviceOperation::ReadLineRight => FutureOperation::ReadLineRight,
             DeviceOperation::ReadMotorAngles => FutureOperation::ReadMotorAngles,
+            DeviceOperation::ReadEncoderTicks => FutureOperation::ReadEncoderTicks,
             DeviceOperation::ReadGyro => FutureOperation::ReadGyro,
+            DeviceOperation::ReadAccel => FutureOperation::ReadAccel,
             DeviceOperation::ReadImuFusedData => FutureOperation::ReadImuFusedData,
+            DeviceOperation::ReadBattery => FutureOperation::ReadBattery,
             DeviceOperation::GetTime => FutureOperation::GetTime,
             DeviceOperation::GetPeriod => FutureOperation::GetPeriod,
             DeviceOperation::SleepFor(_) => FutureOperation::Sleep,
@@ -319,8 +336,11 @@ impl FutureOperation {
             FutureOperation::ReadLineLeft => "ReadLineLeft",
             FutureOperation::ReadLineRight => "ReadLineRight",
             FutureOperation::ReadMotorAngles => "ReadMotorAngles",
+            FutureOperation::ReadEncoderTicks => "ReadEncoderTicks",
             FutureOperation::ReadGyro => "ReadGyro",
+            FutureOperation::ReadAccel => "ReadAccel",
             FutureOperation::ReadImuFusedData => "ReadImuFusedData",
+            FutureOperation::ReadBattery => "ReadBattery",
             FutureOperation::GetTime => "GetTime",
             FutureOperation::GetPeriod => "GetPeriod",
             FutureOperation::Sleep => "Sleep",
@@ -346,10 +366,17 @@ impl FutureOperation {
             FutureOperation::ReadMotorAngles => {
                 DeviceValueRaw::from_motor_angles(stepper.get_motor_angles())
             }
+            FutureOperation::ReadEncoderTicks => {
+                DeviceValueRaw::from_encoder_ticks(stepper.get_encoder_ticks())
+            }
             FutureOperation::ReadGyro => DeviceValueRaw::from_gyro_data(stepped_data.gyro_data),
+            FutureOperation::ReadAccel => DeviceValueRaw::from_accel_data(stepped_data.accel_data),
             FutureOperation::ReadImuFusedData => {
                 DeviceValueRaw::from_imu_fused_data(stepped_data.imu_fused_data)
             }
+            FutureOperation::ReadBattery => {
+                DeviceValueRaw::from_battery_data(stepper.get_battery())
+            }
             FutureOperation::GetTime => DeviceValueRaw::zero().with_u32(0, current_time),
             FutureOperation::GetPeriod => DeviceValueRaw::zero()
                 .with_u32(0, stepper.step_us())
@@ -486,11 +513,40 @@ impl DeviceValueRaw {
             .with_u16(1, (angles.right * (u16::MAX as f32) / (PI * 2.0)) as u16)
     }
 
+    pub fn from_encoder_ticks(ticks: EncoderTicks) -> Self {
+        Self::zero()
+            .with_u32(0, ticks.left as u32)
+            .with_u32(1, ticks.right as u32)
+    }
+
+    /// Scales a reading like a sensor register, with `i16::MAX` at full scale
+    fn full_scale_value(value: f32, full_scale: f32) -> i16 {
+        if full_scale > 0.0 {
+            (value / full_scale * i16::MAX as f32)
+                .round()
+                .clamp(-i16::MAX as f32, i16::MAX as f32) as i16
+        } else {
+            0
+        }
+    }
+
     pub fn from_gyro_data(gyro_data: GyroData) -> Self {
+        // Read in deg/s (unlike the accelerometer), the full scale only
+        // saturates the readings
+        let full_scale = gyro_data.full_scale.to_degrees();
+        let degrees = |value: f32| value.to_degrees().clamp(-full_scale, full_scale) as i16;
         Self::zero()
-            .with_i16(0, gyro_data.roll_angular_speed as i16)
-            .with_i16(1, gyro_data.pitch_angular_speed as i16)
-            .with_i16(2, gyro_data.yaw_angular_speed as i16)
+            .with_i16(0, degrees(gyro_data.roll_angular_speed))
+            .with_i16(1, degrees(gyro_data.pitch_angular_speed))
+            .with_i16(2, degrees(gyro_data.yaw_angular_speed))
+    }
+
+    pub fn from_accel_data(accel_data: AccelData) -> Self {
+        let scale = |value| Self::full_scale_value(value, accel_data.full_scale);
+        Self::zero()
+            .with_i16(0, scale(accel_data.right))
+            .with_i16(1, scale(accel_data.forward))
+            .with_i16(2, scale(accel_data.up))
     }
 
     pub fn from_imu_fused_data(imu_data: ImuFusedData) -> Self {
@@ -499,6 +555,13 @@ impl DeviceValueRaw {
             .with_i16(1, imu_data.pitch.to_radians() as i16)
             .with_i16(2, imu_data.yaw.to_radians() as i16)
     }
+
+    pub fn from_battery_data(battery_data: BatteryData) -> Self {
+        Self::zero()
+            .with_u16(0, (battery_data.voltage * 1000.0) as u16)
+            .with_i16(1, (battery_data.current * 1000.0) as i16)
+            .with_u16(2, battery_data.remaining_charge as u16)
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -602,6 +665,7 @@ impl Ord for FutureValueReadyTime {
 }
 
 const READY_STEPS_GYRO: u32 = 2;
+const READY_STEPS_ACCEL: u32 = 2;
 const READY_STEPS_IMU_FUSED: u32 = 10;
 
 pub trait DeviceOperationExt {
@@ -624,8 +688,11 @@ impl DeviceOperationExt for DeviceOperation {
             DeviceOperation::ReadLineLeft
             | DeviceOperation::ReadLineRight
             | DeviceOperation::ReadMotorAngles
+            | DeviceOperation::ReadEncoderTicks
             | DeviceOperation::ReadGyro
-            | DeviceOperation::ReadImuFusedData => {
+            | DeviceOperation::ReadAccel
+            | DeviceOperation::ReadImuFusedData
+            | DeviceOperation::ReadBattery => {
                 let step_time = stepper.step_us();
                 let stray_time = current_time % step_time;
                 let trigger_time = if stray_time == 0 {
@@ -654,6 +721,8 @@ impl DeviceOperationExt for DeviceOperation {
             DeviceOperation::ReadLineLeft
             | DeviceOperation::ReadLineRight
             | DeviceOperation::ReadMotorAngles
+            | DeviceOperation::ReadEncoderTicks
+            | DeviceOperation::ReadBattery
             | DeviceOperation::GetTime
             | DeviceOperation::GetPeriod
             | DeviceOperation::GetEnabled
@@ -662,6 +731,7 @@ impl DeviceOperationExt for DeviceOperation {
             | DeviceOperation::SleepFor(_)
             | DeviceOperation::SleepUntil(_) => 1,
             DeviceOperation::ReadGyro => READY_STEPS_GYRO,
+            DeviceOperation::ReadAccel => READY_STEPS_ACCEL,
             DeviceOperation::ReadImuFusedData => READY_STEPS_IMU_FUSED,
         }
     }
@@ -671,8 +741,11 @@ impl DeviceOperationExt for DeviceOperation {
             DeviceOperation::ReadLineLeft
             | DeviceOperation::ReadLineRight
             | DeviceOperation::ReadMotorAngles
+            | DeviceOperation::ReadEncoderTicks
             | DeviceOperation::ReadGyro
+            | DeviceOperation::ReadAccel
             | DeviceOperation::ReadImuFusedData
+            | DeviceOperation::ReadBattery
             | DeviceOperation::GetTime
             | DeviceOperation::GetPeriod
             | DeviceOperation::GetEnabled
@@ -744,6 +817,7 @@ impl WakeupPoint {
 #[derive(Clone, Copy, Default)]
 pub struct SteppedData {
     pub gyro_data: GyroData,
+    pub accel_data: AccelData,
     pub imu_fused_data: ImuFusedData,
 }
 
@@ -778,8 +852,11 @@ impl<S: SimulationStepper> wasm_bindings::devices::Host for BotHost<S> {
             DeviceOperation::ReadLineLeft
             | DeviceOperation::ReadLineRight
             | DeviceOperation::ReadMotorAngles
+            | DeviceOperation::ReadEncoderTicks
             | DeviceOperation::ReadGyro
+            | DeviceOperation::ReadAccel
             | DeviceOperation::ReadImuFusedData
+            | DeviceOperation::ReadBattery
             | DeviceOperation::GetTime
             | DeviceOperation::GetPeriod
             | DeviceOperation::GetEnabled => {
@@ -933,6 +1010,24 @@ impl<S: SimulationStepper> wasm_bindings::devices::Host for BotHost<S> {
             .set_motor_drivers_duty_cycles(MotorDriversDutyCycles { left, right });
         Ok(())
     }
+
+    #[doc = " Set the idle mode of both motor drivers, used at zero power and"]
+    #[doc = " in the off phase of the PWM cycle"]
+    fn set_motors_idle_mode(
+        &mut self,
+        current_fuel: u64,
+        left: MotorIdleMode,
+        right: MotorIdleMode,
+    ) -> wasmtime::Result<()> {
+        let current_time = self.setup_current_time(current_fuel)?;
+        self.step_until_time(current_time);
+        self.stepper
+            .set_motor_drivers_idle_modes(MotorDriversIdleModes {
+                left: left.into(),
+                right: right.into(),
+            });
+        Ok(())
+    }
 }
 
 impl<S: SimulationStepper> wasm_bindings::diagnostics::Host for BotHost<S> {
@@ -1306,6 +1401,9 @@ impl<S: SimulationStepper> BotHost<S> {
         if steps % READY_STEPS_GYRO == 0 {
             self.stepped_data.gyro_data = self.stepper.get_gyro();
         }
+        if steps % READY_STEPS_ACCEL == 0 {
+            self.stepped_data.accel_data = self.stepper.get_accel();
+        }
         if steps % READY_STEPS_IMU_FUSED == 0 {
             self.stepped_data.imu_fused_data = self.stepper.get_imu_fused_data();
         }
@@ -1333,3 +1431,54 @@ impl<S: SimulationStepper> BotHost<S> {
         self.stepper.get_data()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn i16_at(value: DeviceValueRaw, index: usize) -> i16 {
+        let bytes = [
+            value.v0, value.v1, value.v2, value.v3, value.v4, value.v5, value.v6, value.v7,
+        ];
+        i16::from_le_bytes([bytes[index * 2], bytes[index * 2 + 1]])
+    }
+
+    #[test]
+    fn full_scale_register_values() {
+        assert_eq!(DeviceValueRaw::full_scale_value(0.0, 4.0), 0);
+        assert_eq!(DeviceValueRaw::full_scale_value(4.0, 4.0), i16::MAX);
+        assert_eq!(DeviceValueRaw::full_scale_value(-4.0, 4.0), -i16::MAX);
+        assert_eq!(DeviceValueRaw::full_scale_value(1.0, 4.0), 8192);
+        // Saturated readings
+        assert_eq!(DeviceValueRaw::full_scale_value(10.0, 4.0), i16::MAX);
+        assert_eq!(DeviceValueRaw::full_scale_value(-10.0, 4.0), -i16::MAX);
+    }
+
+    #[test]
+    fn accel_one_g_up() {
+        let g = 9.80665;
+        let value = DeviceValueRaw::from_accel_data(AccelData {
+            right: 0.0,
+            forward: 0.0,
+            up: g,
+            full_scale: 2.0 * g,
+        });
+        assert_eq!(i16_at(value, 0), 0);
+        assert_eq!(i16_at(value, 1), 0);
+        assert_eq!(i16_at(value, 2), 16384);
+    }
+
+    #[test]
+    fn gyro_in_degrees_per_second() {
+        let value = DeviceValueRaw::from_gyro_data(GyroData {
+            roll_angular_speed: 90f32.to_radians(),
+            pitch_angular_speed: -45f32.to_radians(),
+            // Beyond the full scale
+            yaw_angular_speed: 1000f32.to_radians(),
+            full_scale: 250f32.to_radians(),
+        });
+        assert_eq!(i16_at(value, 0), 90);
+        assert_eq!(i16_at(value, 1), -45);
+        assert_eq!(i16_at(value, 2), 250);
+    }
+}
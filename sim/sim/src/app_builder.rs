@@ -37,7 +37,7 @@ pub enum VisualizerData {
         start_time: u32,
     },
     Runner {
-        bot: BotExecutionData,
+        bot: Box<BotExecutionData>,
         output: String,
         logs: bool,
         total_simulation_time_us: u32,
@@ -91,7 +91,7 @@ impl VisualizerData {
     pub fn first_bot(&self) -> Option<BotExecutionData> {
         match self {
             VisualizerData::Server { .. } => None,
-            VisualizerData::Runner { bot, .. } => Some(bot.clone()),
+            VisualizerData::Runner { bot, .. } => Some(bot.as_ref().clone()),
         }
     }
 
@@ -107,6 +107,8 @@ pub enum AppType {
     Simulator(Configuration),
     Test(Configuration),
     Visualizer(VisualizerData),
+    /// Track editor, saving to the given track file
+    Editor(String),
 }
 
 impl std::fmt::Display for AppType {
@@ -115,6 +117,7 @@ impl std::fmt::Display for AppType {
             AppType::Simulator(_) => write!(f, "Simulator"),
             AppType::Test(_) => write!(f, "Test"),
             AppType::Visualizer(_) => write!(f, "Visualizer"),
+            AppType::Editor(_) => write!(f, "Editor"),
         }
     }
 }
@@ -125,6 +128,7 @@ impl AppType {
             AppType::Simulator(_) => EntityFeatures::Physics,
             AppType::Test(_) => EntityFeatures::PhysicsAndVisualization,
             AppType::Visualizer(_) => EntityFeatures::Visualization,
+            AppType::Editor(_) => EntityFeatures::Visualization,
         }
     }
 
@@ -141,6 +145,7 @@ impl AppType {
             AppType::Simulator(config) => Some(config.clone()),
             AppType::Test(config) => Some(config.clone()),
             AppType::Visualizer(_) => None,
+            AppType::Editor(_) => None,
         }
     }
 
@@ -149,6 +154,7 @@ impl AppType {
             AppType::Simulator(config) => (Some(config.clone()), None),
             AppType::Test(config) => (Some(config.clone()), None),
             AppType::Visualizer(data) => (None, Some(data.clone())),
+            AppType::Editor(_) => (None, None),
         }
     }
 }
@@ -0,0 +1,230 @@
+use bevy::prelude::*;
+use execution_data::BatteryData;
+use executor::wasm_bindings::exports::robot::BatteryParameters;
+use executor::wasmtime;
+
+use super::motors::MotorLoad;
+
+const VOLTAGE_RANGE: std::ops::RangeInclusive<f32> = 3.0..=15.0;
+const INTERNAL_RESISTANCE_RANGE: std::ops::RangeInclusive<f32> = 0.01..=2.0;
+const CAPACITY_RANGE: std::ops::RangeInclusive<f32> = 50.0..=5000.0;
+
+/// Open circuit voltage of a full and of an empty battery, relative to the
+/// nominal one (roughly the discharge curve of a LiPo cell)
+const FULL_VOLTAGE_RATIO: f32 = 1.12;
+const EMPTY_VOLTAGE_RATIO: f32 = 0.9;
+/// Below this voltage, relative to the nominal one, the motor drivers shut
+/// down until the battery recovers
+const BROWN_OUT_RATIO: f32 = 0.6;
+
+const MAH_TO_COULOMBS: f32 = 3.6;
+
+/// Checks that the battery of a configuration (if any) is in sensible ranges
+pub fn validate_battery(battery: &Option<BatteryParameters>) -> wasmtime::Result<()> {
+    let Some(battery) = battery else {
+        return Ok(());
+    };
+    for (name, value, range, unit) in [
+        ("voltage", battery.voltage, VOLTAGE_RANGE, "V"),
+        (
+            "internal resistance",
+            battery.internal_resistance,
+            INTERNAL_RESISTANCE_RANGE,
+            "Ω",
+        ),
+        ("capacity", battery.capacity, CAPACITY_RANGE, "mAh"),
+    ] {
+        if !range.contains(&value) {
+            return Err(wasmtime::Error::msg(format!(
+                "battery {} {} {} out of range ({} to {})",
+                name,
+                value,
+                unit,
+                range.start(),
+                range.end()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// The battery powering the motor drivers: an open circuit voltage that
+/// falls as the charge is used, behind an internal resistance (without
+/// parameters, an ideal supply that never sags nor runs out)
+#[derive(Component)]
+pub struct Battery {
+    parameters: Option<BatteryParameters>,
+    /// Voltage of the ideal supply (the motor rated voltage)
+    ideal_voltage: f32,
+    /// Charge drawn so far (in coulombs)
+    used_charge: f32,
+    voltage: f32,
+    current: f32,
+    is_browned_out: bool,
+}
+
+impl Battery {
+    pub fn new(parameters: Option<BatteryParameters>, ideal_voltage: f32) -> Self {
+        let mut battery = Self {
+            parameters,
+            ideal_voltage,
+            used_charge: 0.0,
+            voltage: 0.0,
+            current: 0.0,
+            is_browned_out: false,
+        };
+        battery.voltage = battery.open_circuit_voltage();
+        battery
+    }
+
+    fn capacity_coulombs(&self) -> f32 {
+        self.parameters.map_or(f32::INFINITY, |parameters| {
+            parameters.capacity * MAH_TO_COULOMBS
+        })
+    }
+
+    fn open_circuit_voltage(&self) -> f32 {
+        let Some(parameters) = self.parameters else {
+            return self.ideal_voltage;
+        };
+        let depth_of_discharge = (self.used_charge / self.capacity_coulombs()).clamp(0.0, 1.0);
+        parameters.voltage
+            * (FULL_VOLTAGE_RATIO - (FULL_VOLTAGE_RATIO - EMPTY_VOLTAGE_RATIO) * depth_of_discharge)
+    }
+
+    /// Voltage at the terminals with the motors loading the battery (`None`
+    /// when it falls below the brown-out threshold)
+    pub fn terminal_voltage(&self, loads: &[MotorLoad]) -> Option<f32> {
+        let open_circuit = self.open_circuit_voltage();
+        let (nominal_voltage, resistance) = self
+            .parameters
+            .map_or((self.ideal_voltage, 0.0), |parameters| {
+                (parameters.voltage, parameters.internal_resistance)
+            });
+
+        // The motor currents are linear in the supply voltage, so the
+        // terminal voltage solves V = Voc - R * sum(drive * I(V)) directly
+        // (motors blocked by their drivers draw nothing)
+        let (conductance, emf_current) = loads
+            .iter()
+            .filter(|load| load.drive > 0.0 && load.current(open_circuit) != 0.0)
+            .fold((0.0, 0.0), |(conductance, emf_current), load| {
+                (
+                    conductance + load.drive * load.drive / load.resistance,
+                    emf_current + load.sign * load.drive * load.back_emf / load.resistance,
+                )
+            });
+        let voltage = (open_circuit + resistance * emf_current) / (1.0 + resistance * conductance);
+        (voltage >= nominal_voltage * BROWN_OUT_RATIO).then_some(voltage)
+    }
+
+    /// Powers the motors for a time step, returning the voltage the
+    /// drivers switch to the motors (`None` when browned out)
+    pub fn supply(&mut self, loads: &[MotorLoad], dt: f32) -> Option<f32> {
+        let voltage = self.terminal_voltage(loads);
+        self.is_browned_out = voltage.is_none();
+        let Some(voltage) = voltage else {
+            self.voltage = self.open_circuit_voltage();
+            self.current = 0.0;
+            return None;
+        };
+        let current: f32 = loads.iter().map(|load| load.supply_current(voltage)).sum();
+
+        self.voltage = voltage;
+        self.current = current;
+        self.used_charge = (self.used_charge + current * dt).max(0.0);
+        Some(voltage)
+    }
+
+    pub fn data(&self) -> BatteryData {
+        BatteryData {
+            voltage: self.voltage,
+            current: self.current,
+            remaining_charge: (self.capacity_coulombs() - self.used_charge).max(0.0)
+                / MAH_TO_COULOMBS,
+            is_browned_out: self.is_browned_out,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use execution_data::MotorIdleMode;
+    use executor::wasm_bindings::exports::robot::MotorParameters;
+
+    use super::*;
+
+    const MOTOR: MotorParameters = MotorParameters {
+        no_load_rpm: 31000.0,
+        stall_torque: 2.0,
+        rated_voltage: 6.0,
+        stall_current: 1.5,
+    };
+
+    fn battery(internal_resistance: f32) -> Battery {
+        Battery::new(
+            Some(BatteryParameters {
+                voltage: 6.0,
+                internal_resistance,
+                capacity: 1000.0,
+            }),
+            MOTOR.rated_voltage,
+        )
+    }
+
+    fn stalled_motors(duty: f32) -> [MotorLoad; 2] {
+        [MotorLoad::new(duty, 0.0, 1, 1, &MOTOR, MotorIdleMode::Coast); 2]
+    }
+
+    #[test]
+    fn terminal_voltage_sags_with_the_load() {
+        let battery = battery(0.5);
+        let open_circuit = battery.terminal_voltage(&[]).unwrap();
+        assert_eq!(open_circuit, 6.0 * FULL_VOLTAGE_RATIO);
+
+        // Two stalled motors of 4 Ω in parallel behind 0.5 Ω
+        let loaded = battery.terminal_voltage(&stalled_motors(1.0)).unwrap();
+        assert!((loaded - open_circuit * 2.0 / 2.5).abs() < 1e-4);
+        let half = battery.terminal_voltage(&stalled_motors(0.5)).unwrap();
+        assert!(loaded < half && half < open_circuit);
+    }
+
+    #[test]
+    fn stalled_motors_brown_out_a_weak_battery() {
+        let mut battery = battery(2.0);
+        assert_eq!(battery.terminal_voltage(&stalled_motors(1.0)), None);
+        assert_eq!(battery.supply(&stalled_motors(1.0), 0.001), None);
+        let data = battery.data();
+        assert!(data.is_browned_out);
+        assert_eq!(data.current, 0.0);
+
+        // It recovers once the motors stop
+        assert!(battery.supply(&stalled_motors(0.0), 0.001).is_some());
+        assert!(!battery.data().is_browned_out);
+    }
+
+    #[test]
+    fn supply_uses_the_charge() {
+        let mut battery = battery(0.01);
+        let voltage = battery.supply(&stalled_motors(0.5), 1.0).unwrap();
+        let data = battery.data();
+        let current: f32 = stalled_motors(0.5)
+            .iter()
+            .map(|load| load.supply_current(voltage))
+            .sum();
+        assert!(current > 0.0);
+        assert_eq!(data.current, current);
+        assert!((data.remaining_charge - (1000.0 - current / MAH_TO_COULOMBS)).abs() < 1e-3);
+        // The open circuit voltage falls as the battery discharges
+        assert!(battery.terminal_voltage(&[]).unwrap() < 6.0 * FULL_VOLTAGE_RATIO);
+    }
+
+    #[test]
+    fn ideal_supply() {
+        let mut battery = Battery::new(None, 6.0);
+        assert_eq!(battery.supply(&stalled_motors(1.0), 1.0), Some(6.0));
+        let data = battery.data();
+        assert!(!data.is_browned_out);
+        assert_eq!(data.remaining_charge, f32::INFINITY);
+    }
+}
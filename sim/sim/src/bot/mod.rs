@@ -1,5 +1,6 @@
 use bevy::prelude::*;
 
+pub mod battery;
 pub mod model;
 pub mod motors;
 pub mod sensors;
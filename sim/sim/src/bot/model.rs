@@ -1,11 +1,18 @@
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
+use execution_data::{MotorDriversIdleModes, MotorIdleMode};
+use executor::wasm_bindings::exports::robot::Configuration;
 
 use super::BotBodyMarker;
-use super::motors::{Motors, Wheel};
+use super::battery::Battery;
+use super::motors::{Motors, Wheel, motor_parameters};
 use super::sensors::bot_position::BotPositionDetector;
+use super::sensors::imu::ImuModel;
 use super::sensors::line_sensors::LineSensor;
+use super::sensors::motor_angles::EncoderModel;
 use crate::app_builder::BotConfigWrapper;
+use crate::track::Surface;
+use crate::utils::NormalRandom;
 
 const BOT_COLLISION_GROUP: Group = Group::GROUP_1;
 
@@ -16,11 +23,21 @@ const BOT_BODY_TO_WHEEL: f32 = 0.003;
 const BOT_BODY_WEIGHT: f32 = 0.1;
 const BOT_WHEEL_QUAD_DENSITY: f32 = 20.0;
 
+fn wheel_mass(wheel_diameter: f32) -> f32 {
+    BOT_WHEEL_QUAD_DENSITY * wheel_diameter * wheel_diameter
+}
+
+/// Total mass of a bot (body and wheels)
+pub fn bot_mass(config: &Configuration) -> f32 {
+    BOT_BODY_WEIGHT + 2.0 * wheel_mass(config.wheel_diameter / 1000.0)
+}
+
 pub fn setup_bot_model(
     mut commands: Commands,
     config_wrapper: Res<BotConfigWrapper>,
     body_query: Query<Entity, With<BotBodyMarker>>,
     wheels_query: Query<(Entity, &Wheel)>,
+    mut rng: ResMut<NormalRandom>,
 ) {
     let config = &config_wrapper.config;
 
@@ -38,6 +55,17 @@ pub fn setup_bot_model(
     let gear_ratio_num: u32 = config.gear_ratio_num;
     // Transmission gear ratio denumerator (from 1 to 100)
     let gear_ratio_den: u32 = config.gear_ratio_den;
+    // Motors driving the wheels
+    let motor =
+        motor_parameters(&config.motor).expect("motor validated when the configuration is loaded");
+    // Battery powering the motors (an ideal supply when none)
+    let battery = config.battery;
+    // Drivers of the motors (dead band, slew rate and idle mode)
+    let driver = config.driver;
+    // Encoders of the motors
+    let encoders = EncoderModel::new(&config.encoders, gear_ratio_num, gear_ratio_den);
+    // Gyroscope and accelerometer (the power on biases are drawn here)
+    let imu = ImuModel::new(&config.gyro, &config.accel, &mut rng);
     // Spacing of line sensors (in mm, from 1 to 15)
     let front_sensors_spacing: f32 = config.front_sensors_spacing / 1000.0;
     // Height of line sensors from the ground (in mm, from 1 to wheels radius)
@@ -60,6 +88,19 @@ pub fn setup_bot_model(
     let body_width = width_axle - 2.0 * BOT_BODY_TO_WHEEL;
     let bumper_width = body_width / 2.0;
 
+    // The drivers start in their configured idle mode
+    let idle_mode = if driver.brake_when_idle {
+        MotorIdleMode::Brake
+    } else {
+        MotorIdleMode::Coast
+    };
+    commands.insert_resource(MotorDriversIdleModes {
+        left: idle_mode,
+        right: idle_mode,
+    });
+    commands.insert_resource(encoders);
+    commands.insert_resource(imu);
+
     // Static body with motors
     let body = body_query.single().unwrap();
     commands.entity(body).insert((
@@ -104,7 +145,8 @@ pub fn setup_bot_model(
         CollisionGroups::new(BOT_COLLISION_GROUP, !BOT_COLLISION_GROUP),
         Transform::from_xyz(body_world.x, body_world.y, body_world.z),
         GlobalTransform::default(),
-        Motors::new(gear_ratio_num, gear_ratio_den),
+        Motors::new(gear_ratio_num, gear_ratio_den, motor, driver),
+        Battery::new(battery, motor.rated_voltage),
         BotPositionDetector::default(),
         ExternalForce::default(),
         Velocity::zero(),
@@ -119,11 +161,14 @@ pub fn setup_bot_model(
             Collider::ball(wheel_diameter / 2.0),
             Transform::from_xyz(wheel_world.x, wheel_world.y, wheel_world.z),
             RigidBody::Dynamic,
+            // The coefficient follows the surface below the wheel (see
+            // `update_wheels_grip`): Multiply wins over the floor combine rule
+            // and the floor coefficient is 1
             Friction {
-                coefficient: 0.8,
-                combine_rule: CoefficientCombineRule::Max,
+                coefficient: Surface::Normal.friction(),
+                combine_rule: CoefficientCombineRule::Multiply,
             },
-            ColliderMassProperties::Mass(BOT_WHEEL_QUAD_DENSITY * wheel_diameter * wheel_diameter),
+            ColliderMassProperties::Mass(wheel_mass(wheel_diameter)),
             CollisionGroups::new(BOT_COLLISION_GROUP, !BOT_COLLISION_GROUP),
             Velocity::zero(),
             ExternalForce::default(),
@@ -1,18 +1,207 @@
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
-use execution_data::{ExecutionData, MotorDriversDutyCycles, PWM_MAX, PWM_MIN};
+use execution_data::{
+    ExecutionData, MotorDriversDutyCycles, MotorDriversIdleModes, MotorIdleMode, PWM_MAX, PWM_MIN,
+};
+use executor::wasm_bindings::exports::robot::{Motor, MotorDriver, MotorParameters};
+use executor::wasmtime;
 
+use super::battery::Battery;
+use crate::track::{Surface, TrackSegment};
 use crate::utils::{GetBySide, Side};
 
+/// The motor used when none is chosen (the original simulator model)
+const GENERIC_MOTOR: MotorParameters = MotorParameters {
+    no_load_rpm: 40000.0,
+    stall_torque: 1.0,
+    rated_voltage: 6.0,
+    stall_current: 1.0,
+};
+
+/// Common micro metal gearmotors, by name: the values are at the motor
+/// shaft (the gearbox is modeled by the configuration gear ratio) and
+/// approximated from the datasheets of the 6V and 12V versions
+const MOTOR_CATALOGUE: &[(&str, MotorParameters)] = &[
+    ("generic", GENERIC_MOTOR),
+    (
+        "pololu-hpcb-12v",
+        MotorParameters {
+            no_load_rpm: 34000.0,
+            stall_torque: 2.2,
+            rated_voltage: 12.0,
+            stall_current: 0.75,
+        },
+    ),
+    (
+        "pololu-hpcb-6v",
+        MotorParameters {
+            no_load_rpm: 31000.0,
+            stall_torque: 2.0,
+            rated_voltage: 6.0,
+            stall_current: 1.5,
+        },
+    ),
+    (
+        "pololu-hp-6v",
+        MotorParameters {
+            no_load_rpm: 32000.0,
+            stall_torque: 2.0,
+            rated_voltage: 6.0,
+            stall_current: 1.6,
+        },
+    ),
+    (
+        "pololu-mp-6v",
+        MotorParameters {
+            no_load_rpm: 22000.0,
+            stall_torque: 1.3,
+            rated_voltage: 6.0,
+            stall_current: 0.67,
+        },
+    ),
+    (
+        "pololu-lp-6v",
+        MotorParameters {
+            no_load_rpm: 14000.0,
+            stall_torque: 0.7,
+            rated_voltage: 6.0,
+            stall_current: 0.36,
+        },
+    ),
+    (
+        "n20-6v",
+        MotorParameters {
+            no_load_rpm: 15000.0,
+            stall_torque: 0.5,
+            rated_voltage: 6.0,
+            stall_current: 0.5,
+        },
+    ),
+];
+
+const NO_LOAD_RPM_RANGE: std::ops::RangeInclusive<f32> = 1000.0..=100000.0;
+const STALL_TORQUE_RANGE: std::ops::RangeInclusive<f32> = 0.1..=100.0;
+const RATED_VOLTAGE_RANGE: std::ops::RangeInclusive<f32> = 1.0..=24.0;
+const STALL_CURRENT_RANGE: std::ops::RangeInclusive<f32> = 0.05..=10.0;
+
+/// Resolves the motor of a configuration to its parameters, checking that
+/// they are in sensible ranges
+pub fn motor_parameters(motor: &Motor) -> wasmtime::Result<MotorParameters> {
+    let parameters = match motor {
+        Motor::Catalogue(name) => MOTOR_CATALOGUE
+            .iter()
+            .find(|(entry, _)| entry == name)
+            .map(|(_, parameters)| *parameters)
+            .ok_or_else(|| {
+                let names: Vec<&str> = MOTOR_CATALOGUE.iter().map(|(name, _)| *name).collect();
+                wasmtime::Error::msg(format!(
+                    "unknown motor \"{}\" (the catalogue has {})",
+                    name,
+                    names.join(", ")
+                ))
+            })?,
+        Motor::Custom(parameters) => *parameters,
+    };
+    for (name, value, range, unit) in [
+        (
+            "no-load speed",
+            parameters.no_load_rpm,
+            NO_LOAD_RPM_RANGE,
+            "RPM",
+        ),
+        (
+            "stall torque",
+            parameters.stall_torque,
+            STALL_TORQUE_RANGE,
+            "mN·m",
+        ),
+        (
+            "rated voltage",
+            parameters.rated_voltage,
+            RATED_VOLTAGE_RANGE,
+            "V",
+        ),
+        (
+            "stall current",
+            parameters.stall_current,
+            STALL_CURRENT_RANGE,
+            "A",
+        ),
+    ] {
+        if !range.contains(&value) {
+            return Err(wasmtime::Error::msg(format!(
+                "motor {} {} {} out of range ({} to {})",
+                name,
+                value,
+                unit,
+                range.start(),
+                range.end()
+            )));
+        }
+    }
+    Ok(parameters)
+}
+
+const GEAR_RATIO_RANGE: std::ops::RangeInclusive<u32> = 1..=100;
+
+/// Checks that the transmission gear ratio of a configuration is in range
+pub fn validate_gear_ratio(gear_ratio_num: u32, gear_ratio_den: u32) -> wasmtime::Result<()> {
+    for (name, value) in [
+        ("numerator", gear_ratio_num),
+        ("denominator", gear_ratio_den),
+    ] {
+        if !GEAR_RATIO_RANGE.contains(&value) {
+            return Err(wasmtime::Error::msg(format!(
+                "gear ratio {} {} out of range ({} to {})",
+                name,
+                value,
+                GEAR_RATIO_RANGE.start(),
+                GEAR_RATIO_RANGE.end()
+            )));
+        }
+    }
+    Ok(())
+}
+
+const DEAD_BAND_RANGE: std::ops::RangeInclusive<u16> = 0..=200;
+/// Zero disables the slew limit
+const SLEW_RATE_RANGE: std::ops::RangeInclusive<u16> = 0..=2000;
+
+/// Checks that the motor drivers of a configuration are in sensible ranges
+pub fn validate_driver(driver: &MotorDriver) -> wasmtime::Result<()> {
+    for (name, value, range, unit) in [
+        ("dead band", driver.dead_band, DEAD_BAND_RANGE, "PWM"),
+        ("slew rate", driver.slew_rate, SLEW_RATE_RANGE, "PWM/ms"),
+    ] {
+        if !range.contains(&value) {
+            return Err(wasmtime::Error::msg(format!(
+                "motor driver {} {} {} out of range ({} to {})",
+                name,
+                value,
+                unit,
+                range.start(),
+                range.end()
+            )));
+        }
+    }
+    Ok(())
+}
+
 #[derive(Component)]
 pub struct Wheel {
     pub axle: Vec3,
     pub side: Side,
+    /// Duty cycle the driver of this wheel motor is switching (-1 to 1)
+    duty: f32,
 }
 
 impl Wheel {
     pub fn new(axle: Vec3, side: Side) -> Self {
-        Self { axle, side }
+        Self {
+            axle,
+            side,
+            duty: 0.0,
+        }
     }
 }
 
@@ -20,84 +209,139 @@ impl Wheel {
 pub struct Motors {
     gear_ratio_num: u32,
     gear_ratio_den: u32,
+    parameters: MotorParameters,
+    driver: MotorDriver,
 }
 
 impl Motors {
-    pub fn new(gear_ratio_num: u32, gear_ratio_den: u32) -> Self {
+    pub fn new(
+        gear_ratio_num: u32,
+        gear_ratio_den: u32,
+        parameters: MotorParameters,
+        driver: MotorDriver,
+    ) -> Self {
         Self {
             gear_ratio_num,
             gear_ratio_den,
+            parameters,
+            driver,
+        }
+    }
+
+    /// Duty cycle the driver switches after a time step, moving from the
+    /// current one towards the requested PWM
+    fn driver_duty(&self, duty: f32, pwm: i16, dt: f32) -> f32 {
+        let pwm = pwm.clamp(PWM_MIN, PWM_MAX);
+        let target = if pwm.unsigned_abs() < self.driver.dead_band {
+            0.0
+        } else {
+            pwm as f32 / PWM_MAX as f32
+        };
+        if self.driver.slew_rate == 0 {
+            return target;
         }
+        let max_change = self.driver.slew_rate as f32 * 1000.0 * dt / PWM_MAX as f32;
+        duty + (target - duty).clamp(-max_change, max_change)
     }
 }
 
-fn pwm_to_torque(
-    pwm: i16,     // -1000 .. 1000
-    ang_vel: f32, // rad/s
-    gear_ratio_num: u32,
-    gear_ratio_den: u32,
-) -> f32 {
-    // Model a simple DC motor: torque is proportional to PWM (drive) and
-    // decreases linearly with angular velocity, reaching zero at the motor
-    // no-load speed. This is a common, simple approximation of a brushed DC
-    // motor's torque-speed curve.
-
-    // Reference-ish values (Core DC Motor 6V 750 RPM by Jsumo or similar):
-    // - no-load speed: ~750 RPM -> 750/60*2*pi = ~78.54 rad/s
-    // - stall torque: small toy motor ~0.15..0.25 N·m; choose conservative 0.18
-    // These are rough; tune to your robot size.
-    const NO_LOAD_RPM: f32 = 40000.0;
-    const NO_LOAD_OMEGA: f32 = NO_LOAD_RPM / 60.0 * std::f32::consts::TAU; // rad/s
-    const STALL_TORQUE: f32 = 0.001; // N·m at PWM = 1.0 and zero speed
-
-    // Saturate PWM
-    let pwm = (pwm.clamp(PWM_MIN, PWM_MAX) as f32) / (PWM_MAX as f32);
-
-    // Gear ratio as floating point (motor rotations per wheel rotation).
-    let gear_ratio = if gear_ratio_den == 0 {
-        1.0
-    } else {
-        gear_ratio_num as f32 / gear_ratio_den as f32
-    };
+/// The electrical state of a motor driven by a PWM duty cycle
+#[derive(Clone, Copy)]
+pub struct MotorLoad {
+    /// Duty cycle magnitude (0 to 1)
+    pub drive: f32,
+    /// Direction of the drive
+    pub sign: f32,
+    /// Back-EMF at the current motor speed, positive when turning forward
+    /// (in V)
+    pub back_emf: f32,
+    /// Winding resistance (in Ω)
+    pub resistance: f32,
+    /// Motor torque per ampere (in N·m/A)
+    torque_constant: f32,
+    gear_ratio: f32,
+    idle_mode: MotorIdleMode,
+}
 
-    // Motor angular velocity = wheel angular velocity * gear_ratio
-    let motor_omega = ang_vel / gear_ratio.abs();
+impl MotorLoad {
+    pub fn new(
+        duty: f32,    // -1 .. 1
+        ang_vel: f32, // rad/s
+        gear_ratio_num: u32,
+        gear_ratio_den: u32,
+        motor: &MotorParameters,
+        idle_mode: MotorIdleMode,
+    ) -> Self {
+        // Model a brushed DC motor: the winding resistance and the torque
+        // and back-EMF constants follow from the stall and no-load ratings
+        let no_load_omega = motor.no_load_rpm / 60.0 * std::f32::consts::TAU; // rad/s
+        let stall_torque = motor.stall_torque / 1000.0; // N·m at the rated voltage
 
-    // Motor torque magnitude scales with |pwm|
-    let drive = pwm.abs();
+        // Gear ratio as floating point (motor rotations per wheel rotation).
+        let gear_ratio = gear_ratio_num as f32 / gear_ratio_den as f32;
 
-    // Effective motor no-load speed for this drive (assume linear scaling with drive)
-    let omega_noload_motor = NO_LOAD_OMEGA * drive;
+        // Motor angular velocity = wheel angular velocity * gear_ratio
+        let motor_omega = ang_vel / gear_ratio;
 
-    // If drive is zero, no torque.
-    if drive <= 0.0 {
-        return 0.0;
+        Self {
+            drive: duty.abs().min(1.0),
+            sign: if duty >= 0.0 { 1.0 } else { -1.0 },
+            back_emf: motor.rated_voltage / no_load_omega * motor_omega,
+            resistance: motor.rated_voltage / motor.stall_current,
+            torque_constant: stall_torque / motor.stall_current,
+            gear_ratio,
+            idle_mode,
+        }
     }
 
-    // Motor torque falls linearly with motor speed: Tm = T_stall * (1 - |omega_m|/omega_noload_m)
-    let torque_ratio = if omega_noload_motor > 1e-6 {
-        (1.0 - motor_omega.abs() / omega_noload_motor).max(0.0)
-    } else {
-        0.0
-    };
+    /// Current through the motor windings with the driver switching a
+    /// supply voltage, positive when pushing forward (braking drivers short
+    /// the motor in the PWM off phase, so the back-EMF can reverse the
+    /// current, coasting drivers block it)
+    pub fn current(&self, supply_voltage: f32) -> f32 {
+        let current = (self.sign * self.drive * supply_voltage - self.back_emf) / self.resistance;
+        match self.idle_mode {
+            MotorIdleMode::Brake => current,
+            MotorIdleMode::Coast if self.drive > 0.0 && current * self.sign > 0.0 => current,
+            MotorIdleMode::Coast => 0.0,
+        }
+    }
+
+    /// Current drawn from the supply (negative when charging it back)
+    pub fn supply_current(&self, supply_voltage: f32) -> f32 {
+        self.sign * self.drive * self.current(supply_voltage)
+    }
 
-    let motor_torque = STALL_TORQUE * drive * torque_ratio;
+    /// Torque at the wheel with the driver switching a supply voltage
+    pub fn wheel_torque(&self, supply_voltage: f32) -> f32 {
+        let motor_torque = self.torque_constant * self.current(supply_voltage);
 
-    // Wheel torque = motor torque * gear_ratio (torque amplified by gearbox)
-    let wheel_torque = motor_torque / gear_ratio.abs();
+        // Wheel torque = motor torque * gear_ratio (torque amplified by gearbox)
+        motor_torque / self.gear_ratio
+    }
+}
 
-    if pwm >= 0.0 {
-        wheel_torque
-    } else {
-        -wheel_torque
+/// Duration of the physics step being prepared
+pub fn physics_step_secs(timestep_mode: &TimestepMode, time: &Time) -> f32 {
+    match *timestep_mode {
+        TimestepMode::Fixed { dt, .. } | TimestepMode::Interpolated { dt, .. } => dt,
+        TimestepMode::Variable {
+            max_dt, time_scale, ..
+        } => (time.delta_secs() * time_scale).min(max_dt),
     }
 }
 
 fn apply_motors_pwm(
     pwm: Res<MotorDriversDutyCycles>,
+    idle_modes: Res<MotorDriversIdleModes>,
     data: Res<ExecutionData>,
-    mut wheels_query: Query<(&Wheel, &Transform, &Velocity, &mut ExternalForce), Without<Motors>>,
-    mut motors_query: Query<(&Motors, &mut ExternalForce), Without<Wheel>>,
+    timestep_mode: Res<TimestepMode>,
+    time: Res<Time>,
+    mut wheels_query: Query<
+        (&mut Wheel, &Transform, &Velocity, &mut ExternalForce),
+        Without<Motors>,
+    >,
+    mut motors_query: Query<(&Motors, &mut Battery, &mut ExternalForce), Without<Wheel>>,
 ) {
     if !data.activity_data.is_active_now() {
         return;
@@ -105,17 +349,30 @@ fn apply_motors_pwm(
 
     let mut body_torque = Vec3::ZERO;
 
-    let (motors, mut motors_ext_force) = motors_query.single_mut().unwrap();
+    let (motors, mut battery, mut motors_ext_force) = motors_query.single_mut().unwrap();
+    let dt = physics_step_secs(&timestep_mode, &time);
 
-    for (wheel, transform, velocity, mut ext_impulse) in &mut wheels_query {
-        let ang_vel = -velocity.angvel.dot(transform.rotation * wheel.axle.abs()); // rad/s
-        let pwm_value = pwm.get_by_side(wheel.side);
-        let torque = pwm_to_torque(
-            pwm_value,
-            ang_vel,
-            motors.gear_ratio_num,
-            motors.gear_ratio_den,
-        );
+    // Both motors load the battery at once
+    let loads: Vec<MotorLoad> = wheels_query
+        .iter_mut()
+        .map(|(mut wheel, transform, velocity, _)| {
+            wheel.duty = motors.driver_duty(wheel.duty, pwm.get_by_side(wheel.side), dt);
+            let ang_vel = -velocity.angvel.dot(transform.rotation * wheel.axle.abs()); // rad/s
+            MotorLoad::new(
+                wheel.duty,
+                ang_vel,
+                motors.gear_ratio_num,
+                motors.gear_ratio_den,
+                &motors.parameters,
+                idle_modes.get_by_side(wheel.side),
+            )
+        })
+        .collect();
+    let supply_voltage = battery.supply(&loads, dt);
+
+    for ((wheel, transform, _, mut ext_impulse), load) in wheels_query.iter_mut().zip(&loads) {
+        // Browned out drivers leave the motors floating
+        let torque = supply_voltage.map_or(0.0, |voltage| load.wheel_torque(voltage));
 
         let wheel_axle = transform.rotation * wheel.axle.abs();
         let torque_vec = -wheel_axle * torque;
@@ -126,16 +383,188 @@ fn apply_motors_pwm(
     motors_ext_force.torque = body_torque;
 }
 
+/// Sets the friction of each wheel to the grip of the track surface below
+/// it (the wheels roll on the floor or on raised segments, the segment
+/// path colliders lie under them)
+fn update_wheels_grip(
+    read_rapier_context: ReadRapierContext,
+    mut wheels_query: Query<(&GlobalTransform, &mut Friction), With<Wheel>>,
+    track_segments_query: Query<&TrackSegment>,
+) {
+    let rapier_context = read_rapier_context.single().unwrap();
+
+    for (transform, mut friction) in &mut wheels_query {
+        // Where the track crosses itself the least grip wins
+        let mut grip: Option<f32> = None;
+        rapier_context.intersect_ray(
+            transform.translation(),
+            Vec3::NEG_Z,
+            0.2,
+            true,
+            QueryFilter::default().predicate(&|entity| track_segments_query.get(entity).is_ok()),
+            |entity, _| {
+                let friction = track_segments_query
+                    .get(entity)
+                    .unwrap()
+                    .surface()
+                    .friction();
+                grip = Some(grip.map_or(friction, |current| current.min(friction)));
+                true
+            },
+        );
+        let grip = grip.unwrap_or(Surface::Normal.friction());
+        if friction.coefficient != grip {
+            friction.coefficient = grip;
+        }
+    }
+}
+
 pub struct MotorsModelPlugin;
 
 impl Plugin for MotorsModelPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(MotorDriversDutyCycles::default())
+            .insert_resource(MotorDriversIdleModes::default())
             .add_systems(
                 RunFixedMainLoop,
-                (apply_motors_pwm)
+                (update_wheels_grip, apply_motors_pwm)
                     .chain()
                     .in_set(RunFixedMainLoopSystem::BeforeFixedMainLoop),
             );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Wheel torque of the motor model the simulator had before motors
+    /// could be chosen (a torque falling linearly with speed from the stall
+    /// torque, both scaled by the duty cycle)
+    fn baseline_torque(duty: f32, ang_vel: f32, gear_ratio: f32) -> f32 {
+        const NO_LOAD_OMEGA: f32 = 40000.0 / 60.0 * std::f32::consts::TAU;
+        const STALL_TORQUE: f32 = 0.001;
+        let drive = duty.abs();
+        let torque_ratio = (1.0 - (ang_vel / gear_ratio).abs() / (NO_LOAD_OMEGA * drive)).max(0.0);
+        STALL_TORQUE * drive * torque_ratio / gear_ratio * duty.signum()
+    }
+
+    fn generic_load(duty: f32, ang_vel: f32, idle_mode: MotorIdleMode) -> MotorLoad {
+        MotorLoad::new(duty, ang_vel, 1, 1, &GENERIC_MOTOR, idle_mode)
+    }
+
+    #[test]
+    fn generic_motor_on_ideal_supply_matches_baseline() {
+        let mut battery = Battery::new(None, GENERIC_MOTOR.rated_voltage);
+        for (gear_ratio_num, gear_ratio_den) in [(1, 1), (30, 1)] {
+            let gear_ratio = gear_ratio_num as f32 / gear_ratio_den as f32;
+            for duty in [1.0, 0.5, 0.2, -0.5] {
+                // From stall to the no-load speed at this duty cycle,
+                // turning the way the motor pushes
+                for speed_ratio in [0.0, 0.25, 0.5, 0.9] {
+                    let no_load_speed = GENERIC_MOTOR.no_load_rpm / 60.0 * std::f32::consts::TAU;
+                    let ang_vel = no_load_speed * gear_ratio * duty * speed_ratio;
+                    let load = MotorLoad::new(
+                        duty,
+                        ang_vel,
+                        gear_ratio_num,
+                        gear_ratio_den,
+                        &GENERIC_MOTOR,
+                        MotorIdleMode::Coast,
+                    );
+                    let voltage = battery.supply(&[load], 0.001).unwrap();
+                    let torque = load.wheel_torque(voltage);
+                    let expected = baseline_torque(duty, ang_vel, gear_ratio);
+                    assert!(
+                        (torque - expected).abs() <= expected.abs() * 1e-4,
+                        "torque {} instead of {} (duty {}, {} rad/s, ratio {})",
+                        torque,
+                        expected,
+                        duty,
+                        ang_vel,
+                        gear_ratio
+                    );
+                }
+            }
+        }
+    }
+
+    fn motors_with_driver(dead_band: u16, slew_rate: u16) -> Motors {
+        Motors::new(
+            1,
+            1,
+            GENERIC_MOTOR,
+            MotorDriver {
+                dead_band,
+                slew_rate,
+                brake_when_idle: false,
+            },
+        )
+    }
+
+    #[test]
+    fn driver_dead_band() {
+        let motors = motors_with_driver(50, 0);
+        assert_eq!(motors.driver_duty(0.0, 49, 0.001), 0.0);
+        assert_eq!(motors.driver_duty(0.0, -49, 0.001), 0.0);
+        assert_eq!(motors.driver_duty(0.0, 50, 0.001), 50.0 / PWM_MAX as f32);
+        assert_eq!(motors.driver_duty(0.0, -500, 0.001), -0.5);
+    }
+
+    #[test]
+    fn driver_slew_rate() {
+        // 100 PWM units per millisecond
+        let motors = motors_with_driver(0, 100);
+        assert!((motors.driver_duty(0.0, PWM_MAX, 0.001) - 0.1).abs() < 1e-6);
+        assert!((motors.driver_duty(0.0, PWM_MAX, 0.0005) - 0.05).abs() < 1e-6);
+        assert!((motors.driver_duty(0.5, PWM_MIN, 0.002) - 0.3).abs() < 1e-6);
+        // The target is reached without overshooting it
+        assert_eq!(motors.driver_duty(0.45, 500, 0.001), 0.5);
+
+        // No slew limit
+        let motors = motors_with_driver(0, 0);
+        assert_eq!(motors.driver_duty(0.0, PWM_MAX, 0.001), 1.0);
+        assert_eq!(motors.driver_duty(1.0, PWM_MIN, 0.0001), -1.0);
+    }
+
+    #[test]
+    fn idle_current() {
+        // Turning forward at half the no-load speed without power
+        let ang_vel = GENERIC_MOTOR.no_load_rpm / 60.0 * std::f32::consts::TAU / 2.0;
+        let voltage = GENERIC_MOTOR.rated_voltage;
+
+        let coasting = generic_load(0.0, ang_vel, MotorIdleMode::Coast);
+        assert_eq!(coasting.current(voltage), 0.0);
+        assert_eq!(coasting.wheel_torque(voltage), 0.0);
+
+        // Shorted windings: the back-EMF drives a current that brakes
+        let braking = generic_load(0.0, ang_vel, MotorIdleMode::Brake);
+        let expected = -voltage / 2.0 / (voltage / GENERIC_MOTOR.stall_current);
+        assert!((braking.current(voltage) - expected).abs() < 1e-6);
+        assert!(braking.wheel_torque(voltage) < 0.0);
+        // Nothing flows from the supply
+        assert_eq!(braking.supply_current(voltage), 0.0);
+    }
+
+    #[test]
+    fn regenerative_braking_current() {
+        // Half the duty cycle at the full no-load speed: the back-EMF is
+        // twice the switched voltage
+        let ang_vel = GENERIC_MOTOR.no_load_rpm / 60.0 * std::f32::consts::TAU;
+        let voltage = GENERIC_MOTOR.rated_voltage;
+
+        let braking = generic_load(0.5, ang_vel, MotorIdleMode::Brake);
+        assert!(braking.current(voltage) < 0.0);
+        assert!(braking.supply_current(voltage) < 0.0);
+        assert!(braking.wheel_torque(voltage) < 0.0);
+
+        // The same going backwards
+        let braking = generic_load(-0.5, -ang_vel, MotorIdleMode::Brake);
+        assert!(braking.current(voltage) > 0.0);
+        assert!(braking.supply_current(voltage) < 0.0);
+
+        let mut battery = Battery::new(None, voltage);
+        battery.supply(&[braking], 0.001);
+        assert!(battery.data().current < 0.0);
+    }
+}
@@ -0,0 +1,9 @@
+use bevy::prelude::*;
+
+use crate::bot::battery::Battery;
+use execution_data::SensorsData;
+
+pub fn compute_battery_data(battery_query: Query<&Battery>, mut sensors_data: ResMut<SensorsData>) {
+    let battery = battery_query.single().unwrap();
+    sensors_data.battery = battery.data();
+}
@@ -1,45 +1,184 @@
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
 
-use crate::track::TrackSegment;
+use crate::track::{
+    TRACK_HALF_WIDTH, Track, TrackCheckpoint, TrackGeometry, TrackObstacle, TrackSegment,
+};
+use crate::utils::rotate_vec2;
 use execution_data::{BotPhysicalPosition, BotPosition, SensorsData};
 
 #[derive(Component, Default)]
 pub struct BotPositionDetector {}
 
+/// Indices of the segments under the bot in the current step (more than
+/// one where the track crosses itself)
+#[derive(Resource, Default)]
+pub struct SegmentsUnderBot(Vec<usize>);
+
+/// Calls `visit` for each segment (by index) under the bot, computing them
+/// from the track geometry when the bot is over level segments
+fn for_segments_under_bot(
+    rapier_context: &RapierContext,
+    geometry: &TrackGeometry,
+    track_segments_query: &Query<(&TrackSegment, &TrackCheckpoint)>,
+    origin: Vec3,
+    mut visit: impl FnMut(usize, &TrackSegment),
+) {
+    let dir = Vec3::NEG_Z;
+    let max_toi = 0.1;
+
+    match TrackGeometry::surface_hit(origin, dir, max_toi)
+        .filter(|&point| geometry.is_level_at(point))
+    {
+        Some(point) => {
+            for (index, segment, _) in geometry.segments_under(point) {
+                visit(index, segment);
+            }
+        }
+        None => {
+            rapier_context.intersect_ray(
+                origin,
+                dir,
+                max_toi,
+                true,
+                QueryFilter::default()
+                    .predicate(&|entity| track_segments_query.get(entity).is_ok()),
+                |entity, _| {
+                    let (segment, checkpoint) = track_segments_query.get(entity).unwrap();
+                    visit(checkpoint.index, segment);
+                    true
+                },
+            );
+        }
+    }
+}
+
 pub fn compute_bot_position(
     read_rapier_context: ReadRapierContext,
+    geometry: Res<TrackGeometry>,
     bot_query: Query<&Transform, With<BotPositionDetector>>,
-    track_segments_query: Query<&TrackSegment>,
+    track_segments_query: Query<(&TrackSegment, &TrackCheckpoint)>,
+    mut segments_under_bot: ResMut<SegmentsUnderBot>,
     mut sensors_data: ResMut<SensorsData>,
 ) {
     let rapier_context = read_rapier_context.single().unwrap();
     let bot_transform = bot_query.single().unwrap();
     let origin = bot_transform.translation;
     let (bot_rx, bot_ry, bot_rz) = bot_transform.rotation.to_euler(EulerRot::XYZ);
-    let dir = Vec3::NEG_Z;
-    let max_toi = 0.1;
 
-    sensors_data.bot_position = if let Some((entity, _)) = rapier_context.cast_ray_and_get_normal(
+    segments_under_bot.0.clear();
+    let mut over_end_only = true;
+    for_segments_under_bot(
+        &rapier_context,
+        &geometry,
+        &track_segments_query,
         origin,
-        dir,
-        max_toi,
-        true,
-        QueryFilter::default().predicate(&|entity| track_segments_query.get(entity).is_ok()),
-    ) {
-        // Bot is over the track
-        if track_segments_query.get(entity).unwrap() == &TrackSegment::End {
-            BotPosition::End
-        } else {
-            BotPosition::OnTrack
-        }
-    } else {
-        // Bot is out
-        BotPosition::Out
+        |index, segment| {
+            segments_under_bot.0.push(index);
+            over_end_only &= segment.is_end();
+        },
+    );
+    // Where the track crosses itself the bot is at the end only when no
+    // other segment is below it
+    let over_track = !segments_under_bot.0.is_empty();
+    sensors_data.bot_position = match (over_track, over_end_only) {
+        (false, _) => BotPosition::Out,
+        (true, true) => BotPosition::End,
+        (true, false) => BotPosition::OnTrack,
     };
+    sensors_data.is_out_of_track = !over_track;
+    sensors_data.is_over_track_end = over_track && over_end_only;
 
     sensors_data.bot_physical_position = BotPhysicalPosition {
         pos: origin,
         rot: Vec3::new(bot_rx, bot_ry, bot_rz),
     };
 }
+
+pub fn track_checkpoints(
+    track: Res<Track>,
+    segments_under_bot: Res<SegmentsUnderBot>,
+    mut sensors_data: ResMut<SensorsData>,
+) {
+    let checkpoint_count = track.checkpoint_count();
+    let is_closed = track.laps().is_some();
+
+    // The bot can be over more than one segment (where the track crosses
+    // itself): only the next checkpoint in order counts
+    let starting = sensors_data.checkpoints_reached == 0;
+    let mut reached = sensors_data.checkpoints_reached;
+    for &index in &segments_under_bot.0 {
+        if starting {
+            // The segment where the bot starts (the first one if the bot is
+            // over the joint between two segments)
+            reached = if reached == 0 {
+                index + 1
+            } else {
+                reached.min(index + 1)
+            };
+        } else if index == reached % checkpoint_count && (is_closed || reached < checkpoint_count) {
+            reached += 1;
+        }
+    }
+    sensors_data.checkpoints_reached = reached;
+
+    // The end counts only after every other checkpoint
+    if is_closed || reached < checkpoint_count {
+        sensors_data.is_over_track_end = false;
+        if let BotPosition::End = sensors_data.bot_position {
+            sensors_data.bot_position = BotPosition::OnTrack;
+        }
+    }
+}
+
+pub fn detect_obstacle_hits(
+    read_rapier_context: ReadRapierContext,
+    obstacles_query: Query<Entity, With<TrackObstacle>>,
+    mut sensors_data: ResMut<SensorsData>,
+) {
+    let rapier_context = read_rapier_context.single().unwrap();
+    // Obstacles are fixed, so the only contacts they can have are with the bot
+    sensors_data.is_hitting_obstacle = obstacles_query.iter().any(|obstacle| {
+        rapier_context
+            .contact_pairs_with(obstacle)
+            .any(|pair| pair.has_any_active_contact())
+    });
+}
+
+pub fn compute_track_progress(track: Res<Track>, mut sensors_data: ResMut<SensorsData>) {
+    if let Some(progress) = track.progress_at(
+        sensors_data.bot_physical_position.pos.truncate(),
+        sensors_data.checkpoints_reached,
+    ) {
+        sensors_data.track_progress = progress;
+    }
+}
+
+pub fn count_laps(
+    track: Res<Track>,
+    mut previous_distance: Local<Option<f32>>,
+    mut sensors_data: ResMut<SensorsData>,
+) {
+    let Some(finish_line) = track.finish_line() else {
+        return;
+    };
+
+    // Bot position relative to the finish line: y along the track, x across it
+    let position = rotate_vec2(
+        sensors_data.bot_physical_position.pos.truncate() - finish_line.position(),
+        -finish_line.direction().to_radians(),
+    );
+
+    // A crossing counts only once all the checkpoints of the lap are visited
+    let lap_done = sensors_data.checkpoints_reached
+        >= (sensors_data.completed_laps + 1) * track.checkpoint_count();
+    if let Some(previous_distance) = *previous_distance
+        && lap_done
+        && previous_distance < 0.0
+        && position.y >= 0.0
+        && position.x.abs() <= TRACK_HALF_WIDTH * 2.0
+    {
+        sensors_data.completed_laps += 1;
+    }
+    *previous_distance = Some(position.y);
+}
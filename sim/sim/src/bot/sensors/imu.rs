@@ -1,17 +1,213 @@
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
+use executor::wasm_bindings::exports::robot::InertialSensor;
+use executor::wasmtime;
 
 use super::bot_position::BotPositionDetector;
-use execution_data::{GyroData, ImuFusedData, SensorsData};
+use crate::{bot::motors::physics_step_secs, utils::NormalRandom};
+use execution_data::{AccelData, GyroData, ImuFusedData, SensorsData};
+
+const GYRO_FULL_SCALE_RANGE: std::ops::RangeInclusive<f32> = 125.0..=4000.0;
+const GYRO_BIAS_RANGE: std::ops::RangeInclusive<f32> = 0.0..=20.0;
+const GYRO_DRIFT_RANGE: std::ops::RangeInclusive<f32> = 0.0..=1.0;
+const GYRO_NOISE_RANGE: std::ops::RangeInclusive<f32> = 0.0..=5.0;
+
+const ACCEL_FULL_SCALE_RANGE: std::ops::RangeInclusive<f32> = 2.0..=32.0;
+const ACCEL_BIAS_RANGE: std::ops::RangeInclusive<f32> = 0.0..=0.5;
+const ACCEL_DRIFT_RANGE: std::ops::RangeInclusive<f32> = 0.0..=0.05;
+const ACCEL_NOISE_RANGE: std::ops::RangeInclusive<f32> = 0.0..=0.5;
+
+const STANDARD_GRAVITY: f32 = 9.80665;
+
+/// Checks that the inertial sensors of a configuration are in sensible ranges
+pub fn validate_inertial_sensors(
+    gyro: &InertialSensor,
+    accel: &InertialSensor,
+) -> wasmtime::Result<()> {
+    for (name, value, range, unit) in [
+        (
+            "gyro full scale",
+            gyro.full_scale,
+            GYRO_FULL_SCALE_RANGE,
+            "deg/s",
+        ),
+        ("gyro bias", gyro.bias, GYRO_BIAS_RANGE, "deg/s"),
+        ("gyro drift", gyro.drift, GYRO_DRIFT_RANGE, "deg/s/√s"),
+        ("gyro noise", gyro.noise, GYRO_NOISE_RANGE, "deg/s"),
+        (
+            "accel full scale",
+            accel.full_scale,
+            ACCEL_FULL_SCALE_RANGE,
+            "g",
+        ),
+        ("accel bias", accel.bias, ACCEL_BIAS_RANGE, "g"),
+        ("accel drift", accel.drift, ACCEL_DRIFT_RANGE, "g/√s"),
+        ("accel noise", accel.noise, ACCEL_NOISE_RANGE, "g"),
+    ] {
+        if !range.contains(&value) {
+            return Err(wasmtime::Error::msg(format!(
+                "{} {} {} out of range ({} to {})",
+                name,
+                value,
+                unit,
+                range.start(),
+                range.end()
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn random_vec3(rng: &mut NormalRandom) -> Vec3 {
+    Vec3::new(rng.sample(), rng.sample(), rng.sample())
+}
+
+/// The errors of a three axes inertial sensor (in SI units)
+struct InertialSensorModel {
+    full_scale: f32,
+    /// Current offset of each axis (power on bias plus drift so far)
+    offset: Vec3,
+    drift: f32,
+    noise: f32,
+}
+
+impl InertialSensorModel {
+    /// Converts the configuration with `unit` (SI units per configured unit)
+    /// and draws the power on bias
+    fn new(sensor: &InertialSensor, unit: f32, rng: &mut NormalRandom) -> Self {
+        Self {
+            full_scale: sensor.full_scale * unit,
+            offset: random_vec3(rng) * sensor.bias * unit,
+            drift: sensor.drift * unit,
+            noise: sensor.noise * unit,
+        }
+    }
+
+    /// Reads a true value after `dt` seconds since the last reading
+    fn measure(&mut self, value: Vec3, dt: f32, rng: &mut NormalRandom) -> Vec3 {
+        self.offset += random_vec3(rng) * self.drift * dt.sqrt();
+        let reading = value + self.offset + random_vec3(rng) * self.noise;
+        reading.clamp(Vec3::splat(-self.full_scale), Vec3::splat(self.full_scale))
+    }
+}
+
+/// What an accelerometer measures in the body frame: the acceleration
+/// minus gravity, so it reads 1 g upwards at rest
+fn specific_force(to_body: Quat, linear_acceleration: Vec3, gravity: Vec3) -> Vec3 {
+    to_body * (linear_acceleration - gravity)
+}
+
+/// The IMU: a gyroscope and an accelerometer fixed to the bot body
+#[derive(Resource)]
+pub struct ImuModel {
+    gyro: InertialSensorModel,
+    accel: InertialSensorModel,
+    /// Body velocity at the previous step (in m/s)
+    last_velocity: Option<Vec3>,
+}
+
+impl ImuModel {
+    pub fn new(gyro: &InertialSensor, accel: &InertialSensor, rng: &mut NormalRandom) -> Self {
+        Self {
+            gyro: InertialSensorModel::new(gyro, 1f32.to_radians(), rng),
+            accel: InertialSensorModel::new(accel, STANDARD_GRAVITY, rng),
+            last_velocity: None,
+        }
+    }
+}
 
 pub fn compute_imu_data(
     bot_query: Query<(&Transform, &Velocity), With<BotPositionDetector>>,
+    rapier_config: Query<&RapierConfiguration>,
+    timestep_mode: Res<TimestepMode>,
+    time: Res<Time>,
+    mut imu: ResMut<ImuModel>,
+    mut rng: ResMut<NormalRandom>,
     mut sensors_data: ResMut<SensorsData>,
 ) {
     let (transform, velocity) = bot_query.single().unwrap();
+    let to_body = transform.rotation.inverse();
+    let dt = physics_step_secs(&timestep_mode, &time);
 
-    let body_rot = Vec3::from(transform.rotation.to_euler(EulerRot::XYZ));
-    sensors_data.gyro = GyroData::from(velocity.angvel * body_rot);
+    let angular_velocity = imu.gyro.measure(to_body * velocity.angvel, dt, &mut rng);
+    sensors_data.gyro = GyroData::new(angular_velocity, imu.gyro.full_scale);
 
+    let gravity = rapier_config.single().unwrap().gravity;
+    let linear_acceleration = match imu.last_velocity {
+        Some(last_velocity) if dt > 0.0 => (velocity.linvel - last_velocity) / dt,
+        _ => Vec3::ZERO,
+    };
+    imu.last_velocity = Some(velocity.linvel);
+    let acceleration = imu.accel.measure(
+        specific_force(to_body, linear_acceleration, gravity),
+        dt,
+        &mut rng,
+    );
+    sensors_data.accel = AccelData::new(acceleration, imu.accel.full_scale);
+
+    let body_rot = Vec3::from(transform.rotation.to_euler(EulerRot::XYZ));
     sensors_data.imu_fused = ImuFusedData::from(body_rot);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sensor(full_scale: f32, bias: f32, noise: f32) -> InertialSensor {
+        InertialSensor {
+            full_scale,
+            bias,
+            drift: 0.0,
+            noise,
+        }
+    }
+
+    #[test]
+    fn readings_saturate_at_full_scale() {
+        let mut rng = NormalRandom::new();
+        let mut gyro =
+            InertialSensorModel::new(&sensor(250.0, 0.0, 0.0), 1f32.to_radians(), &mut rng);
+        let full_scale = 250f32.to_radians();
+        let reading = gyro.measure(Vec3::new(10.0, -10.0, 1.0), 0.001, &mut rng);
+        assert_eq!(reading, Vec3::new(full_scale, -full_scale, 1.0));
+    }
+
+    #[test]
+    fn bias_is_drawn_once() {
+        let mut rng = NormalRandom::new();
+        let mut accel =
+            InertialSensorModel::new(&sensor(2.0, 0.1, 0.0), STANDARD_GRAVITY, &mut rng);
+        let first = accel.measure(Vec3::ZERO, 0.001, &mut rng);
+        assert_ne!(first, Vec3::ZERO);
+        assert_eq!(accel.measure(Vec3::ZERO, 0.001, &mut rng), first);
+    }
+
+    #[test]
+    fn noise_spreads_the_readings() {
+        let mut rng = NormalRandom::new();
+        let noise = 0.05;
+        let mut accel =
+            InertialSensorModel::new(&sensor(2.0, 0.0, noise), STANDARD_GRAVITY, &mut rng);
+        let readings: Vec<f32> = (0..10000)
+            .map(|_| accel.measure(Vec3::ZERO, 0.001, &mut rng).x / STANDARD_GRAVITY)
+            .collect();
+        let mean = readings.iter().sum::<f32>() / readings.len() as f32;
+        let deviation = (readings.iter().map(|r| (r - mean).powi(2)).sum::<f32>()
+            / readings.len() as f32)
+            .sqrt();
+        assert!(mean.abs() < 0.005, "mean {}", mean);
+        assert!((deviation - noise).abs() < 0.005, "deviation {}", deviation);
+    }
+
+    #[test]
+    fn one_g_up_at_rest() {
+        let gravity = Vec3::NEG_Z * STANDARD_GRAVITY;
+        let level = specific_force(Quat::IDENTITY, Vec3::ZERO, gravity);
+        assert_eq!(level, Vec3::Z * STANDARD_GRAVITY);
+
+        // Rolled on its right side, up is to the left of the bot
+        let rolled = Quat::from_rotation_y(std::f32::consts::FRAC_PI_2);
+        let reading = specific_force(rolled.inverse(), Vec3::ZERO, gravity);
+        assert!((reading - Vec3::NEG_X * STANDARD_GRAVITY).length() < 1e-4);
+    }
+}
@@ -1,12 +1,10 @@
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
 
-use crate::track::TrackSegment;
+use crate::track::{LineStyle, TrackGeometry, TrackSegment, surface_height_at};
 use crate::utils::{NormalRandom, point_to_new_origin};
 use execution_data::SensorsData;
 
-use super::bot_position::BotPositionDetector;
-
 #[inline]
 fn line_reflection_attenuation(value: f32, z: f32) -> f32 {
     // Attenuation model: increased z makes to that the sensor perceives
@@ -34,10 +32,31 @@ fn line_reflection_attenuation(value: f32, z: f32) -> f32 {
     value
 }
 
-fn line_reflection(x: f32, z: f32) -> f32 {
-    const LINE_SIZE: f32 = 0.02; // 20 mm
+/// Reading at `outside` from the edge of a black shape on a white floor
+/// (negative inside the shape)
+fn edge_reflection(outside: f32, transition: f32) -> f32 {
+    if !outside.is_finite() {
+        // Treat NaN/inf as far away (white)
+        100.0
+    } else if outside <= 0.0 {
+        // Fully over the black shape
+        0.0
+    } else if outside >= transition {
+        // Far enough to see full white
+        100.0
+    } else {
+        // Smooth interpolation between black and white using smoothstep
+        let t = outside / transition; // normalized 0..1
+        // smoothstep (cubic hermite) -> smooth start/end
+        let s = t * t * (3.0 - 2.0 * t);
+        100.0 * s
+    }
+}
 
-    // Model: black line of width LINE_SIZE centered at 0 on a white floor.
+fn line_reflection(x: f32, marker_distance: f32, z: f32, style: LineStyle) -> f32 {
+    // Model: black line of width 2 * style.half_width centered at 0 on a white
+    // floor (or a white line on a black track band when inverted); side
+    // markers, `marker_distance` away, have the line color.
     // The sensor doesn't have infinite spatial resolution, so we smooth the
     // transition between black and white across a finite transition region.
     // We return 0.0 for pure black, 100.0 for pure white.
@@ -47,35 +66,26 @@ fn line_reflection(x: f32, z: f32) -> f32 {
     // the transition width is the sensor z coordinate.
     let transition = z;
 
-    let value = {
-        if x.is_finite() {
-            let half = LINE_SIZE * 0.5;
-            let d = x.abs();
-
-            if d <= half {
-                // Fully over the black line
-                0.0
-            } else if d >= half + transition {
-                // Far enough to see full white
-                100.0
-            } else {
-                // Smooth interpolation between black and white using smoothstep
-                let t = (d - half) / transition; // normalized 0..1
-                // smoothstep (cubic hermite) -> smooth start/end
-                let s = t * t * (3.0 - 2.0 * t);
-                100.0 * s
-            }
-        } else {
-            // Treat NaN/inf as far away (white)
-            100.0
-        }
-    };
+    let value = edge_reflection(x.abs() - style.half_width, transition)
+        .min(edge_reflection(marker_distance, transition));
+    let value = if style.inverted { 100.0 - value } else { value };
     line_reflection_attenuation(value, z)
 }
 
+/// Lateral distance equivalent to being `lateral` away from the line axis
+/// and `along` away from the nearest line section (for interrupted lines)
+fn distance_to_dashed_line(lateral: f32, along: f32, half_width: f32) -> f32 {
+    if along <= 0.0 {
+        lateral
+    } else {
+        half_width + (lateral.abs() - half_width).max(0.0).hypot(along)
+    }
+}
+
 trait TrackSimulateLine {
     fn intersection_to_sensor_value(&self, point: Vec3, z: f32, transform: &GlobalTransform)
     -> f32;
+    fn local_sensor_value(&self, local_point: Vec2, z: f32) -> f32;
 }
 
 impl TrackSimulateLine for TrackSegment {
@@ -85,35 +95,107 @@ impl TrackSimulateLine for TrackSegment {
         z: f32,
         transform: &GlobalTransform,
     ) -> f32 {
-        let local_point = point_to_new_origin(point, transform);
+        self.local_sensor_value(point_to_new_origin(point, transform), z)
+    }
+
+    fn local_sensor_value(&self, local_point: Vec2, z: f32) -> f32 {
+        let style = self.line_style();
 
-        match *self {
-            TrackSegment::Start | TrackSegment::End => line_reflection(local_point.x, z),
-            TrackSegment::Straight(_) => line_reflection(local_point.x, z),
+        let dist_to_line = match *self {
+            TrackSegment::Start | TrackSegment::End => local_point.x,
+            TrackSegment::Straight(data) | TrackSegment::Crossing(data) => {
+                let along = data.gaps.map_or(0.0, |gaps| {
+                    gaps.distance_to_line(local_point.y + data.length / 2.0, data.length)
+                });
+                distance_to_dashed_line(local_point.x, along, style.half_width)
+            }
             TrackSegment::NinetyDegTurn(data) => {
-                let dist_to_line = if local_point.y < data.side.sign() * local_point.x {
+                if local_point.y < data.side.sign() * local_point.x {
                     local_point.x
                 } else {
                     data.side.sign() * local_point.y
-                };
-                line_reflection(dist_to_line, z)
+                }
             }
             TrackSegment::CyrcleTurn(data) => {
                 let dist_to_line = (local_point.length() - data.radius) * data.side.sign();
-                line_reflection(dist_to_line, z)
+                let along = data.gaps.map_or(0.0, |gaps| {
+                    let length = data.radius * data.angle.to_radians();
+                    gaps.distance_to_line(data.angle_to(local_point) * data.radius, length)
+                });
+                distance_to_dashed_line(dist_to_line, along, style.half_width)
             }
-        }
+        };
+        let marker_distance = self
+            .marker_rects()
+            .iter()
+            .map(|marker| marker.distance(local_point))
+            .fold(f32::INFINITY, f32::min);
+        line_reflection(dist_to_line, marker_distance, z, style)
     }
 }
 
+/// Reading of a line sensor over level segments, computed from the track
+/// geometry (`None` where a ray has to be cast instead, see
+/// [`TrackGeometry::is_level_at`]); the inner value is `None` when the
+/// sensor is out of the track
+pub fn analytic_line_value(
+    geometry: &TrackGeometry,
+    origin: Vec3,
+    dir: Vec3,
+    max_toi: f32,
+) -> Option<Option<f32>> {
+    let point = TrackGeometry::surface_hit(origin, dir, max_toi)
+        .filter(|&point| geometry.is_level_at(point))?;
+    // The surface of level segments is the floor plane
+    Some(
+        geometry
+            .segments_under(point)
+            .map(|(_, track_segment, local_point)| {
+                track_segment.local_sensor_value(local_point, origin.z)
+            })
+            .reduce(f32::min),
+    )
+}
+
+/// Reading of a line sensor from the segment path colliders hit by its ray
+/// (`None` when the sensor is out of the track)
+pub fn raycast_line_value(
+    rapier_context: &RapierContext,
+    track_segments_query: &Query<(&TrackSegment, &GlobalTransform)>,
+    origin: Vec3,
+    dir: Vec3,
+    max_toi: f32,
+) -> Option<f32> {
+    // Where the track crosses itself the sensor sees more than one
+    // segment: the darkest reading wins.
+    let mut line_value: Option<f32> = None;
+    rapier_context.intersect_ray(
+        origin,
+        dir,
+        max_toi,
+        true,
+        QueryFilter::default().predicate(&|entity| track_segments_query.get(entity).is_ok()),
+        |entity, intersection| {
+            let point = intersection.point;
+            let (track_segment, transform) = track_segments_query.get(entity).unwrap();
+            // Height over the track surface, which can be raised
+            let z = origin.z - surface_height_at(point);
+            let value = track_segment.intersection_to_sensor_value(point, z, transform);
+            line_value = Some(line_value.map_or(value, |current| current.min(value)));
+            true
+        },
+    );
+    line_value
+}
+
 #[derive(Component, Default)]
 pub struct LineSensor {}
 
 pub fn compute_sensor_readings(
     read_rapier_context: ReadRapierContext,
     sensors_query: Query<&GlobalTransform, With<LineSensor>>,
-    bot_body_query: Query<&GlobalTransform, With<BotPositionDetector>>,
     track_segments_query: Query<(&TrackSegment, &GlobalTransform)>,
+    geometry: Res<TrackGeometry>,
     mut rng: ResMut<NormalRandom>,
     mut sensors_data: ResMut<SensorsData>,
 ) {
@@ -127,47 +209,115 @@ pub fn compute_sensor_readings(
         let dir = sensor_tf.rotation().mul_vec3(Vec3::NEG_Z);
         let max_toi = 0.1;
 
-        if let Some((entity, intersection)) = rapier_context.cast_ray_and_get_normal(
-            origin,
-            dir,
-            max_toi,
-            true,
-            QueryFilter::default().predicate(&|entity| track_segments_query.get(entity).is_ok()),
-        ) {
+        let line_value =
+            analytic_line_value(&geometry, origin, dir, max_toi).unwrap_or_else(|| {
+                raycast_line_value(&rapier_context, &track_segments_query, origin, dir, max_toi)
+            });
+
+        sensors_data.line_sensors[i] = match line_value {
             // Sensor is over the track
-            let point: Vec3 = intersection.point.into();
-            let (track_segment, transform) = track_segments_query.get(entity).unwrap();
-            sensors_data.line_sensors[i] = rng
-                .noisy_value(
-                    track_segment.intersection_to_sensor_value(point, sensor_z, transform),
-                    NOISE,
-                )
-                .clamp(0.0, 100.0);
-        } else {
+            Some(value) => rng.noisy_value(value, NOISE).clamp(0.0, 100.0),
             // Sensor is out
-            sensors_data.line_sensors[i] = rng
+            None => rng
                 .noisy_value(line_reflection_attenuation(100.0, sensor_z), NOISE)
-                .clamp(0.0, 100.0);
+                .clamp(0.0, 100.0),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::text::cosmic_text::Angle;
+
+    use super::*;
+    use crate::track::{GapPattern, LINE_HALF_WIDTH, Track};
+    use crate::utils::Side;
+
+    /// At this height the reading is not attenuated and goes from black to
+    /// white over this distance from the line edge
+    const SENSOR_HEIGHT: f32 = 0.002;
+
+    /// Reading of a sensor `lateral` away from the center line of a segment,
+    /// `along` from its start
+    fn reading(track: &Track, segment: usize, along: f32, lateral: f32) -> Option<f32> {
+        let geometry = TrackGeometry::new(track);
+        let (segment, origin) = track.segment_origins()[segment];
+        let point = segment
+            .point_at(origin, along)
+            .translate_in_direction(Vec2::X * lateral);
+        let position = point.position() + track.floor_center().truncate();
+        analytic_line_value(&geometry, position.extend(SENSOR_HEIGHT), Vec3::NEG_Z, 0.1)
+            .expect("level segments are computed analytically")
+    }
+
+    /// Compares readings up to the rounding of world positions
+    fn assert_reading(value: Option<f32>, expected: f32) {
+        let value = value.expect("the sensor is over the track");
+        assert!(
+            (value - expected).abs() < 0.01,
+            "read {} instead of {}",
+            value,
+            expected
+        );
+    }
+
+    fn open_track(pieces: &[TrackSegment]) -> Track {
+        let mut segments = vec![TrackSegment::start()];
+        segments.extend_from_slice(pieces);
+        segments.push(TrackSegment::end());
+        Track::new(segments)
+    }
+
+    #[test]
+    fn straight_and_arc() {
+        let track = open_track(&[
+            TrackSegment::straight(1.0),
+            TrackSegment::cyrcle_turn(0.5, Angle::from_degrees(90.0), Side::Left),
+        ]);
+        for (segment, along) in [(1, 0.5), (2, 0.4)] {
+            assert_reading(reading(&track, segment, along, 0.0), 0.0);
+            assert_reading(reading(&track, segment, along, -0.05), 100.0);
+            // Halfway through the transition from the line edge
+            assert_reading(
+                reading(
+                    &track,
+                    segment,
+                    along,
+                    LINE_HALF_WIDTH + SENSOR_HEIGHT / 2.0,
+                ),
+                50.0,
+            );
+            // Out of the track
+            assert_eq!(reading(&track, segment, along, 0.2), None);
         }
     }
 
-    let bot_body_tf = bot_body_query.single().unwrap();
-    let bot_body_origin = bot_body_tf.translation();
-    let bot_body_dir = bot_body_tf.rotation().mul_vec3(Vec3::NEG_Z);
-    if let Some((entity, _)) = rapier_context.cast_ray_and_get_normal(
-        bot_body_origin,
-        bot_body_dir,
-        0.1,
-        true,
-        QueryFilter::default().predicate(&|entity| track_segments_query.get(entity).is_ok()),
-    ) {
-        // Bot body is over the track
-        sensors_data.is_out_of_track = false;
-        let (track_segment, _) = track_segments_query.get(entity).unwrap();
-        sensors_data.is_over_track_end = track_segment.is_end();
-    } else {
-        // Bot body is out of the track
-        sensors_data.is_out_of_track = true;
-        sensors_data.is_over_track_end = false;
+    #[test]
+    fn gaps() {
+        let track =
+            open_track(&[TrackSegment::straight(1.0).with_gaps(GapPattern::new(0.25, 0.25))]);
+        assert_reading(reading(&track, 1, 0.125, 0.0), 0.0);
+        // In the middle of a gap the line is 0.125 m away
+        assert_reading(reading(&track, 1, 0.375, 0.0), 100.0);
+        // At the end of a gap the reading fades like at the line edges
+        assert_reading(reading(&track, 1, 0.5 - SENSOR_HEIGHT / 2.0, 0.0), 50.0);
+    }
+
+    #[test]
+    fn crossing() {
+        // The turn brings the second crossing across the first one, 0.2 m
+        // from its start (and 0.3 m from the start of the second one)
+        let track = open_track(&[
+            TrackSegment::crossing(0.5),
+            TrackSegment::cyrcle_turn(0.3, Angle::from_degrees(270.0), Side::Left),
+            TrackSegment::crossing(0.6),
+        ]);
+        assert_reading(reading(&track, 1, 0.2, 0.0), 0.0);
+        // Over the line of either segment, away from the other one's line,
+        // the darkest reading wins
+        assert_reading(reading(&track, 1, 0.2, 0.05), 0.0);
+        assert_reading(reading(&track, 3, 0.35, 0.0), 0.0);
+        // Away from both lines
+        assert_reading(reading(&track, 1, 0.25, 0.05), 100.0);
     }
 }
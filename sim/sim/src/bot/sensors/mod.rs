@@ -2,12 +2,17 @@ use bevy::prelude::*;
 
 use execution_data::{MotorAngles, SensorsData};
 
+pub mod battery;
 pub mod bot_position;
 pub mod imu;
 pub mod line_sensors;
 pub mod motor_angles;
 
-use bot_position::compute_bot_position;
+use battery::compute_battery_data;
+use bot_position::{
+    SegmentsUnderBot, compute_bot_position, compute_track_progress, count_laps,
+    detect_obstacle_hits, track_checkpoints,
+};
 use imu::compute_imu_data;
 use line_sensors::compute_sensor_readings;
 use motor_angles::compute_motor_angles_position;
@@ -22,16 +27,30 @@ fn print_sensors_data(sensors_data: Res<SensorsData>) {
         "motor angles: l {} r {}",
         sensors_data.motor_angles.left, sensors_data.motor_angles.right
     );
+    println!(
+        "encoder ticks: l {} r {}",
+        sensors_data.encoder_ticks.left, sensors_data.encoder_ticks.right
+    );
     println!(
         "gyro: r {:.4} p {:.4} y {:.4}",
         sensors_data.gyro.roll_angular_speed,
         sensors_data.gyro.pitch_angular_speed,
         sensors_data.gyro.yaw_angular_speed
     );
+    println!(
+        "accel: r {:.4} f {:.4} u {:.4}",
+        sensors_data.accel.right, sensors_data.accel.forward, sensors_data.accel.up
+    );
     println!(
         "imu: r {:.4} p {:.4} y {:.4}",
         sensors_data.imu_fused.roll, sensors_data.imu_fused.pitch, sensors_data.imu_fused.yaw
     );
+    println!(
+        "battery: {:.3} V {:.3} A {:.1} mAh",
+        sensors_data.battery.voltage,
+        sensors_data.battery.current,
+        sensors_data.battery.remaining_charge
+    );
 }
 
 pub struct SensorsModelPlugin;
@@ -40,13 +59,19 @@ impl Plugin for SensorsModelPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(SensorsData::default())
             .insert_resource(MotorAngles::default())
+            .init_resource::<SegmentsUnderBot>()
             .add_systems(
                 BotUpdate,
                 (
                     compute_sensor_readings,
                     compute_bot_position,
+                    track_checkpoints,
+                    compute_track_progress,
+                    count_laps,
+                    detect_obstacle_hits,
                     compute_motor_angles_position,
                     compute_imu_data,
+                    compute_battery_data,
                     // print_sensors_data,
                 )
                     .chain(),
@@ -1,12 +1,39 @@
-use std::f32::consts::PI;
+use std::f32::consts::{PI, TAU};
 
 use bevy::prelude::*;
+use executor::wasm_bindings::exports::robot::Encoders;
+use executor::wasmtime;
 
+use super::bot_position::BotPositionDetector;
 use crate::{
     bot::motors::Wheel,
-    utils::{SetBySide, Side},
+    utils::{NormalRandom, SetBySide, Side},
 };
-use execution_data::{MotorAngles, SensorsData};
+use execution_data::{EncoderTicks, MotorAngles, SensorsData};
+
+const COUNTS_PER_REVOLUTION_RANGE: std::ops::RangeInclusive<u32> = 1..=10000;
+const JITTER_RANGE: std::ops::RangeInclusive<f32> = 0.0..=5.0;
+
+/// Checks that the encoders of a configuration are in sensible ranges
+pub fn validate_encoders(encoders: &Encoders) -> wasmtime::Result<()> {
+    if !COUNTS_PER_REVOLUTION_RANGE.contains(&encoders.counts_per_revolution) {
+        return Err(wasmtime::Error::msg(format!(
+            "encoder counts per revolution {} out of range ({} to {})",
+            encoders.counts_per_revolution,
+            COUNTS_PER_REVOLUTION_RANGE.start(),
+            COUNTS_PER_REVOLUTION_RANGE.end()
+        )));
+    }
+    if !JITTER_RANGE.contains(&encoders.jitter) {
+        return Err(wasmtime::Error::msg(format!(
+            "encoder jitter {} counts out of range ({} to {})",
+            encoders.jitter,
+            JITTER_RANGE.start(),
+            JITTER_RANGE.end()
+        )));
+    }
+    Ok(())
+}
 
 impl SetBySide<f32> for MotorAngles {
     fn set_by_side(&mut self, side: Side, value: f32) {
@@ -17,11 +44,95 @@ impl SetBySide<f32> for MotorAngles {
     }
 }
 
+impl SetBySide<i32> for EncoderTicks {
+    fn set_by_side(&mut self, side: Side, value: i32) {
+        match side {
+            Side::Left => self.left = value,
+            Side::Right => self.right = value,
+        };
+    }
+}
+
+/// Rotation of one wheel relative to the bot body
+#[derive(Default)]
+struct WheelRotation {
+    /// Last twist of the wheel around its axle (in radians, wrapping)
+    twist: f32,
+    /// Rotation since the start (in radians, forward positive)
+    total: f32,
+}
+
+impl WheelRotation {
+    fn update(&mut self, twist: f32) {
+        let mut delta = (twist - self.twist).rem_euclid(TAU);
+        if delta > PI {
+            delta -= TAU;
+        }
+        self.twist = twist;
+        self.total += delta;
+    }
+}
+
+/// The rotary encoders: they count the rotation of the wheels, or of the
+/// motor shafts before the gearbox, in whole steps
+#[derive(Resource)]
+pub struct EncoderModel {
+    counts_per_revolution: u32,
+    /// Revolutions of the measured shaft per wheel revolution
+    shaft_ratio: f32,
+    /// Standard deviation of the readings (in counts)
+    jitter: f32,
+    left: WheelRotation,
+    right: WheelRotation,
+}
+
+impl EncoderModel {
+    pub fn new(encoders: &Encoders, gear_ratio_num: u32, gear_ratio_den: u32) -> Self {
+        let shaft_ratio = if encoders.motor_side {
+            gear_ratio_den as f32 / gear_ratio_num as f32
+        } else {
+            1.0
+        };
+        Self {
+            counts_per_revolution: encoders.counts_per_revolution,
+            shaft_ratio,
+            jitter: encoders.jitter,
+            left: WheelRotation::default(),
+            right: WheelRotation::default(),
+        }
+    }
+
+    fn rotation_mut(&mut self, side: Side) -> &mut WheelRotation {
+        match side {
+            Side::Left => &mut self.left,
+            Side::Right => &mut self.right,
+        }
+    }
+
+    /// Reads the counts of one encoder (the jitter does not accumulate)
+    fn counts(&mut self, side: Side, rng: &mut NormalRandom) -> i32 {
+        let shaft_rotation = self.rotation_mut(side).total * self.shaft_ratio;
+        let counts = shaft_rotation / TAU * self.counts_per_revolution as f32;
+        rng.noisy_value(counts, self.jitter).round() as i32
+    }
+
+    /// Angle of the measured shaft within a revolution, for some counts
+    fn angle(&self, counts: i32) -> f32 {
+        counts.rem_euclid(self.counts_per_revolution as i32) as f32 * TAU
+            / self.counts_per_revolution as f32
+    }
+}
+
 pub fn compute_motor_angles_position(
     wheels_query: Query<(&Wheel, &Transform)>,
+    body_query: Query<&Transform, With<BotPositionDetector>>,
+    mut encoders: ResMut<EncoderModel>,
+    mut rng: ResMut<NormalRandom>,
     mut sensors_data: ResMut<SensorsData>,
     mut motor_angles: ResMut<MotorAngles>,
 ) {
+    let body_rotation = body_query.single().unwrap().rotation;
+
     for (wheel, transform) in &wheels_query {
         // Rotation in radians [0, 2pi]
         let rot = PI
@@ -29,6 +140,85 @@ pub fn compute_motor_angles_position(
                 .dot(transform.rotation * wheel.axle.abs());
 
         motor_angles.set_by_side(wheel.side, rot);
+
+        // The encoders see the twist of the wheel around its axle relative
+        // to the body (the wheels turn forward around -X)
+        let relative = body_rotation.inverse() * transform.rotation;
+        let twist = -2.0 * relative.x.atan2(relative.w);
+        encoders.rotation_mut(wheel.side).update(twist);
+
+        let counts = encoders.counts(wheel.side, &mut rng);
+        sensors_data.encoder_ticks.set_by_side(wheel.side, counts);
+        sensors_data
+            .motor_angles
+            .set_by_side(wheel.side, encoders.angle(counts));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoder_model(motor_side: bool) -> EncoderModel {
+        let encoders = Encoders {
+            counts_per_revolution: 12,
+            motor_side,
+            jitter: 0.0,
+        };
+        // 30 motor revolutions per wheel revolution
+        EncoderModel::new(&encoders, 1, 30)
+    }
+
+    #[test]
+    fn wheel_rotation_wraps_around() {
+        // The twist wraps from pi to -pi
+        let step = TAU - 6.0;
+        let mut rotation = WheelRotation::default();
+        rotation.update(3.0);
+        // Forward across the wrapping point
+        rotation.update(-3.0);
+        assert!((rotation.total - (3.0 + step)).abs() < 1e-5);
+        // And back
+        rotation.update(3.0);
+        rotation.update(0.0);
+        assert!(rotation.total.abs() < 1e-5);
+        // Backwards across the wrapping point
+        rotation.update(-3.0);
+        rotation.update(3.0);
+        assert!((rotation.total - (-3.0 - step)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn counts_in_whole_steps() {
+        let mut rng = NormalRandom::new();
+
+        let mut wheel_side = encoder_model(false);
+        wheel_side.left.total = TAU / 4.0;
+        assert_eq!(wheel_side.counts(Side::Left, &mut rng), 3);
+        // Less than half a step
+        wheel_side.left.total = TAU / 12.0 * 0.4;
+        assert_eq!(wheel_side.counts(Side::Left, &mut rng), 0);
+        wheel_side.left.total = -TAU / 12.0 * 0.6;
+        assert_eq!(wheel_side.counts(Side::Left, &mut rng), -1);
+
+        // The motor shaft turns 30 times faster
+        let mut motor_side = encoder_model(true);
+        motor_side.right.total = TAU / 4.0;
+        assert_eq!(motor_side.counts(Side::Right, &mut rng), 90);
+        motor_side.right.total = TAU / 12.0 * 0.4 / 30.0;
+        assert_eq!(motor_side.counts(Side::Right, &mut rng), 0);
+        motor_side.right.total = -TAU / 12.0 * 0.6 / 30.0;
+        assert_eq!(motor_side.counts(Side::Right, &mut rng), -1);
+    }
+
+    #[test]
+    fn angle_within_a_revolution() {
+        let encoders = encoder_model(false);
+        assert_eq!(encoders.angle(0), 0.0);
+        assert!((encoders.angle(3) - PI / 2.0).abs() < 1e-6);
+        assert!((encoders.angle(15) - PI / 2.0).abs() < 1e-6);
+        // Negative counts wrap to the same angles
+        assert!((encoders.angle(-3) - PI * 1.5).abs() < 1e-6);
+        assert!((encoders.angle(-12)).abs() < 1e-6);
     }
-    sensors_data.motor_angles = *motor_angles;
 }
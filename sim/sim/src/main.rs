@@ -1,10 +1,20 @@
 use app_builder::VisualizerData;
 use clap::{self, ArgEnum, Parser, Subcommand, ValueEnum};
+use execution_data::BotFinalStatus;
 use executor::{
-    wasm_bindings::exports::robot::{Color, Configuration},
+    wasm_bindings::exports::robot::{
+        Color, Configuration, Encoders, InertialSensor, Motor, MotorDriver,
+    },
     wasmtime,
 };
+use par_time::par_time;
 use runner::{get_bot_config_from_file, run_bot_from_file};
+use track_bench::bench_line_sensors;
+use track_check::check_track;
+use track_export::{PageSize, export_track};
+use track_file::{load_track_from_file, save_track_to_file};
+use track_generator::TrackGeneratorParams;
+use track_import::{TrackImportParams, import_track};
 use track_selection::build_track;
 
 use crate::app_builder::create_app;
@@ -12,11 +22,19 @@ use crate::app_builder::create_app;
 mod app_builder;
 mod bot;
 mod data;
+mod par_time;
 mod runner;
 mod server;
 mod track;
+mod track_bench;
+mod track_check;
+mod track_export;
+mod track_file;
+mod track_generator;
+mod track_import;
 mod track_selection;
 mod ui;
+mod ui_editor;
 mod ui_runner;
 mod ui_test;
 mod utils;
@@ -35,6 +53,10 @@ pub enum TrackId {
     Simple,
     /// A full racing track
     Race,
+    /// A closed loop raced for 3 laps
+    Loop,
+    /// A randomly generated track (see --seed)
+    Random,
 }
 
 impl std::str::FromStr for TrackId {
@@ -54,9 +76,14 @@ struct Args {
     #[clap(long, short, default_value = "500")]
     period: u32,
     /// Track used in the simulation
-    /// (one of line, angle, turn, simple, race)
+    /// (one of line, angle, turn, simple, race, loop, random)
     #[clap(long, short, default_value = "simple")]
     track: TrackId,
+    /// Seed of the random track (a new one is picked and printed if missing)
+    #[clap(long)]
+    seed: Option<u64>,
+    #[clap(flatten)]
+    random_track: TrackGeneratorParams,
     #[clap(subcommand)]
     cmd: Command,
 }
@@ -83,12 +110,18 @@ enum Command {
         /// CLI only (run headless, without graphical visualizer)
         #[clap(long, short)]
         cli: bool,
+        /// Path to a track file (overrides --track)
+        #[clap(long)]
+        track_file: Option<String>,
     },
     /// Test a robot configuration
     Test {
         /// Path to the robot configuration file
         #[clap(long, short)]
         input: Option<String>,
+        /// Path to a track file (overrides --track)
+        #[clap(long)]
+        track_file: Option<String>,
     },
     /// Run the simulator accepting robots from HTTP requests
     Serve {
@@ -104,14 +137,126 @@ enum Command {
         /// Racing start time in us
         #[clap(long, short, default_value = "1000000")]
         start_time: u32,
+        /// Path to a track file (overrides --track)
+        #[clap(long)]
+        track_file: Option<String>,
+    },
+    /// Edit the selected track in the graphical track editor
+    Edit {
+        /// Path of the track file to save (defaults to --track-file)
+        #[clap(long, short)]
+        output: Option<String>,
+        /// Path to a track file (overrides --track)
+        #[clap(long)]
+        track_file: Option<String>,
     },
+    /// Track utilities
+    Track {
+        #[clap(subcommand)]
+        cmd: TrackCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum TrackCommand {
+    /// Save the selected track to a track file
+    Save {
+        /// Path of the track file to write
+        #[clap(long, short)]
+        output: String,
+        /// Path to a track file (overrides --track)
+        #[clap(long)]
+        track_file: Option<String>,
+    },
+    /// Export the selected track as a 1:1 scale drawing (.svg or .pdf)
+    Export {
+        /// Path of the drawing to write
+        #[clap(long, short)]
+        output: String,
+        /// Tile the drawing on pages of this size (a4 or a3) with alignment marks
+        #[clap(long)]
+        page: Option<PageSize>,
+        /// Path to a track file (overrides --track)
+        #[clap(long)]
+        track_file: Option<String>,
+    },
+    /// Fit a track to a center line from an SVG file or a point list
+    Import {
+        /// Path of the SVG file (.svg) or point list (x y pairs, one per line)
+        #[clap(long, short)]
+        input: String,
+        /// Path of the track file to write
+        #[clap(long, short)]
+        output: Option<String>,
+        #[clap(flatten)]
+        params: TrackImportParams,
+    },
+    /// Check the selected track for overlaps, off-floor segments and tight turns
+    Check {
+        /// Path to a track file (overrides --track)
+        #[clap(long)]
+        track_file: Option<String>,
+    },
+    /// Time the line sensor readings with physics raycasts and with the
+    /// analytic track queries
+    Bench {
+        /// Number of sensor readings to time
+        #[clap(long, short, default_value = "100000")]
+        samples: usize,
+        /// Path to a track file (overrides --track)
+        #[clap(long)]
+        track_file: Option<String>,
+    },
+}
+
+fn import_track_file(
+    input: &str,
+    output: Option<&str>,
+    params: &TrackImportParams,
+) -> wasmtime::Result<()> {
+    let imported = import_track(input, params)?;
+    println!(
+        "fitted {} segments: max error {:.1} mm, rms error {:.1} mm",
+        imported.track.segments().len(),
+        imported.max_error * 1000.0,
+        imported.rms_error * 1000.0
+    );
+    if let Some(gap) = imported.closing_gap {
+        println!("closed loop: closing gap {:.1} mm", gap * 1000.0);
+    }
+    for issue in check_track(&imported.track) {
+        eprintln!("track warning: {}", issue);
+    }
+    if let Some(output) = output {
+        save_track_to_file(&imported.track, output)?;
+        println!("track saved to \"{}\"", output);
+    }
+    Ok(())
 }
 
 fn main() -> executor::wasmtime::Result<()> {
     let args = Args::parse();
 
     let period = args.period;
-    let track = build_track(args.track);
+    // The selected track is built only by the commands that use it (imports
+    // fit a new one)
+    let select_track = |track_file: Option<String>| match track_file {
+        Some(path) => load_track_from_file(&path),
+        None => {
+            let seed = args.seed.unwrap_or_else(rand::random);
+            if args.track == TrackId::Random && args.seed.is_none() {
+                println!("random track seed: {}", seed);
+            }
+            build_track(args.track, seed, &args.random_track)
+        }
+    };
+    let checked_track = |track_file: Option<String>| -> wasmtime::Result<_> {
+        let track = select_track(track_file)?;
+        for issue in check_track(&track) {
+            eprintln!("track warning: {}", issue);
+        }
+        Ok(track)
+    };
 
     match args.cmd {
         Command::Run {
@@ -121,7 +266,9 @@ fn main() -> executor::wasmtime::Result<()> {
             start_time,
             time_limit,
             cli,
+            track_file,
         } => {
+            let track = checked_track(track_file)?;
             println!(
                 "running robot \"{}\" output at path \"{}\" (write logs: {})...",
                 input, output, logs
@@ -140,11 +287,38 @@ fn main() -> executor::wasmtime::Result<()> {
                 "data has {} frames",
                 bot_execution_data.data.body_data.steps.len()
             );
+            match par_time(&track, &bot_execution_data.config) {
+                Some(par) => {
+                    let final_status = bot_execution_data.data.activity_data.final_status();
+                    match final_status {
+                        BotFinalStatus::EndedAt { time_secs, .. } => println!(
+                            "robot time {:.2}s, par time {:.2}s (+{:.2}s)",
+                            time_secs,
+                            par.race_secs,
+                            time_secs - par.race_secs
+                        ),
+                        _ => println!("par time {:.2}s", par.race_secs),
+                    }
+                    if let Some(lap_secs) = par.lap_secs {
+                        let activity_data = &bot_execution_data.data.activity_data;
+                        match activity_data.lap_times_secs().into_iter().reduce(f32::min) {
+                            Some(best_lap_secs) => println!(
+                                "best lap {:.2}s, par lap {:.2}s (+{:.2}s)",
+                                best_lap_secs,
+                                lap_secs,
+                                best_lap_secs - lap_secs
+                            ),
+                            None => println!("par lap {:.2}s", lap_secs),
+                        }
+                    }
+                }
+                None => println!("no par time: the robot cannot finish this track"),
+            }
 
             if !cli {
                 create_app(
                     app_builder::AppType::Visualizer(app_builder::VisualizerData::Runner {
-                        bot: bot_execution_data,
+                        bot: Box::new(bot_execution_data),
                         output,
                         logs,
                         total_simulation_time_us: time_limit * 1_000_000,
@@ -157,7 +331,8 @@ fn main() -> executor::wasmtime::Result<()> {
                 .run();
             }
         }
-        Command::Test { input } => {
+        Command::Test { input, track_file } => {
+            let track = checked_track(track_file)?;
             let cfg = match input {
                 Some(input) => match get_bot_config_from_file(input) {
                     Ok(config) => {
@@ -184,6 +359,30 @@ fn main() -> executor::wasmtime::Result<()> {
                 wheel_diameter: 20.0,
                 gear_ratio_num: 1,
                 gear_ratio_den: 20,
+                motor: Motor::Catalogue("generic".into()),
+                battery: None,
+                driver: MotorDriver {
+                    dead_band: 0,
+                    slew_rate: 0,
+                    brake_when_idle: false,
+                },
+                encoders: Encoders {
+                    counts_per_revolution: 12,
+                    motor_side: true,
+                    jitter: 0.0,
+                },
+                gyro: InertialSensor {
+                    full_scale: 500.0,
+                    bias: 0.0,
+                    drift: 0.0,
+                    noise: 0.0,
+                },
+                accel: InertialSensor {
+                    full_scale: 4.0,
+                    bias: 0.0,
+                    drift: 0.0,
+                    noise: 0.0,
+                },
                 front_sensors_spacing: 10.0,
                 front_sensors_height: 4.0,
             });
@@ -195,7 +394,9 @@ fn main() -> executor::wasmtime::Result<()> {
             port,
             time_limit,
             start_time,
+            track_file,
         } => {
+            let track = checked_track(track_file)?;
             println!("Starting server...");
             create_app(
                 app_builder::AppType::Visualizer(VisualizerData::Server {
@@ -210,6 +411,67 @@ fn main() -> executor::wasmtime::Result<()> {
             )?
             .run();
         }
+        Command::Edit { output, track_file } => {
+            let track = checked_track(track_file.clone())?;
+            let output = output.or(track_file).ok_or_else(|| {
+                wasmtime::Error::msg("missing output track file: use --output or --track-file")
+            })?;
+            create_app(app_builder::AppType::Editor(output), track, period)?.run();
+        }
+        Command::Track { cmd } => match cmd {
+            TrackCommand::Save { output, track_file } => {
+                let track = checked_track(track_file)?;
+                save_track_to_file(&track, &output)?;
+                println!("track saved to \"{}\"", output);
+            }
+            TrackCommand::Export {
+                output,
+                page,
+                track_file,
+            } => {
+                let track = checked_track(track_file)?;
+                for path in export_track(&track, &output, page)? {
+                    println!("track drawing written to \"{}\"", path);
+                }
+            }
+            TrackCommand::Import {
+                input,
+                output,
+                params,
+            } => import_track_file(&input, output.as_deref(), &params)?,
+            TrackCommand::Check { track_file } => {
+                // The issues are the output, not warnings
+                let track_issues = check_track(&select_track(track_file)?);
+                if track_issues.is_empty() {
+                    println!("track ok");
+                } else {
+                    for issue in &track_issues {
+                        println!("{}", issue);
+                    }
+                    return Err(wasmtime::Error::msg(format!(
+                        "track has {} issue(s)",
+                        track_issues.len()
+                    )));
+                }
+            }
+            TrackCommand::Bench {
+                samples,
+                track_file,
+            } => {
+                let track = checked_track(track_file)?;
+                let result = bench_line_sensors(&track, samples, 42)?;
+                println!(
+                    "raycast: {:.2} us/reading, analytic: {:.2} us/reading ({:.1}x faster)",
+                    result.raycast.as_secs_f64() * 1e6 / result.samples as f64,
+                    result.analytic.as_secs_f64() * 1e6 / result.samples as f64,
+                    result.speedup()
+                );
+                println!(
+                    "{} of {} readings fell back to raycasts, {} differ by more than 1.0",
+                    result.fallbacks, result.samples, result.mismatches
+                );
+            }
+        },
     }
 
     Ok(())
@@ -0,0 +1,308 @@
+//! Theoretical reference ("par") times: the fastest physically plausible
+//! run of a robot configuration on a [`Track`].
+//!
+//! The speed profile along the center line is limited by the motor model
+//! (the torque available at each speed), by the grip of the wheels on the
+//! surface of each segment and by the lateral acceleration in turns.
+
+use bevy::math::Vec2;
+use execution_data::MotorIdleMode;
+use executor::wasm_bindings::exports::robot::Configuration;
+
+use crate::bot::battery::Battery;
+use crate::bot::model::bot_mass;
+use crate::bot::motors::{MotorLoad, motor_parameters};
+use crate::track::{Track, TrackSegment};
+
+const GRAVITY: f32 = 9.81;
+/// Distance between the points of the speed profile
+const PROFILE_STEP: f32 = 0.005;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ParTime {
+    /// Time to finish the race from a standing start
+    pub race_secs: f32,
+    /// Time of a flying lap (closed loops only)
+    pub lap_secs: Option<f32>,
+}
+
+/// A short piece of the race path
+#[derive(Clone, Copy)]
+struct PathStep {
+    length: f32,
+    /// Inverse of the turn radius (zero on straight lines)
+    curvature: f32,
+    /// Wheel friction coefficient on the segment surface
+    grip: f32,
+    /// Sine of the slope angle (positive uphill)
+    slope: f32,
+}
+
+impl PathStep {
+    /// Longitudinal acceleration the grip leaves at a given speed, after
+    /// the lateral one
+    fn longitudinal_grip(&self, speed: f32) -> f32 {
+        let grip = self.grip * GRAVITY;
+        let lateral = speed * speed * self.curvature;
+        (grip * grip - lateral * lateral).max(0.0).sqrt()
+    }
+
+    fn speed_limit(&self) -> f32 {
+        if self.curvature > 0.0 {
+            (self.grip * GRAVITY / self.curvature).sqrt()
+        } else {
+            f32::INFINITY
+        }
+    }
+}
+
+/// Splits a segment in steps of about [`PROFILE_STEP`]
+fn segment_steps(segment: &TrackSegment, corner_radius: f32) -> Vec<PathStep> {
+    let length = segment.length();
+    let count = (length / PROFILE_STEP).ceil().max(1.0) as usize;
+    let grip = segment.surface().friction();
+    let slope = (segment.rise() / length).atan().sin();
+    (0..count)
+        .map(|index| {
+            let curvature = match *segment {
+                TrackSegment::CyrcleTurn(data) => 1.0 / data.radius,
+                // The corner is turned on the spot, as tight as the wheels
+                // allow
+                TrackSegment::NinetyDegTurn(_) if index == count / 2 => 1.0 / corner_radius,
+                _ => 0.0,
+            };
+            PathStep {
+                length: length / count as f32,
+                curvature,
+                grip,
+                slope,
+            }
+        })
+        .collect()
+}
+
+/// Par time of a robot configuration on a track (`None` when the robot
+/// cannot finish, e.g. a ramp too steep for its motors, or its motor is
+/// not valid)
+pub fn par_time(track: &Track, config: &Configuration) -> Option<ParTime> {
+    let wheel_radius = config.wheel_diameter / 2000.0;
+    let mass = bot_mass(config);
+    let motor = motor_parameters(&config.motor).ok()?;
+    // The full battery, sagging under the load of both motors like in the
+    // simulation
+    let battery = Battery::new(config.battery, motor.rated_voltage);
+    // Acceleration from both wheels driven at a duty cycle
+    let wheels_acceleration = |duty: f32, speed: f32| {
+        let load = MotorLoad::new(
+            duty,
+            speed / wheel_radius,
+            config.gear_ratio_num,
+            config.gear_ratio_den,
+            &motor,
+            MotorIdleMode::Coast,
+        );
+        let torque = battery
+            .terminal_voltage(&[load, load])
+            .map_or(0.0, |voltage| load.wheel_torque(voltage));
+        2.0 * torque / wheel_radius / mass
+    };
+    // Full throttle, and full reverse when braking
+    let drive = |speed: f32| wheels_acceleration(1.0, speed);
+    let brake = |speed: f32| wheels_acceleration(-1.0, speed).abs();
+    let corner_radius = config.width_axle / 2000.0;
+
+    // Closed loops are profiled for at least two laps, to time a flying one
+    let (laps, race_laps) = match track.laps() {
+        Some(laps) => (laps.max(2), laps),
+        None => (1, 1),
+    };
+    let lap: Vec<&TrackSegment> = track
+        .segments()
+        .iter()
+        .filter(|segment| !matches!(segment, TrackSegment::End))
+        .collect();
+    // Bots start at the world origin, the open track races end at the end tip
+    let start = track.progress_at(Vec2::ZERO, 1).unwrap_or(0.0);
+
+    let mut lap_steps = Vec::new();
+    let mut steps_before_start = Vec::new();
+    let mut position = 0.0;
+    for segment in &lap {
+        for step in segment_steps(segment, corner_radius) {
+            position += step.length;
+            if position > start {
+                lap_steps.push(step);
+            } else {
+                steps_before_start.push(step);
+            }
+        }
+    }
+    // Closed loop laps run from the finish line, where the bots start, back
+    // to it
+    if track.laps().is_some() {
+        lap_steps.extend(steps_before_start);
+    }
+
+    let mut steps = Vec::new();
+    let mut lap_ends = Vec::new();
+    for _ in 0..laps {
+        steps.extend_from_slice(&lap_steps);
+        lap_ends.push(steps.len());
+    }
+
+    // Forward pass: accelerate as much as possible from a standing start
+    let mut speeds = vec![0.0; steps.len() + 1];
+    for (index, step) in steps.iter().enumerate() {
+        let speed = speeds[index];
+        let acceleration = drive(speed).min(step.longitudinal_grip(speed)) - GRAVITY * step.slope;
+        let next = (speed * speed + 2.0 * acceleration * step.length)
+            .max(0.0)
+            .sqrt();
+        if next <= 0.0 {
+            return None;
+        }
+        let limit = steps
+            .get(index + 1)
+            .map_or(f32::INFINITY, PathStep::speed_limit)
+            .min(step.speed_limit());
+        speeds[index + 1] = next.min(limit);
+    }
+    // Backward pass: brake in time for the turns
+    for (index, step) in steps.iter().enumerate().rev() {
+        let speed = speeds[index + 1];
+        let deceleration =
+            (brake(speed).min(step.longitudinal_grip(speed)) + GRAVITY * step.slope).max(0.0);
+        let previous = (speed * speed + 2.0 * deceleration * step.length).sqrt();
+        speeds[index] = speeds[index].min(previous);
+    }
+
+    let step_times: Vec<f32> = steps
+        .iter()
+        .enumerate()
+        .map(|(index, step)| 2.0 * step.length / (speeds[index] + speeds[index + 1]))
+        .collect();
+    let time_to = |end: usize| step_times[..end].iter().sum::<f32>();
+
+    let race_secs = time_to(lap_ends[race_laps - 1]);
+    let lap_secs = track
+        .laps()
+        .map(|_| time_to(lap_ends[laps - 1]) - time_to(lap_ends[laps - 2]));
+    race_secs.is_finite().then_some(ParTime {
+        race_secs,
+        lap_secs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::text::cosmic_text::Angle;
+    use executor::wasm_bindings::exports::robot::{
+        Color, Encoders, InertialSensor, Motor, MotorDriver, MotorParameters,
+    };
+
+    use super::*;
+    use crate::utils::Side;
+
+    fn config() -> Configuration {
+        let sensor = |full_scale| InertialSensor {
+            full_scale,
+            bias: 0.0,
+            drift: 0.0,
+            noise: 0.0,
+        };
+        Configuration {
+            name: "test".into(),
+            color_main: Color { r: 0, g: 0, b: 255 },
+            color_secondary: Color { r: 255, g: 0, b: 0 },
+            width_axle: 100.0,
+            length_front: 100.0,
+            length_back: 20.0,
+            clearing_back: 20.0,
+            wheel_diameter: 20.0,
+            gear_ratio_num: 1,
+            gear_ratio_den: 20,
+            motor: Motor::Catalogue("generic".into()),
+            battery: None,
+            driver: MotorDriver {
+                dead_band: 0,
+                slew_rate: 0,
+                brake_when_idle: false,
+            },
+            encoders: Encoders {
+                counts_per_revolution: 12,
+                motor_side: true,
+                jitter: 0.0,
+            },
+            gyro: sensor(500.0),
+            accel: sensor(4.0),
+            front_sensors_spacing: 10.0,
+            front_sensors_height: 4.0,
+        }
+    }
+
+    fn open_track(pieces: &[TrackSegment]) -> Track {
+        let mut segments = vec![TrackSegment::start()];
+        segments.extend_from_slice(pieces);
+        segments.push(TrackSegment::end());
+        Track::new(segments)
+    }
+
+    #[test]
+    fn ramp_too_steep_for_the_motor() {
+        let track = open_track(&[
+            TrackSegment::straight(0.5),
+            TrackSegment::straight(1.0).with_rise(0.1),
+            TrackSegment::straight(0.5),
+        ]);
+        assert!(par_time(&track, &config()).is_some());
+
+        // A motor far too weak to climb 10%
+        let weak = Configuration {
+            motor: Motor::Custom(MotorParameters {
+                no_load_rpm: 10000.0,
+                stall_torque: 0.1,
+                rated_voltage: 6.0,
+                stall_current: 0.1,
+            }),
+            gear_ratio_den: 1,
+            ..config()
+        };
+        assert!(par_time(&track, &weak).is_none());
+        let flat = open_track(&[TrackSegment::straight(2.0)]);
+        assert!(par_time(&flat, &weak).is_some());
+    }
+
+    #[test]
+    fn flying_lap_is_faster() {
+        let half_turn = TrackSegment::cyrcle_turn(0.5, Angle::from_degrees(180.0), Side::Left);
+        let track = Track::new(vec![
+            TrackSegment::straight(1.0),
+            half_turn,
+            TrackSegment::straight(1.0),
+            half_turn,
+        ])
+        .with_laps(3);
+        let par_time = par_time(&track, &config()).unwrap();
+        let lap_secs = par_time.lap_secs.unwrap();
+        assert!(
+            lap_secs < par_time.race_secs / 3.0,
+            "lap {} s, race {} s",
+            lap_secs,
+            par_time.race_secs
+        );
+    }
+
+    #[test]
+    fn tighter_turns_are_slower() {
+        // Two turns of the same length
+        let time = |radius: f32, angle: f32| {
+            let track = open_track(&[
+                TrackSegment::straight(1.0),
+                TrackSegment::cyrcle_turn(radius, Angle::from_degrees(angle), Side::Right),
+                TrackSegment::straight(1.0),
+            ]);
+            par_time(&track, &config()).unwrap().race_secs
+        };
+        assert!(time(0.3, 90.0) > time(0.6, 45.0));
+    }
+}
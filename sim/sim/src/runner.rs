@@ -1,12 +1,17 @@
 use bevy::app::{App, AppExit, FixedMain, Main};
 use execution_data::{
-    BodyExecutionData, BotPhysicalPosition, ExecutionData, MotorDriversDutyCycles, SensorsData,
-    WheelExecutionData,
+    BodyExecutionData, BotPhysicalPosition, ExecutionData, MotorDriversDutyCycles,
+    MotorDriversIdleModes, SensorsData, WheelExecutionData,
 };
 use executor::{wasm_bindings::exports::robot::Configuration, wasm_executor, wasmtime};
 
 use crate::{
     app_builder::{self, create_app},
+    bot::{
+        battery::validate_battery,
+        motors::{motor_parameters, validate_driver, validate_gear_ratio},
+        sensors::{imu::validate_inertial_sensors, motor_angles::validate_encoders},
+    },
     track::Track,
 };
 
@@ -36,11 +41,20 @@ impl AppWrapper {
         *res = dc;
     }
 
+    pub fn set_idle_modes(&mut self, idle_modes: MotorDriversIdleModes) {
+        *self
+            .app
+            .world_mut()
+            .get_resource_mut::<MotorDriversIdleModes>()
+            .unwrap() = idle_modes;
+    }
+
     pub fn step(&mut self, period_us: u32, next_time_us: u32, start_time_us: u32) {
         self.app.world_mut().run_schedule(FixedMain);
         self.app.world_mut().run_schedule(Main);
 
         self.sensors_data = *self.app.world().get_resource::<SensorsData>().unwrap();
+        let laps = self.app.world().get_resource::<Track>().unwrap().laps();
 
         // Get mutable ref to execution data
         let mut execution_data = self
@@ -56,6 +70,14 @@ impl AppWrapper {
         }
 
         if next_time_us > period_us {
+            if activity_data.is_active_now() {
+                while activity_data.checkpoint_times_us.len()
+                    < self.sensors_data.checkpoints_reached
+                {
+                    activity_data.checkpoint_times_us.push(next_time_us);
+                }
+                activity_data.record_progress(next_time_us, self.sensors_data.track_progress);
+            }
             if activity_data.end_time_us.is_none() {
                 if self.sensors_data.is_over_track_end {
                     activity_data.end_time_us = Some(next_time_us);
@@ -66,6 +88,18 @@ impl AppWrapper {
                     activity_data.out_time_us = Some(next_time_us);
                 }
             }
+            if activity_data.is_active_now() && self.sensors_data.is_hitting_obstacle {
+                activity_data.collision_time_us = Some(next_time_us);
+            }
+            if let Some(laps) = laps
+                && activity_data.is_active_now()
+                && activity_data.lap_end_times_us.len() < self.sensors_data.completed_laps
+            {
+                activity_data.lap_end_times_us.push(next_time_us);
+                if activity_data.lap_end_times_us.len() >= laps {
+                    activity_data.end_time_us = Some(next_time_us);
+                }
+            }
         }
     }
 
@@ -91,7 +125,7 @@ impl AppWrapper {
                 side: res.right_wheel_data.side,
                 steps: res.right_wheel_data.steps.drain(..).collect(),
             },
-            activity_data: res.activity_data,
+            activity_data: res.activity_data.clone(),
         }
     }
 
@@ -160,14 +194,26 @@ impl execution_data::SimulationStepper for RunnerStepper {
         self.app_wrapper.sensors_data().motor_angles
     }
 
+    fn get_encoder_ticks(&self) -> execution_data::EncoderTicks {
+        self.app_wrapper.sensors_data().encoder_ticks
+    }
+
     fn get_gyro(&self) -> execution_data::GyroData {
         self.app_wrapper.sensors_data().gyro
     }
 
+    fn get_accel(&self) -> execution_data::AccelData {
+        self.app_wrapper.sensors_data().accel
+    }
+
     fn get_imu_fused_data(&self) -> execution_data::ImuFusedData {
         self.app_wrapper.sensors_data().imu_fused
     }
 
+    fn get_battery(&self) -> execution_data::BatteryData {
+        self.app_wrapper.sensors_data().battery
+    }
+
     fn get_absolute_bot_position(&self) -> BotPhysicalPosition {
         self.app_wrapper.sensors_data().bot_physical_position
     }
@@ -179,6 +225,10 @@ impl execution_data::SimulationStepper for RunnerStepper {
         self.app_wrapper.set_motors(duty_cycles);
     }
 
+    fn set_motor_drivers_idle_modes(&mut self, idle_modes: MotorDriversIdleModes) {
+        self.app_wrapper.set_idle_modes(idle_modes);
+    }
+
     fn get_data(&mut self) -> execution_data::ExecutionData {
         self.app_wrapper.get_execution_data()
     }
@@ -194,10 +244,23 @@ pub struct BotExecutionData {
     pub data: ExecutionData,
 }
 
+/// Checks that the parts of a robot configuration are in sensible ranges
+fn validate_configuration(config: &Configuration) -> wasmtime::Result<()> {
+    validate_gear_ratio(config.gear_ratio_num, config.gear_ratio_den)?;
+    motor_parameters(&config.motor)?;
+    validate_battery(&config.battery)?;
+    validate_driver(&config.driver)?;
+    validate_encoders(&config.encoders)?;
+    validate_inertial_sensors(&config.gyro, &config.accel)?;
+    Ok(())
+}
+
 pub fn get_bot_config_from_file(input: String) -> wasmtime::Result<Configuration> {
     // Load the component from disk
     let wasm_bytes = std::fs::read(&input)?;
-    wasm_executor::get_robot_configuration(&wasm_bytes)
+    let config = wasm_executor::get_robot_configuration(&wasm_bytes)?;
+    validate_configuration(&config)?;
+    Ok(config)
 }
 
 pub fn run_bot_from_file(
@@ -233,6 +296,7 @@ pub fn run_bot_from_code(
 ) -> wasmtime::Result<BotExecutionData> {
     // Get configuration
     let config = wasm_executor::get_robot_configuration(&wasm_bytes)?;
+    validate_configuration(&config)?;
     println!("Robot configuration: {:#?}", &config);
 
     let (result_sender, result_receiver) = std::sync::mpsc::channel();
@@ -1,4 +1,4 @@
-use std::f32::consts::{FRAC_PI_2, PI};
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
 
 use bevy::text::cosmic_text::Angle;
 use bevy::{pbr::NotShadowCaster, prelude::*};
@@ -12,8 +12,14 @@ pub const LINE_HALF_WIDTH: f32 = 0.01;
 const TRACK_HALF_HEIGHT: f32 = 0.001;
 const TRACK_TIPS_LENGTH: f32 = 0.5;
 const TRACK_CIRCLE_SEGMENTS_PER_PI: usize = 40;
+/// Side markers size along and across the track, and distance of their
+/// inner edge from the center line
+const MARKER_LENGTH: f32 = 0.02;
+const MARKER_WIDTH: f32 = 0.04;
+const MARKER_OFFSET: f32 = 0.04;
 
 const TRACK_ORIGIN_OFFSET: Vec2 = Vec2::new(0.0, -0.25);
+const TRACK_FLOOR_MARGIN: f32 = 0.5;
 
 /// Generates a curved "track turn" collider (an arc section)
 ///
@@ -257,6 +263,16 @@ pub fn quad_mesh(width: f32, height: f32) -> Mesh {
     .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
 }
 
+/// Merges meshes built by the functions above (they share the same attributes)
+fn merge_meshes(meshes: impl Iterator<Item = Mesh>) -> Mesh {
+    meshes
+        .reduce(|mut merged, mesh| {
+            merged.merge(&mesh).unwrap();
+            merged
+        })
+        .unwrap_or_else(|| quad_mesh(0.0, 0.0))
+}
+
 pub fn ninety_deg_mesh(width: f32, half_lenght: f32, side: Side) -> Mesh {
     let half_w = width * 0.5;
 
@@ -330,15 +346,18 @@ pub fn ninety_deg_mesh(width: f32, half_lenght: f32, side: Side) -> Mesh {
 pub struct SegmentTransform {
     position: Vec2,
     direction: Angle,
+    /// Height of the track surface above the floor
+    height: f32,
 }
 
 impl std::fmt::Display for SegmentTransform {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "SEG x {} y {} ang {}",
+            "SEG x {} y {} z {} ang {}",
             self.position.x,
             self.position.y,
+            self.height,
             self.direction.to_degrees()
         )
     }
@@ -349,33 +368,242 @@ impl SegmentTransform {
         Self {
             position,
             direction,
+            height: 0.0,
         }
     }
 
+    pub fn position(&self) -> Vec2 {
+        self.position
+    }
+
+    pub fn direction(&self) -> Angle {
+        self.direction
+    }
+
+    pub fn height(&self) -> f32 {
+        self.height
+    }
+
     pub fn translate_in_direction(&self, translation: Vec2) -> Self {
         Self {
             position: self.position + rotate_vec2(translation, self.direction.to_radians()),
-            direction: self.direction,
+            ..*self
         }
     }
 
     pub fn rotate(&self, rotation: Angle) -> Self {
         Self {
-            position: self.position,
             direction: Angle::from_radians(self.direction.to_radians() + rotation.to_radians()),
+            ..*self
+        }
+    }
+
+    pub fn raise(&self, height: f32) -> Self {
+        Self {
+            height: self.height + height,
+            ..*self
+        }
+    }
+
+    /// Inverse of [`Self::translate_in_direction`]: the coordinates of
+    /// `point` relative to this transform
+    pub fn local_position(&self, point: Vec2) -> Vec2 {
+        rotate_vec2(point - self.position, -self.direction.to_radians())
+    }
+}
+
+/// A line interrupted by gaps: `line` meters of line followed by `gap`
+/// meters without it, repeated along the segment
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GapPattern {
+    pub line: f32,
+    pub gap: f32,
+}
+
+impl GapPattern {
+    pub fn new(line: f32, gap: f32) -> Self {
+        Self { line, gap }
+    }
+
+    /// Distance from `distance` (measured along the segment) to the nearest
+    /// line section (zero when over a line section)
+    pub fn distance_to_line(&self, distance: f32, length: f32) -> f32 {
+        let period = self.line + self.gap;
+        let phase = distance.rem_euclid(period);
+        if phase < self.line {
+            return 0.0;
+        }
+        let to_previous = phase - self.line;
+        let to_next = period - phase;
+        // Line sections are cut at the segment end
+        if distance + to_next > length {
+            to_previous
+        } else {
+            to_previous.min(to_next)
+        }
+    }
+
+    /// The (start, end) distances of the line sections inside `length`
+    pub fn line_sections(&self, length: f32) -> Vec<(f32, f32)> {
+        let period = self.line + self.gap;
+        let mut sections = Vec::new();
+        let mut start = 0.0;
+        while start < length {
+            sections.push((start, (start + self.line).min(length)));
+            start += period;
+        }
+        sections
+    }
+}
+
+/// How the line of a segment is drawn
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineStyle {
+    pub half_width: f32,
+    /// A white line over a black track band
+    pub inverted: bool,
+}
+
+impl Default for LineStyle {
+    fn default() -> Self {
+        Self {
+            half_width: LINE_HALF_WIDTH,
+            inverted: false,
+        }
+    }
+}
+
+/// Side markers (short bars in the line color beside the line, as in
+/// Robotrace rules) at the start and at the end of a segment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SideMarkers {
+    pub start_left: bool,
+    pub start_right: bool,
+    pub end_left: bool,
+    pub end_right: bool,
+}
+
+impl SideMarkers {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// The surface of a segment, which sets the grip of the wheels on it
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Surface {
+    #[default]
+    Normal,
+    /// Dust left on the track, noticeably less grip
+    Dusty,
+    /// A slippery patch, the wheels barely hold
+    Slippery,
+    /// High grip tape
+    HighGrip,
+    /// A custom wheel friction coefficient
+    Friction(f32),
+}
+
+impl Surface {
+    /// Friction coefficient of the wheels on the surface
+    pub fn friction(&self) -> f32 {
+        match *self {
+            Surface::Normal => 0.8,
+            Surface::Dusty => 0.5,
+            Surface::Slippery => 0.2,
+            Surface::HighGrip => 1.2,
+            Surface::Friction(friction) => friction,
+        }
+    }
+
+    /// Tint of the track band, to tell the surfaces apart (purely visual:
+    /// the line sensors still see a white background)
+    fn tint(&self) -> Option<Color> {
+        let normal = Surface::Normal.friction();
+        let friction = self.friction();
+        if friction < normal {
+            let amount = (1.0 - friction / normal).clamp(0.0, 1.0);
+            Some(Color::srgb(1.0 - amount * 0.3, 1.0 - amount * 0.15, 1.0))
+        } else if friction > normal {
+            Some(Color::srgb(0.8, 0.75, 0.7))
+        } else {
+            None
         }
     }
 }
 
+/// A side marker, in the local frame of its segment (see
+/// [`TrackSegment::transform`])
+#[derive(Debug, Clone, Copy)]
+pub struct MarkerRect {
+    center: Vec2,
+    /// Unit vector along the track
+    direction: Vec2,
+}
+
+impl MarkerRect {
+    /// The marker beside the center line point `point` (where the track goes
+    /// along `direction`), placed before the point if `at_end` is set
+    fn new(point: Vec2, direction: Vec2, at_end: bool, side: Side) -> Self {
+        let along = if at_end { -0.5 } else { 0.5 } * MARKER_LENGTH;
+        let across = (MARKER_OFFSET + MARKER_WIDTH / 2.0) * side.sign();
+        Self {
+            center: point + direction * along + direction.perp() * across,
+            direction,
+        }
+    }
+
+    /// Distance of `point` from the marker (zero inside it)
+    pub fn distance(&self, point: Vec2) -> f32 {
+        let relative = point - self.center;
+        Vec2::new(
+            relative.dot(self.direction.perp()).abs() - MARKER_WIDTH / 2.0,
+            relative.dot(self.direction).abs() - MARKER_LENGTH / 2.0,
+        )
+        .max(Vec2::ZERO)
+        .length()
+    }
+
+    fn corners(&self) -> Vec<Vec2> {
+        let along = self.direction * MARKER_LENGTH / 2.0;
+        let across = self.direction.perp() * MARKER_WIDTH / 2.0;
+        vec![
+            self.center - along - across,
+            self.center - along + across,
+            self.center + along + across,
+            self.center + along - across,
+        ]
+    }
+
+    fn mesh(&self) -> Mesh {
+        quad_mesh(MARKER_WIDTH, MARKER_LENGTH)
+            .rotated_by(Quat::from_rotation_z(
+                (-self.direction.x).atan2(self.direction.y),
+            ))
+            .translated_by(self.center.extend(0.0))
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub(crate) struct StraightSegment {
     pub(crate) length: f32,
+    pub(crate) gaps: Option<GapPattern>,
+    pub(crate) line: LineStyle,
+    pub(crate) markers: SideMarkers,
+    pub(crate) surface: Surface,
+    pub(crate) step: f32,
+    /// Height gained along the segment (a ramp when not zero)
+    pub(crate) rise: f32,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub(crate) struct NinetyDegTurnSegment {
     pub(crate) line_half_length: f32,
     pub(crate) side: Side,
+    pub(crate) line: LineStyle,
+    pub(crate) markers: SideMarkers,
+    pub(crate) surface: Surface,
+    pub(crate) step: f32,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -383,6 +611,24 @@ pub(crate) struct CyrcleTurnSegment {
     pub(crate) radius: f32,
     pub(crate) side: Side,
     pub(crate) angle: Angle,
+    pub(crate) gaps: Option<GapPattern>,
+    pub(crate) line: LineStyle,
+    pub(crate) markers: SideMarkers,
+    pub(crate) surface: Surface,
+    pub(crate) step: f32,
+}
+
+impl CyrcleTurnSegment {
+    /// Angle swept from the start of the turn to a point in the local frame
+    /// of [`TrackSegment::transform`] (in `0..TAU`)
+    pub(crate) fn angle_to(&self, point: Vec2) -> f32 {
+        let local_angle = point.y.atan2(point.x);
+        match self.side {
+            Side::Left => local_angle,
+            Side::Right => PI - local_angle,
+        }
+        .rem_euclid(TAU)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Component)]
@@ -390,6 +636,8 @@ pub enum TrackSegment {
     Start,
     End,
     Straight(StraightSegment),
+    /// A straight line where the track deliberately crosses itself
+    Crossing(StraightSegment),
     NinetyDegTurn(NinetyDegTurnSegment),
     CyrcleTurn(CyrcleTurnSegment),
 }
@@ -410,13 +658,37 @@ impl TrackSegment {
     }
 
     pub fn straight(length: f32) -> Self {
-        Self::Straight(StraightSegment { length })
+        Self::Straight(StraightSegment {
+            length,
+            gaps: None,
+            line: LineStyle::default(),
+            markers: SideMarkers::default(),
+            surface: Surface::default(),
+            step: 0.0,
+            rise: 0.0,
+        })
+    }
+
+    pub fn crossing(length: f32) -> Self {
+        Self::Crossing(StraightSegment {
+            length,
+            gaps: None,
+            line: LineStyle::default(),
+            markers: SideMarkers::default(),
+            surface: Surface::default(),
+            step: 0.0,
+            rise: 0.0,
+        })
     }
 
     pub fn ninety_deg_turn(line_half_length: f32, side: Side) -> Self {
         Self::NinetyDegTurn(NinetyDegTurnSegment {
             line_half_length: line_half_length,
             side,
+            line: LineStyle::default(),
+            markers: SideMarkers::default(),
+            surface: Surface::default(),
+            step: 0.0,
         })
     }
 
@@ -425,16 +697,233 @@ impl TrackSegment {
             radius,
             angle,
             side,
+            gaps: None,
+            line: LineStyle::default(),
+            markers: SideMarkers::default(),
+            surface: Surface::default(),
+            step: 0.0,
         })
     }
 
+    /// Changes the line style (start and end tips always use the default one)
+    pub fn with_line_style(self, line: LineStyle) -> Self {
+        match self {
+            Self::Straight(data) => Self::Straight(StraightSegment { line, ..data }),
+            Self::Crossing(data) => Self::Crossing(StraightSegment { line, ..data }),
+            Self::NinetyDegTurn(data) => Self::NinetyDegTurn(NinetyDegTurnSegment { line, ..data }),
+            Self::CyrcleTurn(data) => Self::CyrcleTurn(CyrcleTurnSegment { line, ..data }),
+            Self::Start | Self::End => self,
+        }
+    }
+
+    /// Changes the side markers (start and end tips have none)
+    pub fn with_markers(self, markers: SideMarkers) -> Self {
+        match self {
+            Self::Straight(data) => Self::Straight(StraightSegment { markers, ..data }),
+            Self::Crossing(data) => Self::Crossing(StraightSegment { markers, ..data }),
+            Self::NinetyDegTurn(data) => {
+                Self::NinetyDegTurn(NinetyDegTurnSegment { markers, ..data })
+            }
+            Self::CyrcleTurn(data) => Self::CyrcleTurn(CyrcleTurnSegment { markers, ..data }),
+            Self::Start | Self::End => self,
+        }
+    }
+
+    pub fn markers(&self) -> SideMarkers {
+        match *self {
+            Self::Straight(data) | Self::Crossing(data) => data.markers,
+            Self::NinetyDegTurn(data) => data.markers,
+            Self::CyrcleTurn(data) => data.markers,
+            Self::Start | Self::End => SideMarkers::default(),
+        }
+    }
+
+    /// Changes the surface (start and end tips always have the normal one)
+    pub fn with_surface(self, surface: Surface) -> Self {
+        match self {
+            Self::Straight(data) => Self::Straight(StraightSegment { surface, ..data }),
+            Self::Crossing(data) => Self::Crossing(StraightSegment { surface, ..data }),
+            Self::NinetyDegTurn(data) => {
+                Self::NinetyDegTurn(NinetyDegTurnSegment { surface, ..data })
+            }
+            Self::CyrcleTurn(data) => Self::CyrcleTurn(CyrcleTurnSegment { surface, ..data }),
+            Self::Start | Self::End => self,
+        }
+    }
+
+    pub fn surface(&self) -> Surface {
+        match *self {
+            Self::Straight(data) | Self::Crossing(data) => data.surface,
+            Self::NinetyDegTurn(data) => data.surface,
+            Self::CyrcleTurn(data) => data.surface,
+            Self::Start | Self::End => Surface::default(),
+        }
+    }
+
+    /// Changes the height step at the start of the segment, like the seam
+    /// between two tiles (start and end tips always follow the previous
+    /// segment)
+    pub fn with_step(self, step: f32) -> Self {
+        match self {
+            Self::Straight(data) => Self::Straight(StraightSegment { step, ..data }),
+            Self::Crossing(data) => Self::Crossing(StraightSegment { step, ..data }),
+            Self::NinetyDegTurn(data) => Self::NinetyDegTurn(NinetyDegTurnSegment { step, ..data }),
+            Self::CyrcleTurn(data) => Self::CyrcleTurn(CyrcleTurnSegment { step, ..data }),
+            Self::Start | Self::End => self,
+        }
+    }
+
+    pub fn step(&self) -> f32 {
+        match *self {
+            Self::Straight(data) | Self::Crossing(data) => data.step,
+            Self::NinetyDegTurn(data) => data.step,
+            Self::CyrcleTurn(data) => data.step,
+            Self::Start | Self::End => 0.0,
+        }
+    }
+
+    /// Makes a straight segment a ramp, climbing `rise` meters (going down
+    /// when negative) along its length
+    pub fn with_rise(self, rise: f32) -> Self {
+        match self {
+            Self::Straight(data) => Self::Straight(StraightSegment { rise, ..data }),
+            _ => self,
+        }
+    }
+
+    pub fn rise(&self) -> f32 {
+        match *self {
+            Self::Straight(data) | Self::Crossing(data) => data.rise,
+            _ => 0.0,
+        }
+    }
+
+    /// Height of the track surface at the start and at the end of the
+    /// segment
+    pub fn heights(&self, origin: SegmentTransform) -> (f32, f32) {
+        let start = origin.height + self.step();
+        (start, start + self.rise())
+    }
+
+    /// Position and direction of the center line at the start and at the
+    /// end of the segment, in the local frame of [`Self::transform`]
+    fn local_ends(&self) -> [(Vec2, Vec2); 2] {
+        let straight = |length: f32| {
+            [
+                (Vec2::NEG_Y * length / 2.0, Vec2::Y),
+                (Vec2::Y * length / 2.0, Vec2::Y),
+            ]
+        };
+        match *self {
+            TrackSegment::Start | TrackSegment::End => straight(TRACK_TIPS_LENGTH),
+            TrackSegment::Straight(data) | TrackSegment::Crossing(data) => straight(data.length),
+            TrackSegment::NinetyDegTurn(data) => {
+                let sign = data.side.sign();
+                [
+                    (Vec2::NEG_Y * data.line_half_length, Vec2::Y),
+                    (
+                        Vec2::NEG_X * data.line_half_length * sign,
+                        Vec2::NEG_X * sign,
+                    ),
+                ]
+            }
+            TrackSegment::CyrcleTurn(data) => {
+                let sign = data.side.sign();
+                let start = Vec2::X * data.radius * sign;
+                let rotation = data.angle.to_radians() * sign;
+                [
+                    (start, Vec2::Y),
+                    (rotate_vec2(start, rotation), rotate_vec2(Vec2::Y, rotation)),
+                ]
+            }
+        }
+    }
+
+    /// The side markers, in the local frame of [`Self::transform`]
+    pub fn marker_rects(&self) -> Vec<MarkerRect> {
+        let markers = self.markers();
+        if markers.is_empty() {
+            return Vec::new();
+        }
+        let [(start, start_direction), (end, end_direction)] = self.local_ends();
+        [
+            (
+                markers.start_left,
+                start,
+                start_direction,
+                false,
+                Side::Left,
+            ),
+            (
+                markers.start_right,
+                start,
+                start_direction,
+                false,
+                Side::Right,
+            ),
+            (markers.end_left, end, end_direction, true, Side::Left),
+            (markers.end_right, end, end_direction, true, Side::Right),
+        ]
+        .into_iter()
+        .filter(|(present, ..)| *present)
+        .map(|(_, point, direction, at_end, side)| MarkerRect::new(point, direction, at_end, side))
+        .collect()
+    }
+
+    fn marker_mesh(&self) -> Option<Mesh> {
+        let rects = self.marker_rects();
+        (!rects.is_empty()).then(|| merge_meshes(rects.iter().map(MarkerRect::mesh)))
+    }
+
+    /// Outline of the side markers, relative to the floor center
+    pub fn marker_outline(&self, origin: SegmentTransform) -> Vec<Vec<Vec2>> {
+        let transform = self.transform(origin);
+        self.marker_rects()
+            .iter()
+            .map(|rect| {
+                rect.corners()
+                    .into_iter()
+                    .map(|point| transform.transform_point(point.extend(0.0)).truncate())
+                    .collect()
+            })
+            .collect()
+    }
+
+    pub fn line_style(&self) -> LineStyle {
+        match *self {
+            Self::Straight(data) | Self::Crossing(data) => data.line,
+            Self::NinetyDegTurn(data) => data.line,
+            Self::CyrcleTurn(data) => data.line,
+            Self::Start | Self::End => LineStyle::default(),
+        }
+    }
+
+    /// Interrupts the line of straight segments and circle turns
+    pub fn with_gaps(self, gaps: GapPattern) -> Self {
+        match self {
+            Self::Straight(data) => Self::Straight(StraightSegment {
+                gaps: Some(gaps),
+                ..data
+            }),
+            Self::CyrcleTurn(data) => Self::CyrcleTurn(CyrcleTurnSegment {
+                gaps: Some(gaps),
+                ..data
+            }),
+            _ => self,
+        }
+    }
+
     pub fn collider(&self) -> Collider {
+        self.shape_collider(TRACK_HALF_HEIGHT)
+    }
+
+    fn shape_collider(&self, half_height: f32) -> Collider {
         match *self {
             TrackSegment::Start | TrackSegment::End => {
-                Collider::cuboid(TRACK_HALF_WIDTH, TRACK_TIPS_LENGTH / 2.0, TRACK_HALF_HEIGHT)
+                Collider::cuboid(TRACK_HALF_WIDTH, TRACK_TIPS_LENGTH / 2.0, half_height)
             }
-            TrackSegment::Straight(data) => {
-                Collider::cuboid(TRACK_HALF_WIDTH, data.length / 2.0, TRACK_HALF_HEIGHT)
+            TrackSegment::Straight(data) | TrackSegment::Crossing(data) => {
+                Collider::cuboid(TRACK_HALF_WIDTH, data.length / 2.0, half_height)
             }
             TrackSegment::NinetyDegTurn(data) => {
                 let hl: f32 = (data.line_half_length + TRACK_HALF_WIDTH) / 2.0;
@@ -444,12 +933,12 @@ impl TrackSegment {
                     (
                         Vec3::NEG_Y * ht,
                         Quat::IDENTITY,
-                        Collider::cuboid(TRACK_HALF_WIDTH, hl, TRACK_HALF_HEIGHT),
+                        Collider::cuboid(TRACK_HALF_WIDTH, hl, half_height),
                     ),
                     (
                         Vec3::NEG_X * ht * data.side.sign(),
                         Quat::from_rotation_z(FRAC_PI_2),
-                        Collider::cuboid(TRACK_HALF_WIDTH, hl, TRACK_HALF_HEIGHT),
+                        Collider::cuboid(TRACK_HALF_WIDTH, hl, half_height),
                     ),
                 ])
             }
@@ -458,39 +947,228 @@ impl TrackSegment {
                 TRACK_HALF_WIDTH * 2.0,
                 data.angle.to_radians(),
                 data.side,
-                TRACK_HALF_HEIGHT * 2.0,
+                half_height * 2.0,
             ),
         }
     }
 
+    /// The solid block between the floor and the surface of a raised
+    /// segment, with its transform relative to the floor surface
+    fn relief(&self, origin: SegmentTransform) -> Option<(Transform, Collider)> {
+        let (start_height, end_height) = self.heights(origin);
+        if start_height <= 0.0 && end_height <= 0.0 {
+            return None;
+        }
+        let level = self.level_transform(origin);
+        match *self {
+            TrackSegment::Straight(data) | TrackSegment::Crossing(data) => {
+                // A wedge on ramps
+                let half_length = data.length / 2.0;
+                let points: Vec<Vec3> = [-TRACK_HALF_WIDTH, TRACK_HALF_WIDTH]
+                    .into_iter()
+                    .flat_map(|x| {
+                        [
+                            Vec3::new(x, -half_length, 0.0),
+                            Vec3::new(x, half_length, 0.0),
+                            Vec3::new(x, -half_length, start_height.max(0.0)),
+                            Vec3::new(x, half_length, end_height.max(0.0)),
+                        ]
+                    })
+                    .collect();
+                Collider::convex_hull(&points).map(|collider| (level, collider))
+            }
+            _ => Some((
+                level.with_translation(level.translation + Vec3::Z * start_height / 2.0),
+                self.shape_collider(start_height / 2.0),
+            )),
+        }
+    }
+
     pub fn mesh(&self) -> Mesh {
+        let style = self.line_style();
+        self.shape_mesh(style.half_width, true)
+    }
+
+    /// The track band drawn below the line of inverted segments, of
+    /// segments with a tinted surface and of raised segments
+    pub fn background_mesh(&self) -> Mesh {
+        self.shape_mesh(TRACK_HALF_WIDTH, false)
+    }
+
+    fn shape_mesh(&self, half_width: f32, with_gaps: bool) -> Mesh {
+        let width = half_width * 2.0;
         match *self {
             TrackSegment::Start | TrackSegment::End => {
                 // Collider::cuboid(TRACK_HALF_WIDTH, TRACK_TIPS_LENGTH / 2.0, TRACK_HALF_HEIGHT)
-                quad_mesh(LINE_HALF_WIDTH * 2.0, TRACK_TIPS_LENGTH)
+                quad_mesh(width, TRACK_TIPS_LENGTH)
             }
-            TrackSegment::Straight(data) => {
-                // Collider::cuboid(TRACK_HALF_WIDTH, data.length / 2.0, TRACK_HALF_HEIGHT)
-                quad_mesh(LINE_HALF_WIDTH * 2.0, data.length)
+            TrackSegment::Straight(data) | TrackSegment::Crossing(data) => {
+                match data.gaps.filter(|_| with_gaps) {
+                    // Collider::cuboid(TRACK_HALF_WIDTH, data.length / 2.0, TRACK_HALF_HEIGHT)
+                    None => quad_mesh(width, data.length),
+                    Some(gaps) => merge_meshes(gaps.line_sections(data.length).into_iter().map(
+                        |(start, end)| {
+                            quad_mesh(width, end - start)
+                                .translated_by(Vec3::Y * ((start + end) / 2.0 - data.length / 2.0))
+                        },
+                    )),
+                }
             }
             TrackSegment::NinetyDegTurn(data) => {
-                ninety_deg_mesh(LINE_HALF_WIDTH * 2.0, data.line_half_length, data.side)
+                ninety_deg_mesh(width, data.line_half_length, data.side)
             }
-            TrackSegment::CyrcleTurn(data) => arc_mesh(
-                data.radius,
-                LINE_HALF_WIDTH * 2.0,
-                data.angle.to_radians(),
-                data.side,
-            ),
+            TrackSegment::CyrcleTurn(data) => match data.gaps.filter(|_| with_gaps) {
+                None => arc_mesh(data.radius, width, data.angle.to_radians(), data.side),
+                Some(gaps) => merge_meshes(
+                    gaps.line_sections(data.radius * data.angle.to_radians())
+                        .into_iter()
+                        .map(|(start, end)| {
+                            arc_mesh(data.radius, width, (end - start) / data.radius, data.side)
+                                .rotated_by(Quat::from_rotation_z(
+                                    start / data.radius * data.side.sign(),
+                                ))
+                        }),
+                ),
+            },
         }
     }
 
+    /// Outline of the line (gaps excluded), relative to the floor center
+    pub fn line_outline(&self, origin: SegmentTransform) -> Vec<Vec<Vec2>> {
+        self.outline(origin, self.line_style().half_width, true)
+    }
+
+    /// Outline of the track band around the line, relative to the floor
+    /// center
+    pub fn band_outline(&self, origin: SegmentTransform) -> Vec<Vec<Vec2>> {
+        self.outline(origin, TRACK_HALF_WIDTH, false)
+    }
+
+    /// Same shapes as [`Self::shape_mesh`], as closed polygons placed at
+    /// `origin`
+    fn outline(
+        &self,
+        origin: SegmentTransform,
+        half_width: f32,
+        with_gaps: bool,
+    ) -> Vec<Vec<Vec2>> {
+        fn quad(half_width: f32, start: f32, end: f32) -> Vec<Vec2> {
+            vec![
+                Vec2::new(-half_width, start),
+                Vec2::new(half_width, start),
+                Vec2::new(half_width, end),
+                Vec2::new(-half_width, end),
+            ]
+        }
+        fn arc(radius: f32, half_width: f32, start: f32, angle: f32, side: Side) -> Vec<Vec2> {
+            let segments: usize =
+                ((TRACK_CIRCLE_SEGMENTS_PER_PI as f32 * angle / PI).round() as usize).max(1);
+            let offset = match side {
+                Side::Left => start,
+                Side::Right => PI - angle - start,
+            };
+            let point = |i: usize, radius: f32| {
+                Vec2::from_angle(offset + angle * i as f32 / segments as f32) * radius
+            };
+            (0..=segments)
+                .map(|i| point(i, radius - half_width))
+                .chain((0..=segments).rev().map(|i| point(i, radius + half_width)))
+                .collect()
+        }
+
+        let polygons = match *self {
+            TrackSegment::Start | TrackSegment::End => vec![quad(
+                half_width,
+                -TRACK_TIPS_LENGTH / 2.0,
+                TRACK_TIPS_LENGTH / 2.0,
+            )],
+            TrackSegment::Straight(data) | TrackSegment::Crossing(data) => {
+                let half_length = data.length / 2.0;
+                match data.gaps.filter(|_| with_gaps) {
+                    None => vec![quad(half_width, -half_length, half_length)],
+                    Some(gaps) => gaps
+                        .line_sections(data.length)
+                        .into_iter()
+                        .map(|(start, end)| {
+                            quad(half_width, start - half_length, end - half_length)
+                        })
+                        .collect(),
+                }
+            }
+            TrackSegment::NinetyDegTurn(data) => {
+                let (w, h) = (half_width, data.line_half_length);
+                vec![match data.side {
+                    Side::Left => vec![
+                        Vec2::new(-w, -h),
+                        Vec2::new(w, -h),
+                        Vec2::new(w, w),
+                        Vec2::new(-h, w),
+                        Vec2::new(-h, -w),
+                        Vec2::new(-w, -w),
+                    ],
+                    Side::Right => vec![
+                        Vec2::new(-w, -h),
+                        Vec2::new(w, -h),
+                        Vec2::new(w, -w),
+                        Vec2::new(h, -w),
+                        Vec2::new(h, w),
+                        Vec2::new(-w, w),
+                    ],
+                }]
+            }
+            TrackSegment::CyrcleTurn(data) => {
+                let angle = data.angle.to_radians();
+                match data.gaps.filter(|_| with_gaps) {
+                    None => vec![arc(data.radius, half_width, 0.0, angle, data.side)],
+                    Some(gaps) => gaps
+                        .line_sections(data.radius * angle)
+                        .into_iter()
+                        .map(|(start, end)| {
+                            arc(
+                                data.radius,
+                                half_width,
+                                start / data.radius,
+                                (end - start) / data.radius,
+                                data.side,
+                            )
+                        })
+                        .collect(),
+                }
+            }
+        };
+
+        let transform = self.transform(origin);
+        polygons
+            .into_iter()
+            .map(|polygon| {
+                polygon
+                    .into_iter()
+                    .map(|point| transform.transform_point(point.extend(0.0)).truncate())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The local frame of the segment: on ramps it follows the slope, and
+    /// it is stretched along the track so that local lengths stay the
+    /// lengths on the floor plan
     pub fn transform(&self, origin: SegmentTransform) -> Transform {
+        let (start_height, end_height) = self.heights(origin);
+        let slope = ((end_height - start_height) / self.length()).atan();
+        let level = self.level_transform(origin);
+        level
+            .with_translation(level.translation + Vec3::Z * (start_height + end_height) / 2.0)
+            .with_rotation(level.rotation * Quat::from_rotation_x(slope))
+            .with_scale(Vec3::new(1.0, 1.0 / slope.cos(), 1.0))
+    }
+
+    /// The local frame of the segment at the floor level, ignoring heights
+    fn level_transform(&self, origin: SegmentTransform) -> Transform {
         let transform_origin = match *self {
             TrackSegment::Start | TrackSegment::End => {
                 origin.translate_in_direction(Vec2::Y * TRACK_TIPS_LENGTH / 2.0)
             }
-            TrackSegment::Straight(data) => {
+            TrackSegment::Straight(data) | TrackSegment::Crossing(data) => {
                 origin.translate_in_direction(Vec2::Y * data.length / 2.0)
             }
             TrackSegment::NinetyDegTurn(data) => {
@@ -506,11 +1184,13 @@ impl TrackSegment {
     }
 
     pub fn compute_next_origin(&self, origin: SegmentTransform) -> SegmentTransform {
-        match *self {
+        let next = match *self {
             TrackSegment::Start | TrackSegment::End => {
                 origin.translate_in_direction(Vec2::Y * TRACK_TIPS_LENGTH)
             }
-            TrackSegment::Straight(data) => origin.translate_in_direction(Vec2::Y * data.length),
+            TrackSegment::Straight(data) | TrackSegment::Crossing(data) => {
+                origin.translate_in_direction(Vec2::Y * data.length)
+            }
             TrackSegment::NinetyDegTurn(data) => origin
                 .translate_in_direction(Vec2::new(
                     -data.line_half_length * data.side.sign(),
@@ -525,6 +1205,244 @@ impl TrackSegment {
                 .rotate(Angle::from_radians(
                     data.angle.to_radians() * data.side.sign(),
                 )),
+        };
+        next.raise(self.step() + self.rise())
+    }
+
+    /// Computes the rectangle covered by the segment path (track width included)
+    pub fn bounding_rect(&self, origin: SegmentTransform) -> Rect {
+        let local_points: Vec<Vec2> = match *self {
+            TrackSegment::Start | TrackSegment::End => vec![
+                Vec2::new(-TRACK_HALF_WIDTH, 0.0),
+                Vec2::new(TRACK_HALF_WIDTH, 0.0),
+                Vec2::new(-TRACK_HALF_WIDTH, TRACK_TIPS_LENGTH),
+                Vec2::new(TRACK_HALF_WIDTH, TRACK_TIPS_LENGTH),
+            ],
+            TrackSegment::Straight(data) | TrackSegment::Crossing(data) => vec![
+                Vec2::new(-TRACK_HALF_WIDTH, 0.0),
+                Vec2::new(TRACK_HALF_WIDTH, 0.0),
+                Vec2::new(-TRACK_HALF_WIDTH, data.length),
+                Vec2::new(TRACK_HALF_WIDTH, data.length),
+            ],
+            TrackSegment::NinetyDegTurn(data) => {
+                let h = data.line_half_length;
+                let sign = data.side.sign();
+                vec![
+                    Vec2::new(-TRACK_HALF_WIDTH, 0.0),
+                    Vec2::new(TRACK_HALF_WIDTH, 0.0),
+                    Vec2::new(-TRACK_HALF_WIDTH, h + TRACK_HALF_WIDTH),
+                    Vec2::new(TRACK_HALF_WIDTH, h + TRACK_HALF_WIDTH),
+                    Vec2::new(-sign * h, h - TRACK_HALF_WIDTH),
+                    Vec2::new(-sign * h, h + TRACK_HALF_WIDTH),
+                ]
+            }
+            TrackSegment::CyrcleTurn(data) => {
+                // The arc is swept around its center: the extremes are the
+                // arc ends and the points where the radius is axis aligned.
+                let sign = data.side.sign();
+                let center = origin
+                    .translate_in_direction(Vec2::NEG_X * data.radius * sign)
+                    .position;
+                let start_angle = origin.direction.to_radians()
+                    + match data.side {
+                        Side::Left => 0.0,
+                        Side::Right => PI,
+                    };
+                let end_angle = start_angle + data.angle.to_radians() * sign;
+                let (min_angle, max_angle) =
+                    (start_angle.min(end_angle), start_angle.max(end_angle));
+
+                let mut angles = vec![min_angle, max_angle];
+                let mut axis_angle = (min_angle / FRAC_PI_2).ceil() * FRAC_PI_2;
+                while axis_angle < max_angle {
+                    angles.push(axis_angle);
+                    axis_angle += FRAC_PI_2;
+                }
+
+                return angles
+                    .into_iter()
+                    .flat_map(|angle| {
+                        let radial = Vec2::from_angle(angle);
+                        [
+                            center + radial * (data.radius - TRACK_HALF_WIDTH),
+                            center + radial * (data.radius + TRACK_HALF_WIDTH),
+                        ]
+                    })
+                    .fold(Rect::EMPTY, |rect, point| rect.union_point(point));
+            }
+        };
+
+        local_points.into_iter().fold(Rect::EMPTY, |rect, point| {
+            rect.union_point(origin.translate_in_direction(point).position)
+        })
+    }
+
+    /// Samples the segment center line (both ends included) with points
+    /// at most `step` apart
+    pub fn center_line(&self, origin: SegmentTransform, step: f32) -> Vec<Vec2> {
+        fn samples(length: f32, step: f32) -> impl Iterator<Item = f32> {
+            let count = ((length / step).ceil() as usize).max(1);
+            (0..=count).map(move |i| length * i as f32 / count as f32)
+        }
+
+        match *self {
+            TrackSegment::Start | TrackSegment::End => samples(TRACK_TIPS_LENGTH, step)
+                .map(|y| origin.translate_in_direction(Vec2::Y * y).position)
+                .collect(),
+            TrackSegment::Straight(data) | TrackSegment::Crossing(data) => {
+                samples(data.length, step)
+                    .map(|y| origin.translate_in_direction(Vec2::Y * y).position)
+                    .collect()
+            }
+            TrackSegment::NinetyDegTurn(data) => {
+                let next_origin = self.compute_next_origin(origin);
+                let h = data.line_half_length;
+                samples(h, step)
+                    .map(|y| origin.translate_in_direction(Vec2::Y * y).position)
+                    .chain(samples(h, step).skip(1).map(|y| {
+                        next_origin
+                            .translate_in_direction(Vec2::Y * (y - h))
+                            .position
+                    }))
+                    .collect()
+            }
+            TrackSegment::CyrcleTurn(data) => {
+                let sign = data.side.sign();
+                let center = origin
+                    .translate_in_direction(Vec2::NEG_X * data.radius * sign)
+                    .position;
+                let start_angle = origin.direction.to_radians()
+                    + match data.side {
+                        Side::Left => 0.0,
+                        Side::Right => PI,
+                    };
+                samples(data.angle.to_radians() * data.radius, step)
+                    .map(|l| {
+                        center
+                            + Vec2::from_angle(start_angle + l / data.radius * sign) * data.radius
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// Position, direction and height of the center line `along` meters
+    /// from the start of the segment
+    pub fn point_at(&self, origin: SegmentTransform, along: f32) -> SegmentTransform {
+        let point = match *self {
+            TrackSegment::NinetyDegTurn(data) if along > data.line_half_length => {
+                let h = data.line_half_length;
+                origin
+                    .translate_in_direction(Vec2::new(-h * data.side.sign(), h))
+                    .rotate(Angle::from_degrees(90.0 * data.side.sign()))
+                    .translate_in_direction(Vec2::Y * (along - h))
+            }
+            TrackSegment::CyrcleTurn(data) => {
+                let angle = along / data.radius;
+                origin
+                    .translate_in_direction(Vec2::new(
+                        data.radius * (angle.cos() - 1.0) * data.side.sign(),
+                        data.radius * angle.sin(),
+                    ))
+                    .rotate(Angle::from_radians(angle * data.side.sign()))
+            }
+            _ => origin.translate_in_direction(Vec2::Y * along),
+        };
+        let (start_height, end_height) = self.heights(origin);
+        SegmentTransform {
+            height: start_height + (end_height - start_height) * along / self.length(),
+            ..point
+        }
+    }
+
+    /// Length of the segment center line
+    pub fn length(&self) -> f32 {
+        match *self {
+            TrackSegment::Start | TrackSegment::End => TRACK_TIPS_LENGTH,
+            TrackSegment::Straight(data) | TrackSegment::Crossing(data) => data.length,
+            TrackSegment::NinetyDegTurn(data) => data.line_half_length * 2.0,
+            TrackSegment::CyrcleTurn(data) => data.angle.to_radians() * data.radius,
+        }
+    }
+
+    /// Projects `point` on the segment center line, returning the distance
+    /// along the center line and the distance from it
+    pub fn project(&self, origin: SegmentTransform, point: Vec2) -> (f32, f32) {
+        fn project_on_leg(origin: SegmentTransform, length: f32, point: Vec2) -> (f32, f32) {
+            let local = origin.local_position(point);
+            let along = local.y.clamp(0.0, length);
+            (along, local.distance(Vec2::Y * along))
+        }
+
+        match *self {
+            TrackSegment::Start | TrackSegment::End => {
+                project_on_leg(origin, TRACK_TIPS_LENGTH, point)
+            }
+            TrackSegment::Straight(data) | TrackSegment::Crossing(data) => {
+                project_on_leg(origin, data.length, point)
+            }
+            TrackSegment::NinetyDegTurn(data) => {
+                let h = data.line_half_length;
+                let corner = self
+                    .compute_next_origin(origin)
+                    .translate_in_direction(Vec2::NEG_Y * h);
+                let first = project_on_leg(origin, h, point);
+                let (along, distance) = project_on_leg(corner, h, point);
+                if first.1 <= distance {
+                    first
+                } else {
+                    (h + along, distance)
+                }
+            }
+            TrackSegment::CyrcleTurn(data) => {
+                let sign = data.side.sign();
+                let center = origin
+                    .translate_in_direction(Vec2::NEG_X * data.radius * sign)
+                    .position;
+                let start_angle = origin.direction.to_radians()
+                    + match data.side {
+                        Side::Left => 0.0,
+                        Side::Right => PI,
+                    };
+                let arc_angle = data.angle.to_radians();
+                let relative = point - center;
+                let angle = ((relative.y.atan2(relative.x) - start_angle) * sign).rem_euclid(TAU);
+                // Past the arc end snap to the nearest of the two ends
+                let angle = if angle <= arc_angle {
+                    angle
+                } else if angle - arc_angle < TAU - angle {
+                    arc_angle
+                } else {
+                    0.0
+                };
+                let on_arc = center + Vec2::from_angle(start_angle + angle * sign) * data.radius;
+                (angle * data.radius, point.distance(on_arc))
+            }
+        }
+    }
+
+    /// Whether a point of the floor plan, in the local frame of
+    /// [`Self::transform`], is on the segment (inside its path collider)
+    pub fn contains(&self, point: Vec2) -> bool {
+        let across = |x: f32| x.abs() <= TRACK_HALF_WIDTH;
+        match *self {
+            TrackSegment::Start | TrackSegment::End => {
+                across(point.x) && point.y.abs() <= TRACK_TIPS_LENGTH / 2.0
+            }
+            TrackSegment::Straight(data) | TrackSegment::Crossing(data) => {
+                across(point.x) && point.y.abs() <= data.length / 2.0
+            }
+            TrackSegment::NinetyDegTurn(data) => {
+                // The incoming leg along y, the outgoing one along x
+                let h = data.line_half_length;
+                let outgoing = -data.side.sign() * point.x;
+                (across(point.x) && (-h..=TRACK_HALF_WIDTH).contains(&point.y))
+                    || (across(point.y) && (-TRACK_HALF_WIDTH..=h).contains(&outgoing))
+            }
+            TrackSegment::CyrcleTurn(data) => {
+                across(point.length() - data.radius)
+                    && data.angle_to(point) <= data.angle.to_radians()
+            }
         }
     }
 
@@ -535,56 +1453,584 @@ impl TrackSegment {
         origin: SegmentTransform,
         features: EntityFeatures,
         commands: &mut Commands,
-        meshes: &mut Assets<Mesh>,
-        materials: &mut Assets<StandardMaterial>,
-    ) {
-        if features.has_physics() {
+        assets: Option<&SegmentAssets>,
+    ) -> Option<Entity> {
+        let path_entity = features.has_physics().then(|| {
+            commands
+                .spawn((
+                    *self,
+                    ChildOf(path_parent),
+                    self.transform(origin),
+                    self.collider(),
+                    RigidBody::Fixed,
+                    Friction {
+                        coefficient: 0.0,
+                        combine_rule: CoefficientCombineRule::Min,
+                    },
+                ))
+                .id()
+        });
+        if features.has_physics()
+            && let Some((transform, collider)) = self.relief(origin)
+        {
+            // The path colliders lie under the floor surface, raised
+            // segments stand on it
             commands.spawn((
-                *self,
                 ChildOf(path_parent),
-                self.transform(origin),
-                self.collider(),
+                transform.with_translation(transform.translation + Vec3::Z * FLOOR_HEIGHT),
+                collider,
                 RigidBody::Fixed,
-                Friction {
-                    coefficient: 0.0,
-                    combine_rule: CoefficientCombineRule::Min,
-                },
+                // Like the floor, the wheel friction is the grip of the surface
+                Friction::new(1.0),
             ));
         }
-        if features.has_visualization() {
+        if features.has_visualization()
+            && let Some(assets) = assets
+        {
+            let transform = self.transform(origin);
+            let line_transform = match &assets.band {
+                Some((mesh, material)) => {
+                    commands.spawn((
+                        ChildOf(line_parent),
+                        transform,
+                        Mesh3d(mesh.clone()),
+                        MeshMaterial3d(material.clone()),
+                    ));
+                    // Keep the line just above the band
+                    transform.with_translation(transform.translation + Vec3::Z * 0.0005)
+                }
+                None => transform,
+            };
             commands.spawn((
                 *self,
                 ChildOf(line_parent),
-                self.transform(origin),
-                Mesh3d(meshes.add(self.mesh())),
-                MeshMaterial3d(materials.add(Color::srgba(0.0, 0.0, 0.0, 1.0))),
+                line_transform,
+                Mesh3d(assets.line.clone()),
+                MeshMaterial3d(assets.line_material.clone()),
             ));
+            if let Some(mesh) = &assets.markers {
+                commands.spawn((
+                    ChildOf(line_parent),
+                    line_transform,
+                    Mesh3d(mesh.clone()),
+                    MeshMaterial3d(assets.line_material.clone()),
+                ));
+            }
+        }
+        path_entity
+    }
+}
+
+/// Handles drawing a segment: its line (with the side markers) and the
+/// background band, when it has one
+#[derive(Clone)]
+pub struct SegmentAssets {
+    pub line: Handle<Mesh>,
+    pub line_material: Handle<StandardMaterial>,
+    pub markers: Option<Handle<Mesh>>,
+    pub band: Option<(Handle<Mesh>, Handle<StandardMaterial>)>,
+}
+
+pub struct TrackMeshes {
+    pub floor: Handle<Mesh>,
+    pub finish_line: Handle<Mesh>,
+    pub obstacles: Vec<Handle<Mesh>>,
+}
+
+pub struct TrackMaterials {
+    pub floor: Handle<StandardMaterial>,
+    pub floor_transparent: Handle<StandardMaterial>,
+    pub finish_line: Handle<StandardMaterial>,
+    pub obstacle: Handle<StandardMaterial>,
+}
+
+/// Meshes and materials of a track, shared by all its spawns (the
+/// visualizer draws the track again under every bot)
+#[derive(Resource)]
+pub struct TrackAssets {
+    pub meshes: TrackMeshes,
+    pub materials: TrackMaterials,
+    pub segments: Vec<SegmentAssets>,
+}
+
+impl TrackAssets {
+    pub fn new(
+        track: &Track,
+        meshes: &mut Assets<Mesh>,
+        materials: &mut Assets<StandardMaterial>,
+    ) -> Self {
+        let black = materials.add(Color::srgba(0.0, 0.0, 0.0, 1.0));
+        let white = materials.add(Color::srgba(1.0, 1.0, 1.0, 1.0));
+
+        let segments = track
+            .segment_origins()
+            .into_iter()
+            .map(|(segment, origin)| {
+                let (start_height, end_height) = segment.heights(origin);
+                let raised = start_height > 0.0 || end_height > 0.0;
+                let (line_material, band_material) = if segment.line_style().inverted {
+                    (white.clone(), Some(black.clone()))
+                } else {
+                    (
+                        black.clone(),
+                        match segment.surface().tint() {
+                            Some(tint) => Some(materials.add(tint)),
+                            None => raised.then(|| white.clone()),
+                        },
+                    )
+                };
+                SegmentAssets {
+                    line: meshes.add(segment.mesh()),
+                    line_material,
+                    markers: segment.marker_mesh().map(|mesh| meshes.add(mesh)),
+                    band: band_material
+                        .map(|material| (meshes.add(segment.background_mesh()), material)),
+                }
+            })
+            .collect();
+
+        Self {
+            meshes: TrackMeshes {
+                floor: meshes.add(quad_mesh(track.size.x, track.size.y)),
+                finish_line: meshes.add(quad_mesh(TRACK_HALF_WIDTH * 2.0, LINE_HALF_WIDTH * 2.0)),
+                obstacles: track
+                    .obstacles()
+                    .iter()
+                    .map(|obstacle| meshes.add(obstacle.mesh()))
+                    .collect(),
+            },
+            materials: TrackMaterials {
+                floor: materials.add(Color::srgba(1.0, 1.0, 1.0, 1.0)),
+                floor_transparent: materials.add(Color::srgba(1.0, 1.0, 1.0, 0.1)),
+                finish_line: materials.add(Color::srgba(0.8, 0.0, 0.0, 1.0)),
+                obstacle: materials.add(Color::srgb(0.9, 0.4, 0.1)),
+            },
+            segments,
+        }
+    }
+}
+
+/// Shape of an obstacle: boxes are `size` wide (across the track), long
+/// (along it) and high
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ObstacleShape {
+    Box { size: Vec3 },
+    Cylinder { radius: f32, height: f32 },
+}
+
+/// A static obstacle standing on the track, `along` meters from the start
+/// of a segment and `offset` meters to the left of its center line (to the
+/// right when negative)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Obstacle {
+    pub segment: usize,
+    pub along: f32,
+    pub offset: f32,
+    pub shape: ObstacleShape,
+}
+
+impl Obstacle {
+    fn height(&self) -> f32 {
+        match self.shape {
+            ObstacleShape::Box { size } => size.z,
+            ObstacleShape::Cylinder { height, .. } => height,
+        }
+    }
+
+    fn collider(&self) -> Collider {
+        match self.shape {
+            ObstacleShape::Box { size } => {
+                Collider::cuboid(size.x / 2.0, size.y / 2.0, size.z / 2.0)
+            }
+            ObstacleShape::Cylinder { radius, height } => Collider::cylinder(height / 2.0, radius),
+        }
+    }
+
+    fn mesh(&self) -> Mesh {
+        match self.shape {
+            ObstacleShape::Box { size } => Cuboid::from_size(size).into(),
+            ObstacleShape::Cylinder { radius, height } => Cylinder::new(radius, height).into(),
         }
     }
 }
 
+/// Marks the colliders of the obstacles: touching them disqualifies a bot
+#[derive(Debug, Clone, Copy, Component)]
+pub struct TrackObstacle;
+
+/// Height above the floor of the track surface where a ray hit the path
+/// collider of a segment (path colliders lie under the floor surface)
+pub fn surface_height_at(hit_point: Vec3) -> f32 {
+    hit_point.z + FLOOR_HEIGHT - TRACK_HALF_HEIGHT
+}
+
+/// Floor plan of a segment placed in the world, see [`TrackGeometry`]
+struct SegmentFootprint {
+    segment: TrackSegment,
+    /// Origin and inverse rotation of the segment local frame
+    position: Vec2,
+    to_local: Vec2,
+    bounds: Rect,
+    level: bool,
+}
+
+/// The segments laid out in the world, to find what is under a point
+/// without physics queries: sensors over level segments (where the track
+/// surface is the floor plane) use it instead of casting rays
+#[derive(Resource)]
+pub struct TrackGeometry {
+    segments: Vec<SegmentFootprint>,
+}
+
+impl TrackGeometry {
+    pub fn new(track: &Track) -> Self {
+        let center = track.floor_center().truncate();
+        let segments = track
+            .segment_origins()
+            .into_iter()
+            .map(|(segment, origin)| {
+                let (start_height, end_height) = segment.heights(origin);
+                let transform = segment.level_transform(origin);
+                let rect = segment.bounding_rect(origin);
+                SegmentFootprint {
+                    segment,
+                    position: center + transform.translation.truncate(),
+                    to_local: Vec2::from_angle(-transform.rotation.to_euler(EulerRot::ZYX).0),
+                    bounds: Rect::from_corners(rect.min + center, rect.max + center),
+                    level: start_height == 0.0 && end_height == 0.0,
+                }
+            })
+            .collect();
+        Self { segments }
+    }
+
+    /// Where a ray meets the surface of level segments (the top of their
+    /// path colliders), if it does within `max_toi`
+    pub fn surface_hit(origin: Vec3, dir: Vec3, max_toi: f32) -> Option<Vec2> {
+        let surface_z = TRACK_HALF_HEIGHT - FLOOR_HEIGHT;
+        if dir.z >= 0.0 || origin.z < surface_z {
+            return None;
+        }
+        let toi = (surface_z - origin.z) / dir.z;
+        (toi <= max_toi).then(|| (origin + dir * toi).truncate())
+    }
+
+    /// Whether a world position is away from raised segments, so that
+    /// [`Self::segments_under`] finds everything a ray would hit there
+    pub fn is_level_at(&self, position: Vec2) -> bool {
+        // Tilted rays can hit a raised surface a little off its floor plan
+        const RELIEF_MARGIN: f32 = 0.01;
+        self.segments.iter().all(|footprint| {
+            footprint.level || !footprint.bounds.inflate(RELIEF_MARGIN).contains(position)
+        })
+    }
+
+    /// The segments under a world position (more than one at joints and
+    /// where the track crosses itself), by index and with the position in
+    /// their local frame
+    pub fn segments_under(
+        &self,
+        position: Vec2,
+    ) -> impl Iterator<Item = (usize, &TrackSegment, Vec2)> {
+        self.segments
+            .iter()
+            .enumerate()
+            .filter(move |(_, footprint)| footprint.bounds.contains(position))
+            .filter_map(move |(index, footprint)| {
+                let local = footprint.to_local.rotate(position - footprint.position);
+                footprint
+                    .segment
+                    .contains(local)
+                    .then_some((index, &footprint.segment, local))
+            })
+    }
+}
+
+/// Marks the path collider of each segment: segments are checkpoints that
+/// must be visited in order
+#[derive(Debug, Clone, Copy, Component)]
+pub struct TrackCheckpoint {
+    pub index: usize,
+}
+
 #[derive(Clone, Resource)]
 pub struct Track {
     size: Vec2,
     origin: SegmentTransform,
     segments: Vec<TrackSegment>,
+    size_override: Option<Vec2>,
+    origin_override: Option<Vec2>,
+    laps: Option<usize>,
+    obstacles: Vec<Obstacle>,
 }
 
 impl Track {
-    pub fn new(size: Vec2, origin: SegmentTransform, segments: Vec<TrackSegment>) -> Self {
-        let origin = SegmentTransform {
-            position: origin.position + Vec2::NEG_Y * TRACK_TIPS_LENGTH / 2.0,
-            direction: origin.direction,
-        };
-        Self {
-            size,
-            origin,
+    /// Builds a track whose floor size and origin are computed from the
+    /// extents of its segments
+    pub fn new(segments: Vec<TrackSegment>) -> Self {
+        let mut track = Self {
+            size: Vec2::ZERO,
+            origin: SegmentTransform::new(Vec2::ZERO, Angle::from_radians(0.0)),
             segments,
+            size_override: None,
+            origin_override: None,
+            laps: None,
+            obstacles: Vec::new(),
+        };
+        track.compute_layout();
+        track
+    }
+
+    /// Rotates the whole track around its start
+    pub fn with_direction(mut self, direction: Angle) -> Self {
+        self.origin.direction = direction;
+        self.compute_layout();
+        self
+    }
+
+    /// Overrides the computed floor size
+    pub fn with_size(mut self, size: Vec2) -> Self {
+        self.size_override = Some(size);
+        self.compute_layout();
+        self
+    }
+
+    /// Overrides the computed origin (the position of the start tip
+    /// relative to the floor center)
+    pub fn with_origin(mut self, origin: Vec2) -> Self {
+        self.origin_override = Some(origin);
+        self.compute_layout();
+        self
+    }
+
+    /// Replaces the segments, keeping direction, size and origin overrides,
+    /// laps and obstacles
+    pub fn with_segments(mut self, segments: Vec<TrackSegment>) -> Self {
+        self.segments = segments;
+        self.compute_layout();
+        self
+    }
+
+    /// Makes the track a closed loop (the last segment joins the first one,
+    /// without start and end tips) raced for the given number of laps
+    pub fn with_laps(mut self, laps: usize) -> Self {
+        self.laps = Some(laps);
+        self
+    }
+
+    /// Places static obstacles on the track
+    pub fn with_obstacles(mut self, obstacles: Vec<Obstacle>) -> Self {
+        self.obstacles = obstacles;
+        self
+    }
+
+    pub fn obstacles(&self) -> &[Obstacle] {
+        &self.obstacles
+    }
+
+    /// Pairs each obstacle (and its index in the obstacles list) with its
+    /// transform relative to the floor center (and to the floor surface),
+    /// skipping the ones placed on missing segments
+    pub fn obstacle_transforms(&self) -> Vec<(usize, Obstacle, Transform)> {
+        let segment_origins = self.segment_origins();
+        self.obstacles
+            .iter()
+            .enumerate()
+            .filter_map(|(index, obstacle)| {
+                let (segment, origin) = segment_origins.get(obstacle.segment)?;
+                let point = segment
+                    .point_at(*origin, obstacle.along)
+                    .translate_in_direction(Vec2::NEG_X * obstacle.offset);
+                let rotation = Quat::from_rotation_z(point.direction.to_radians());
+                let rotation = match obstacle.shape {
+                    ObstacleShape::Box { .. } => rotation,
+                    // Standing cylinders (they have a vertical Y axis)
+                    ObstacleShape::Cylinder { .. } => rotation * Quat::from_rotation_x(FRAC_PI_2),
+                };
+                let translation = point
+                    .position
+                    .extend(point.height + obstacle.height() / 2.0);
+                Some((
+                    index,
+                    *obstacle,
+                    Transform::from_translation(translation).with_rotation(rotation),
+                ))
+            })
+            .collect()
+    }
+
+    /// Rectangle covered by the segments when the track starts at (0, 0)
+    pub fn bounding_rect(&self) -> Rect {
+        let mut segment_origin = SegmentTransform::new(Vec2::ZERO, self.origin.direction);
+        let mut rect = Rect::EMPTY;
+        for segment in &self.segments {
+            rect = rect.union(segment.bounding_rect(segment_origin));
+            segment_origin = segment.compute_next_origin(segment_origin);
         }
+        rect
+    }
+
+    fn compute_layout(&mut self) {
+        let rect = self.bounding_rect();
+
+        self.origin.position = match self.origin_override {
+            Some(origin) => origin + Vec2::NEG_Y * TRACK_TIPS_LENGTH / 2.0,
+            None => -rect.center(),
+        };
+
+        self.size = match self.size_override {
+            Some(size) => size,
+            None => {
+                let min = rect.min + self.origin.position;
+                let max = rect.max + self.origin.position;
+                min.abs().max(max.abs()) * 2.0 + Vec2::splat(TRACK_FLOOR_MARGIN * 2.0)
+            }
+        };
+    }
+
+    pub fn size(&self) -> Vec2 {
+        self.size
+    }
+
+    pub fn direction(&self) -> Angle {
+        self.origin.direction
+    }
+
+    pub fn size_override(&self) -> Option<Vec2> {
+        self.size_override
+    }
+
+    pub fn origin_override(&self) -> Option<Vec2> {
+        self.origin_override
+    }
+
+    pub fn segments(&self) -> &[TrackSegment] {
+        &self.segments
+    }
+
+    /// Every segment is a checkpoint
+    pub fn checkpoint_count(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Length of the center line (of a single lap on closed loops)
+    pub fn length(&self) -> f32 {
+        self.segments.iter().map(TrackSegment::length).sum()
+    }
+
+    /// Distance to cover to finish the race (all the laps on closed loops)
+    pub fn race_length(&self) -> f32 {
+        self.length() * self.laps.unwrap_or(1) as f32
+    }
+
+    /// Distance covered along the center line by a bot at the given world
+    /// position, after `checkpoints_reached` checkpoints.
+    ///
+    /// Only the segment of the last checkpoint and the next one are
+    /// considered, so that nearby (or crossing) pieces of the track do not
+    /// make the progress jump ahead.
+    pub fn progress_at(&self, position: Vec2, checkpoints_reached: usize) -> Option<f32> {
+        let count = self.checkpoint_count();
+        if checkpoints_reached == 0 || count == 0 {
+            return None;
+        }
+        let position = position - TRACK_ORIGIN_OFFSET + self.origin.position;
+        let segment_origins = self.segment_origins();
+        let segment_starts: Vec<f32> = segment_origins
+            .iter()
+            .scan(0.0, |start, (segment, _)| {
+                let current = *start;
+                *start += segment.length();
+                Some(current)
+            })
+            .collect();
+        let lap_length = self.length();
+
+        let last = checkpoints_reached - 1;
+        let next_limit = if self.laps.is_some() {
+            usize::MAX
+        } else {
+            count
+        };
+        [last, last + 1]
+            .into_iter()
+            .filter(|&checkpoint| checkpoint < next_limit)
+            .map(|checkpoint| {
+                let index = checkpoint % count;
+                let (segment, origin) = segment_origins[index];
+                let (along, distance) = segment.project(origin, position);
+                let lap_start = (checkpoint / count) as f32 * lap_length;
+                (lap_start + segment_starts[index] + along, distance)
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(progress, _)| progress)
+    }
+
+    /// Number of laps to race (closed loop tracks only)
+    pub fn laps(&self) -> Option<usize> {
+        self.laps
+    }
+
+    /// The start/finish line of closed loop tracks, relative to the floor
+    /// center: across the first segment where the bots start (at the world
+    /// origin), so that the first lap is as long as the others
+    fn local_finish_line(&self) -> Option<SegmentTransform> {
+        self.laps?;
+        let &(segment, origin) = self.segment_origins().first()?;
+        let (along, _) = segment.project(origin, self.origin.position - TRACK_ORIGIN_OFFSET);
+        Some(segment.point_at(origin, along))
+    }
+
+    /// The world position and direction of the start/finish line of closed
+    /// loop tracks
+    pub fn finish_line(&self) -> Option<SegmentTransform> {
+        self.local_finish_line().map(|line| {
+            SegmentTransform::new(
+                line.position() + self.floor_center().truncate(),
+                line.direction(),
+            )
+        })
+    }
+
+    /// Pairs each segment with its origin, relative to the floor center
+    pub fn segment_origins(&self) -> Vec<(TrackSegment, SegmentTransform)> {
+        let mut segment_origin = self.origin;
+        self.segments
+            .iter()
+            .map(|segment| {
+                let current = segment_origin;
+                segment_origin = segment.compute_next_origin(segment_origin);
+                (*segment, current)
+            })
+            .collect()
+    }
+
+    /// The world position of the floor center
+    pub fn floor_center(&self) -> Vec3 {
+        (-self.origin.position + TRACK_ORIGIN_OFFSET).extend(0.0)
     }
 
     pub fn camera_target(&self) -> Vec3 {
-        -self.origin.position.extend(0.0)
+        self.floor_center()
+    }
+
+    /// The world transform of a segment (the floor height excluded)
+    pub fn segment_world_transform(&self, index: usize) -> Option<Transform> {
+        let (segment, origin) = *self.segment_origins().get(index)?;
+        Some(Transform::from_translation(self.floor_center()) * segment.transform(origin))
+    }
+
+    /// Index of the segment whose center line is closest to the given world
+    /// position, if the position is on (or next to) the track
+    pub fn segment_at(&self, position: Vec2) -> Option<usize> {
+        let position = position - self.floor_center().truncate();
+        self.segment_origins()
+            .into_iter()
+            .map(|(segment, origin)| segment.project(origin, position).1)
+            .enumerate()
+            .filter(|&(_, distance)| distance <= TRACK_HALF_WIDTH * 2.0)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(index, _)| index)
     }
 
     pub fn camera_radius(&self) -> f32 {
@@ -597,22 +2043,58 @@ impl Track {
         line_parent: Entity,
         features: EntityFeatures,
         commands: &mut Commands,
-        meshes: &mut Assets<Mesh>,
-        materials: &mut Assets<StandardMaterial>,
+        assets: Option<&TrackAssets>,
     ) {
-        let mut segment_origin = self.origin;
-
-        for segment in &self.segments {
-            segment.spawn(
+        for (index, (segment, segment_origin)) in self.segment_origins().into_iter().enumerate() {
+            if let Some(path_entity) = segment.spawn(
                 path_parent,
                 line_parent,
                 segment_origin,
                 features,
                 commands,
-                meshes,
-                materials,
-            );
-            segment_origin = segment.compute_next_origin(segment_origin);
+                assets.and_then(|assets| assets.segments.get(index)),
+            ) {
+                commands
+                    .entity(path_entity)
+                    .insert(TrackCheckpoint { index });
+            }
+        }
+
+        for (index, obstacle, transform) in self.obstacle_transforms() {
+            let transform =
+                transform.with_translation(transform.translation + Vec3::Z * FLOOR_HEIGHT);
+            let entity = commands
+                .spawn((TrackObstacle, ChildOf(path_parent), transform))
+                .id();
+            if features.has_physics() {
+                commands
+                    .entity(entity)
+                    .insert((obstacle.collider(), RigidBody::Fixed));
+            }
+            if features.has_visualization()
+                && let Some(assets) = assets
+            {
+                commands.entity(entity).insert((
+                    Mesh3d(assets.meshes.obstacles[index].clone()),
+                    MeshMaterial3d(assets.materials.obstacle.clone()),
+                ));
+            }
+        }
+
+        if let Some(finish_line) = self.local_finish_line()
+            && features.has_visualization()
+            && let Some(assets) = assets
+        {
+            // The start/finish line is only drawn, line sensors do not see it
+            commands.spawn((
+                ChildOf(line_parent),
+                Transform::from_translation(
+                    finish_line.position().extend(finish_line.height() + 0.0005),
+                )
+                .with_rotation(Quat::from_rotation_z(finish_line.direction().to_radians())),
+                Mesh3d(assets.meshes.finish_line.clone()),
+                MeshMaterial3d(assets.materials.finish_line.clone()),
+            ));
         }
     }
 }
@@ -623,8 +2105,7 @@ pub fn setup_track(
     features: EntityFeatures,
     track: &Track,
     is_bottom: bool,
-    meshes: &mut Assets<Mesh>,
-    materials: &mut Assets<StandardMaterial>,
+    assets: Option<&TrackAssets>,
 ) {
     let bottom_x = -track.origin.position.x + TRACK_ORIGIN_OFFSET.x;
     let bottom_y = -track.origin.position.y + TRACK_ORIGIN_OFFSET.y;
@@ -655,41 +2136,38 @@ pub fn setup_track(
         commands.entity(track_floor_root).insert((
             Collider::cuboid(track.size.x / 2.0, track.size.y / 2.0, FLOOR_HEIGHT / 2.0),
             RigidBody::Fixed,
-            Friction::new(0.5),
+            // The wheel friction is the grip of the surface below them
+            Friction::new(1.0),
         ));
     }
 
-    if features.has_visualization() {
-        let mesh = meshes.add(quad_mesh(track.size.x, track.size.y));
-
-        let material = materials.add(Color::srgba(
-            1.0,
-            1.0,
-            1.0,
-            if is_bottom { 1.0 } else { 0.1 },
-        ));
+    if features.has_visualization()
+        && let Some(assets) = assets
+    {
+        let material = if is_bottom {
+            &assets.materials.floor
+        } else {
+            &assets.materials.floor_transparent
+        };
 
         commands.spawn((
             Transform::from_xyz(bottom_x, bottom_y, 0.0).with_rotation(bottom_rot),
             ChildOf(track_root),
-            Mesh3d(mesh),
-            MeshMaterial3d(material),
+            Mesh3d(assets.meshes.floor.clone()),
+            MeshMaterial3d(material.clone()),
             NotShadowCaster,
         ));
     }
 
     if !is_bottom || features.has_physics() {
-        track.spawn_bundles(
-            track_path_root,
-            track_line_root,
-            features,
-            commands,
-            meshes,
-            materials,
-        );
+        track.spawn_bundles(track_path_root, track_line_root, features, commands, assets);
     }
 }
 
+/// Root of the track entities spawned at startup
+#[derive(Component)]
+pub struct TrackRoot;
+
 pub struct TrackPlugin {
     features: EntityFeatures,
 }
@@ -709,17 +2187,53 @@ impl Plugin for TrackPlugin {
                   track: Res<Track>,
                   mut meshes: ResMut<Assets<Mesh>>,
                   mut materials: ResMut<Assets<StandardMaterial>>| {
-                let track_root = commands.spawn(Transform::default()).id();
+                let assets = features
+                    .has_visualization()
+                    .then(|| TrackAssets::new(&track, &mut meshes, &mut materials));
+                if features.has_physics() {
+                    commands.insert_resource(TrackGeometry::new(&track));
+                }
+                let track_root = commands.spawn((TrackRoot, Transform::default())).id();
                 setup_track(
                     &mut commands,
                     track_root,
                     features,
                     &track,
                     true,
-                    &mut meshes,
-                    &mut materials,
-                )
+                    assets.as_ref(),
+                );
+                if let Some(assets) = assets {
+                    commands.insert_resource(assets);
+                }
             },
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gap_line_sections() {
+        let pattern = GapPattern::new(0.25, 0.25);
+        assert_eq!(pattern.line_sections(1.0), vec![(0.0, 0.25), (0.5, 0.75)]);
+        // The last section is cut at the segment end
+        assert_eq!(
+            pattern.line_sections(0.625),
+            vec![(0.0, 0.25), (0.5, 0.625)]
+        );
+        assert_eq!(pattern.line_sections(0.125), vec![(0.0, 0.125)]);
+        assert!(pattern.line_sections(0.0).is_empty());
+    }
+
+    #[test]
+    fn gap_distance_to_line() {
+        let pattern = GapPattern::new(0.5, 0.25);
+        assert_eq!(pattern.distance_to_line(0.25, 2.0), 0.0);
+        assert_eq!(pattern.distance_to_line(0.625, 2.0), 0.125);
+        assert_eq!(pattern.distance_to_line(0.5625, 2.0), 0.0625);
+        // No line section after the segment end
+        assert_eq!(pattern.distance_to_line(0.6875, 0.7), 0.1875);
+    }
+}
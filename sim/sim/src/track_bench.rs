@@ -0,0 +1,156 @@
+//! Timing of the line sensor readings: rays cast against the segment path
+//! colliders compared with the analytic [`TrackGeometry`] queries.
+
+use std::time::{Duration, Instant};
+
+use bevy::ecs::system::RunSystemOnce;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use executor::wasmtime;
+use rand::{Rng, SeedableRng, rngs::SmallRng};
+
+use crate::app_builder::{HeadlessSetupPlugin, RapierPhysicsSetupPlugin};
+use crate::bot::sensors::line_sensors::{analytic_line_value, raycast_line_value};
+use crate::track::{Track, TrackGeometry, TrackPlugin, TrackSegment};
+use crate::utils::EntityFeatures;
+
+/// Sensor heights and tilts of the sampled readings
+const SENSOR_MIN_Z: f32 = 0.002;
+const SENSOR_MAX_Z: f32 = 0.01;
+const SENSOR_MAX_TILT: f32 = 0.1;
+const MAX_TOI: f32 = 0.1;
+/// Readings further apart than this are reported as mismatches (the arcs
+/// of the path colliders are polygons, the analytic ones are not)
+const MISMATCH_TOLERANCE: f32 = 1.0;
+
+pub struct BenchResult {
+    pub samples: usize,
+    pub raycast: Duration,
+    pub analytic: Duration,
+    /// Samples where the analytic query falls back to a ray (raised segments)
+    pub fallbacks: usize,
+    pub mismatches: usize,
+}
+
+impl BenchResult {
+    pub fn speedup(&self) -> f64 {
+        self.raycast.as_secs_f64() / self.analytic.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+/// Reads line sensors at random positions over the track floor, both ways
+pub fn bench_line_sensors(
+    track: &Track,
+    samples: usize,
+    seed: u64,
+) -> wasmtime::Result<BenchResult> {
+    if samples == 0 {
+        return Err(wasmtime::Error::msg(
+            "the number of samples must be positive",
+        ));
+    }
+
+    let mut rng = SmallRng::seed_from_u64(seed);
+    let center = track.floor_center();
+    let half_size = track.size() / 2.0;
+    let rays: Vec<(Vec3, Vec3)> = (0..samples)
+        .map(|_| {
+            let origin = Vec3::new(
+                center.x + rng.random_range(-half_size.x..=half_size.x),
+                center.y + rng.random_range(-half_size.y..=half_size.y),
+                rng.random_range(SENSOR_MIN_Z..=SENSOR_MAX_Z),
+            );
+            let tilt = Quat::from_euler(
+                EulerRot::XYZ,
+                rng.random_range(-SENSOR_MAX_TILT..=SENSOR_MAX_TILT),
+                rng.random_range(-SENSOR_MAX_TILT..=SENSOR_MAX_TILT),
+                0.0,
+            );
+            (origin, tilt * Vec3::NEG_Z)
+        })
+        .collect();
+
+    // Only the track colliders, without bots
+    let mut app = App::new();
+    app.add_plugins((
+        HeadlessSetupPlugin,
+        TransformPlugin,
+        RapierPhysicsSetupPlugin,
+        TrackPlugin::new(EntityFeatures::Physics),
+    ))
+    .insert_resource(track.clone());
+    app.finish();
+    app.cleanup();
+    // Let the colliders reach the physics query pipeline
+    for _ in 0..2 {
+        app.update();
+    }
+
+    app.world_mut()
+        .run_system_once(
+            move |read_rapier_context: ReadRapierContext,
+                  track_segments_query: Query<(&TrackSegment, &GlobalTransform)>,
+                  geometry: Res<TrackGeometry>| {
+                let rapier_context = read_rapier_context.single().unwrap();
+
+                let start = Instant::now();
+                let raycast_values: Vec<Option<f32>> = rays
+                    .iter()
+                    .map(|&(origin, dir)| {
+                        raycast_line_value(
+                            &rapier_context,
+                            &track_segments_query,
+                            origin,
+                            dir,
+                            MAX_TOI,
+                        )
+                    })
+                    .collect();
+                let raycast = start.elapsed();
+
+                let start = Instant::now();
+                let analytic_values: Vec<Option<f32>> = rays
+                    .iter()
+                    .map(|&(origin, dir)| {
+                        analytic_line_value(&geometry, origin, dir, MAX_TOI).unwrap_or_else(|| {
+                            raycast_line_value(
+                                &rapier_context,
+                                &track_segments_query,
+                                origin,
+                                dir,
+                                MAX_TOI,
+                            )
+                        })
+                    })
+                    .collect();
+                let analytic = start.elapsed();
+
+                let fallbacks = rays
+                    .iter()
+                    .filter(|&&(origin, dir)| {
+                        analytic_line_value(&geometry, origin, dir, MAX_TOI).is_none()
+                    })
+                    .count();
+                let mismatches = raycast_values
+                    .iter()
+                    .zip(&analytic_values)
+                    .filter(|(raycast, analytic)| match (raycast, analytic) {
+                        (Some(raycast), Some(analytic)) => {
+                            (raycast - analytic).abs() > MISMATCH_TOLERANCE
+                        }
+                        (None, None) => false,
+                        _ => true,
+                    })
+                    .count();
+
+                BenchResult {
+                    samples,
+                    raycast,
+                    analytic,
+                    fallbacks,
+                    mismatches,
+                }
+            },
+        )
+        .map_err(|err| wasmtime::Error::msg(format!("benchmark failed: {}", err)))
+}
@@ -0,0 +1,342 @@
+//! Geometric sanity checks over a [`Track`].
+
+use bevy::math::Vec2;
+
+use crate::track::{TRACK_HALF_WIDTH, Track, TrackSegment};
+
+/// Distance between center line samples
+const SAMPLE_STEP: f32 = 0.02;
+/// Two center line points closer than this make the track surfaces overlap
+const OVERLAP_DISTANCE: f32 = TRACK_HALF_WIDTH * 2.0;
+/// Points closer than this along the path are never reported as overlapping
+/// (this tolerates the sharp corner of 90deg turns)
+const OVERLAP_MIN_PATH_DISTANCE: f32 = TRACK_HALF_WIDTH * 4.0;
+/// Tolerances for the end of a closed loop to join its start
+const CLOSURE_MAX_GAP: f32 = 0.01;
+const CLOSURE_MAX_ANGLE: f32 = 1.0;
+/// Ramps steeper than this (rise over length) are hard to climb
+const RAMP_MAX_SLOPE: f32 = 0.3;
+
+#[derive(Debug, Clone, Copy)]
+pub enum TrackIssue {
+    Overlap {
+        first: usize,
+        second: usize,
+        position: Vec2,
+    },
+    OffFloor {
+        segment: usize,
+        position: Vec2,
+    },
+    TightTurn {
+        segment: usize,
+        radius: f32,
+    },
+    NotClosed {
+        gap: f32,
+        angle: f32,
+    },
+    BelowFloor {
+        segment: usize,
+        height: f32,
+    },
+    SteepRamp {
+        segment: usize,
+        slope: f32,
+    },
+}
+
+impl std::fmt::Display for TrackIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            TrackIssue::Overlap {
+                first,
+                second,
+                position,
+            } if first == second => write!(
+                f,
+                "segment {} overlaps itself near ({:.2}, {:.2})",
+                first, position.x, position.y
+            ),
+            TrackIssue::Overlap {
+                first,
+                second,
+                position,
+            } => write!(
+                f,
+                "segment {} overlaps segment {} near ({:.2}, {:.2})",
+                first, second, position.x, position.y
+            ),
+            TrackIssue::OffFloor { segment, position } => write!(
+                f,
+                "segment {} runs off the floor near ({:.2}, {:.2})",
+                segment, position.x, position.y
+            ),
+            TrackIssue::TightTurn { segment, radius } => write!(
+                f,
+                "segment {} turns with radius {} (must be larger than the track half width {})",
+                segment, radius, TRACK_HALF_WIDTH
+            ),
+            TrackIssue::NotClosed { gap, angle } => write!(
+                f,
+                "the closed loop does not join its start ({:.3} m and {:.1} degrees apart)",
+                gap, angle
+            ),
+            TrackIssue::BelowFloor { segment, height } => write!(
+                f,
+                "segment {} goes below the floor ({:.3} m)",
+                segment, height
+            ),
+            TrackIssue::SteepRamp { segment, slope } => write!(
+                f,
+                "segment {} is a ramp with slope {:.0}% (at most {:.0}% is advised)",
+                segment,
+                slope * 100.0,
+                RAMP_MAX_SLOPE * 100.0
+            ),
+        }
+    }
+}
+
+struct Sample {
+    segment: usize,
+    position: Vec2,
+    path_distance: f32,
+}
+
+fn sample_track(track: &Track) -> Vec<Sample> {
+    let mut samples: Vec<Sample> = Vec::new();
+    for (index, (segment, origin)) in track.segment_origins().into_iter().enumerate() {
+        for position in segment.center_line(origin, SAMPLE_STEP) {
+            let path_distance = samples
+                .last()
+                .map(|last| last.path_distance + last.position.distance(position))
+                .unwrap_or(0.0);
+            samples.push(Sample {
+                segment: index,
+                position,
+                path_distance,
+            });
+        }
+    }
+    samples
+}
+
+fn check_turns(track: &Track, issues: &mut Vec<TrackIssue>) {
+    for (index, segment) in track.segments().iter().enumerate() {
+        let radius = match *segment {
+            TrackSegment::NinetyDegTurn(data) => data.line_half_length,
+            TrackSegment::CyrcleTurn(data) => data.radius,
+            _ => continue,
+        };
+        if radius <= TRACK_HALF_WIDTH {
+            issues.push(TrackIssue::TightTurn {
+                segment: index,
+                radius,
+            });
+        }
+    }
+}
+
+fn is_off_floor(track: &Track, position: Vec2) -> bool {
+    let floor_max = track.size() / 2.0 - Vec2::splat(TRACK_HALF_WIDTH);
+    position.abs().cmpgt(floor_max).any()
+}
+
+fn check_floor(track: &Track, samples: &[Sample], issues: &mut Vec<TrackIssue>) {
+    let mut last_reported = None;
+    for sample in samples {
+        let outside = is_off_floor(track, sample.position);
+        if outside && last_reported != Some(sample.segment) {
+            issues.push(TrackIssue::OffFloor {
+                segment: sample.segment,
+                position: sample.position,
+            });
+            last_reported = Some(sample.segment);
+        }
+    }
+}
+
+fn check_closure(track: &Track, issues: &mut Vec<TrackIssue>) {
+    if track.laps().is_none() {
+        return;
+    }
+    let segment_origins = track.segment_origins();
+    let (Some(&(_, first_origin)), Some(&(last_segment, last_origin))) =
+        (segment_origins.first(), segment_origins.last())
+    else {
+        return;
+    };
+    let end = last_segment.compute_next_origin(last_origin);
+    let gap = end
+        .position()
+        .extend(end.height())
+        .distance(first_origin.position().extend(first_origin.height()));
+    let angle =
+        (end.direction().to_degrees() - first_origin.direction().to_degrees()).rem_euclid(360.0);
+    let angle = angle.min(360.0 - angle);
+    if gap > CLOSURE_MAX_GAP || angle > CLOSURE_MAX_ANGLE {
+        issues.push(TrackIssue::NotClosed { gap, angle });
+    }
+}
+
+fn check_heights(track: &Track, issues: &mut Vec<TrackIssue>) {
+    for (index, (segment, origin)) in track.segment_origins().into_iter().enumerate() {
+        let (start_height, end_height) = segment.heights(origin);
+        let height = start_height.min(end_height);
+        if height < 0.0 {
+            issues.push(TrackIssue::BelowFloor {
+                segment: index,
+                height,
+            });
+        }
+        let slope = segment.rise().abs() / segment.length();
+        if slope > RAMP_MAX_SLOPE {
+            issues.push(TrackIssue::SteepRamp {
+                segment: index,
+                slope,
+            });
+        }
+    }
+}
+
+fn check_overlaps(track: &Track, samples: &[Sample], issues: &mut Vec<TrackIssue>) {
+    // On closed loops the path goes on past the last segment into the first
+    let loop_length = track
+        .laps()
+        .and_then(|_| samples.last())
+        .map(|last| last.path_distance);
+    let path_distance = |first: &Sample, second: &Sample| {
+        let distance = second.path_distance - first.path_distance;
+        match loop_length {
+            Some(loop_length) => distance.min(loop_length - distance),
+            None => distance,
+        }
+    };
+    let is_crossing = |index: usize| matches!(track.segments()[index], TrackSegment::Crossing(_));
+    let mut reported: Vec<(usize, usize)> = Vec::new();
+    for (i, first) in samples.iter().enumerate() {
+        for second in &samples[i + 1..] {
+            if path_distance(first, second) < OVERLAP_MIN_PATH_DISTANCE
+                || first.position.distance(second.position) >= OVERLAP_DISTANCE
+            {
+                continue;
+            }
+            if is_crossing(first.segment) || is_crossing(second.segment) {
+                continue;
+            }
+            let pair = (first.segment, second.segment);
+            if !reported.contains(&pair) {
+                reported.push(pair);
+                issues.push(TrackIssue::Overlap {
+                    first: first.segment,
+                    second: second.segment,
+                    position: (first.position + second.position) / 2.0,
+                });
+            }
+        }
+    }
+}
+
+/// Whether one segment stays on the floor, a quick test to run before
+/// [`check_track`] (which samples the whole track for overlaps)
+pub fn segment_on_floor(track: &Track, index: usize) -> bool {
+    track
+        .segment_origins()
+        .get(index)
+        .is_some_and(|&(segment, origin)| {
+            segment
+                .center_line(origin, SAMPLE_STEP)
+                .into_iter()
+                .all(|position| !is_off_floor(track, position))
+        })
+}
+
+/// Looks for overlapping segments (deliberate crossings excepted), segments
+/// outside the floor or below it, turns too tight for the track width, steep
+/// ramps and closed loops that do not close
+pub fn check_track(track: &Track) -> Vec<TrackIssue> {
+    let samples = sample_track(track);
+    let mut issues = Vec::new();
+    check_turns(track, &mut issues);
+    check_heights(track, &mut issues);
+    check_closure(track, &mut issues);
+    check_floor(track, &samples, &mut issues);
+    check_overlaps(track, &samples, &mut issues);
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::text::cosmic_text::Angle;
+
+    use super::*;
+    use crate::utils::Side;
+
+    fn open_track(pieces: &[TrackSegment]) -> Track {
+        let mut segments = vec![TrackSegment::start()];
+        segments.extend_from_slice(pieces);
+        segments.push(TrackSegment::end());
+        Track::new(segments)
+    }
+
+    #[test]
+    fn clean_track() {
+        let track = open_track(&[
+            TrackSegment::straight(1.0),
+            TrackSegment::cyrcle_turn(0.5, Angle::from_degrees(90.0), Side::Left),
+            TrackSegment::straight(1.0),
+            TrackSegment::ninety_deg_turn(0.3, Side::Right),
+            TrackSegment::straight(0.5),
+        ]);
+        let issues = check_track(&track);
+        assert!(issues.is_empty(), "unexpected issues: {:?}", issues);
+    }
+
+    #[test]
+    fn overlapping_loop() {
+        // A full circle comes back across its own entry
+        let track = open_track(&[
+            TrackSegment::straight(0.5),
+            TrackSegment::cyrcle_turn(0.3, Angle::from_degrees(360.0), Side::Left),
+            TrackSegment::straight(0.5),
+        ]);
+        let issues = check_track(&track);
+        assert!(
+            issues
+                .iter()
+                .any(|issue| matches!(issue, TrackIssue::Overlap { .. })),
+            "no overlap in {:?}",
+            issues
+        );
+    }
+
+    #[test]
+    fn tight_turn() {
+        let track = open_track(&[TrackSegment::cyrcle_turn(
+            TRACK_HALF_WIDTH / 2.0,
+            Angle::from_degrees(90.0),
+            Side::Right,
+        )]);
+        assert!(
+            check_track(&track)
+                .iter()
+                .any(|issue| matches!(issue, TrackIssue::TightTurn { segment: 1, .. }))
+        );
+    }
+
+    #[test]
+    fn open_loop() {
+        let track = Track::new(vec![TrackSegment::cyrcle_turn(
+            0.5,
+            Angle::from_degrees(270.0),
+            Side::Left,
+        )])
+        .with_laps(1);
+        assert!(
+            check_track(&track)
+                .iter()
+                .any(|issue| matches!(issue, TrackIssue::NotClosed { .. }))
+        );
+    }
+}
@@ -0,0 +1,677 @@
+//! Export of tracks as true scale vector drawings (SVG or PDF).
+//!
+//! The drawing has the floor border, the outline of the track band, the line
+//! (with its gaps and inverted sections) and the start/end tips. It can be
+//! drawn on a single page as large as the floor or tiled on A4/A3 pages: every
+//! tile then has alignment marks at the corners of its printable area, so that
+//! neighbouring pages can be overlapped and taped together.
+
+use std::fmt::Write as _;
+
+use bevy::math::{Rect, Vec2};
+use clap::{ArgEnum, ValueEnum};
+use executor::wasmtime;
+
+use crate::track::{TRACK_HALF_WIDTH, Track};
+
+/// Blank border around the printable area of tiled pages (mm)
+const PAGE_MARGIN: f32 = 10.0;
+/// Paper left around the track when tiling (mm)
+const TILED_TRACK_MARGIN: f32 = 20.0;
+/// Length of the alignment mark arms (mm)
+const MARK_LENGTH: f32 = 5.0;
+const OUTLINE_WIDTH: f32 = 0.3;
+const MARK_WIDTH: f32 = 0.2;
+const MM_PER_POINT: f32 = 25.4 / 72.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PageSize {
+    A4,
+    A3,
+}
+
+impl std::str::FromStr for PageSize {
+    type Err = wasmtime::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <PageSize as ArgEnum>::from_str(s, true)
+            .map_err(|err| wasmtime::Error::msg(err.to_string()))
+    }
+}
+
+impl PageSize {
+    /// Portrait size in mm
+    fn size(&self) -> Vec2 {
+        match self {
+            PageSize::A4 => Vec2::new(210.0, 297.0),
+            PageSize::A3 => Vec2::new(297.0, 420.0),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Rgb(f32, f32, f32);
+
+const BLACK: Rgb = Rgb(0.0, 0.0, 0.0);
+const WHITE: Rgb = Rgb(1.0, 1.0, 1.0);
+const GRAY: Rgb = Rgb(0.6, 0.6, 0.6);
+const RED: Rgb = Rgb(0.8, 0.0, 0.0);
+
+impl Rgb {
+    fn svg(&self) -> String {
+        format!(
+            "#{:02x}{:02x}{:02x}",
+            (self.0 * 255.0) as u8,
+            (self.1 * 255.0) as u8,
+            (self.2 * 255.0) as u8
+        )
+    }
+}
+
+enum Paint {
+    Fill(Rgb),
+    Stroke(Rgb, f32),
+}
+
+/// A closed polygon in mm, with y pointing up and (0, 0) at the bottom left
+/// corner of the floor
+struct Shape {
+    points: Vec<Vec2>,
+    paint: Paint,
+}
+
+impl Shape {
+    fn bounds(&self) -> Rect {
+        self.points
+            .iter()
+            .fold(Rect::EMPTY, |rect, &point| rect.union_point(point))
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+        self.points
+            .iter()
+            .zip(self.points.iter().cycle().skip(1))
+            .map(|(&from, &to)| (from, to))
+    }
+
+    fn contains(&self, point: Vec2) -> bool {
+        // Even-odd rule
+        self.edges()
+            .filter(|&(from, to)| {
+                (from.y > point.y) != (to.y > point.y)
+                    && point.x < from.x + (point.y - from.y) / (to.y - from.y) * (to.x - from.x)
+            })
+            .count()
+            % 2
+            == 1
+    }
+
+    /// True if any part of the shape is inside `rect`
+    fn touches(&self, rect: Rect) -> bool {
+        if self.bounds().intersect(rect).is_empty() {
+            return false;
+        }
+        self.points.iter().any(|&point| rect.contains(point))
+            || self
+                .edges()
+                .any(|(from, to)| segment_touches(from, to, rect))
+            || self.contains(rect.center())
+    }
+}
+
+/// Clips the segment to `rect` (Liang-Barsky), true if anything is left
+fn segment_touches(from: Vec2, to: Vec2, rect: Rect) -> bool {
+    let delta = to - from;
+    let (mut enter, mut exit) = (0.0f32, 1.0f32);
+    for (p, q) in [
+        (-delta.x, from.x - rect.min.x),
+        (delta.x, rect.max.x - from.x),
+        (-delta.y, from.y - rect.min.y),
+        (delta.y, rect.max.y - from.y),
+    ] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return false;
+            }
+        } else {
+            let t = q / p;
+            if p < 0.0 {
+                enter = enter.max(t);
+            } else {
+                exit = exit.min(t);
+            }
+        }
+    }
+    enter <= exit
+}
+
+/// A page showing the `area` of the drawing, placed at `offset` on a page
+/// of the given size (all in mm)
+struct Page {
+    size: Vec2,
+    offset: Vec2,
+    area: Rect,
+    /// Row and column of tiled pages
+    tile: Option<(usize, usize)>,
+}
+
+impl Page {
+    /// The shapes (partially) inside the page area
+    fn shapes<'a>(&self, shapes: &'a [Shape]) -> impl Iterator<Item = &'a Shape> {
+        let area = self.area;
+        shapes.iter().filter(move |shape| shape.touches(area))
+    }
+
+    fn label(&self) -> Option<String> {
+        self.tile
+            .map(|(row, column)| format!("row {}  column {}", row + 1, column + 1))
+    }
+}
+
+fn to_mm(track: &Track, point: Vec2) -> Vec2 {
+    (point + track.size() / 2.0) * 1000.0
+}
+
+fn floor_shape(track: &Track) -> Shape {
+    let half_size = track.size() / 2.0;
+    Shape {
+        points: [
+            -half_size,
+            Vec2::new(half_size.x, -half_size.y),
+            half_size,
+            Vec2::new(-half_size.x, half_size.y),
+        ]
+        .into_iter()
+        .map(|point| to_mm(track, point))
+        .collect(),
+        paint: Paint::Stroke(GRAY, OUTLINE_WIDTH),
+    }
+}
+
+fn track_shapes(track: &Track) -> Vec<Shape> {
+    let polygon = |points: Vec<Vec2>, paint: Paint| Shape {
+        points: points
+            .into_iter()
+            .map(|point| to_mm(track, point))
+            .collect(),
+        paint,
+    };
+
+    let mut shapes = Vec::new();
+    let segment_origins = track.segment_origins();
+    for &(segment, origin) in &segment_origins {
+        for band in segment.band_outline(origin) {
+            let paint = if segment.line_style().inverted {
+                Paint::Fill(BLACK)
+            } else {
+                Paint::Stroke(GRAY, OUTLINE_WIDTH)
+            };
+            shapes.push(polygon(band, paint));
+        }
+    }
+    // Lines go over every band, also where the track crosses itself
+    for &(segment, origin) in &segment_origins {
+        let color = if segment.line_style().inverted {
+            WHITE
+        } else {
+            BLACK
+        };
+        for line in segment
+            .line_outline(origin)
+            .into_iter()
+            .chain(segment.marker_outline(origin))
+        {
+            shapes.push(polygon(line, Paint::Fill(color)));
+        }
+    }
+    if let Some(finish_line) = track.finish_line() {
+        let center = finish_line.position() - track.floor_center().truncate();
+        let across = Vec2::from_angle(finish_line.direction().to_radians());
+        let along = across.perp() * 0.002;
+        let across = across * TRACK_HALF_WIDTH;
+        shapes.push(polygon(
+            vec![
+                center - across - along,
+                center + across - along,
+                center + across + along,
+                center - across + along,
+            ],
+            Paint::Fill(RED),
+        ));
+    }
+    shapes
+}
+
+/// Splits the track area in tiles, using the page orientation that needs
+/// fewer pages (tiles without any part of the track are left out)
+fn tile_pages(shapes: &[Shape], page_size: PageSize) -> Vec<Page> {
+    let extent = shapes
+        .iter()
+        .fold(Rect::EMPTY, |rect, shape| rect.union(shape.bounds()))
+        .inflate(TILED_TRACK_MARGIN);
+    let portrait = page_size.size();
+    let landscape = Vec2::new(portrait.y, portrait.x);
+    let tiles = |page: Vec2| (extent.size() / (page - Vec2::splat(PAGE_MARGIN * 2.0))).ceil();
+    let page = if tiles(landscape).element_product() < tiles(portrait).element_product() {
+        landscape
+    } else {
+        portrait
+    };
+    let area_size = page - Vec2::splat(PAGE_MARGIN * 2.0);
+    let count = tiles(page);
+    let (columns, rows) = (count.x as usize, count.y as usize);
+
+    let mut pages = Vec::with_capacity(columns * rows);
+    // From the top left corner, as the pages are read
+    for row in 0..rows {
+        for column in 0..columns {
+            let area_min = Vec2::new(
+                extent.min.x + column as f32 * area_size.x,
+                extent.max.y - (row + 1) as f32 * area_size.y,
+            );
+            let page = Page {
+                size: page,
+                offset: Vec2::splat(PAGE_MARGIN),
+                area: Rect::from_corners(area_min, area_min + area_size),
+                tile: Some((row, column)),
+            };
+            if page.shapes(shapes).next().is_some() {
+                pages.push(page);
+            }
+        }
+    }
+    pages
+}
+
+/// Corner crosses of the printable area, in page coordinates
+fn alignment_marks(page: &Page) -> Vec<(Vec2, Vec2)> {
+    let min = page.offset;
+    let max = page.offset + page.area.size();
+    [min, Vec2::new(max.x, min.y), max, Vec2::new(min.x, max.y)]
+        .into_iter()
+        .flat_map(|corner| {
+            [
+                (
+                    corner - Vec2::X * MARK_LENGTH,
+                    corner + Vec2::X * MARK_LENGTH,
+                ),
+                (
+                    corner - Vec2::Y * MARK_LENGTH,
+                    corner + Vec2::Y * MARK_LENGTH,
+                ),
+            ]
+        })
+        .collect()
+}
+
+fn svg_document(shapes: &[Shape], page: &Page) -> String {
+    let mut svg = String::new();
+    // SVG y points down: flip the drawing and the page
+    let flip = |point: Vec2| Vec2::new(point.x, page.size.y - point.y);
+    let shift = page.offset - page.area.min;
+
+    writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}mm\" height=\"{h}mm\" viewBox=\"0 0 {w} {h}\">",
+        w = page.size.x,
+        h = page.size.y
+    )
+    .unwrap();
+    let clip_min = flip(page.offset + Vec2::Y * page.area.size().y);
+    writeln!(
+        svg,
+        "<clipPath id=\"area\"><rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/></clipPath>",
+        clip_min.x,
+        clip_min.y,
+        page.area.size().x,
+        page.area.size().y
+    )
+    .unwrap();
+    writeln!(svg, "<g clip-path=\"url(#area)\">").unwrap();
+    for shape in page.shapes(shapes) {
+        let points: Vec<String> = shape
+            .points
+            .iter()
+            .map(|&point| {
+                let point = flip(point + shift);
+                format!("{:.3},{:.3}", point.x, point.y)
+            })
+            .collect();
+        let paint = match shape.paint {
+            Paint::Fill(color) => format!("fill=\"{}\"", color.svg()),
+            Paint::Stroke(color, width) => format!(
+                "fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"",
+                color.svg(),
+                width
+            ),
+        };
+        writeln!(svg, "<polygon points=\"{}\" {}/>", points.join(" "), paint).unwrap();
+    }
+    writeln!(svg, "</g>").unwrap();
+
+    if let Some(label) = page.label() {
+        for (from, to) in alignment_marks(page) {
+            let (from, to) = (flip(from), flip(to));
+            writeln!(
+                svg,
+                "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"black\" stroke-width=\"{}\"/>",
+                from.x, from.y, to.x, to.y, MARK_WIDTH
+            )
+            .unwrap();
+        }
+        let position = flip(
+            page.offset + Vec2::new(MARK_LENGTH * 2.0, page.area.size().y + PAGE_MARGIN / 2.0),
+        );
+        writeln!(
+            svg,
+            "<text x=\"{}\" y=\"{}\" font-family=\"sans-serif\" font-size=\"3\">{}</text>",
+            position.x, position.y, label
+        )
+        .unwrap();
+    }
+    writeln!(svg, "</svg>").unwrap();
+    svg
+}
+
+fn pdf_page_content(shapes: &[Shape], page: &Page) -> String {
+    let mut content = String::new();
+    let pt = |mm: f32| mm / MM_PER_POINT;
+    let shift = page.offset - page.area.min;
+
+    // Drawing, clipped to the printable area
+    writeln!(
+        content,
+        "q {:.3} {:.3} {:.3} {:.3} re W n",
+        pt(page.offset.x),
+        pt(page.offset.y),
+        pt(page.area.size().x),
+        pt(page.area.size().y)
+    )
+    .unwrap();
+    for shape in page.shapes(shapes) {
+        let (color, operator) = match shape.paint {
+            Paint::Fill(color) => (format!("{} {} {} rg", color.0, color.1, color.2), "f"),
+            Paint::Stroke(color, width) => (
+                format!("{} {} {} RG {:.3} w", color.0, color.1, color.2, pt(width)),
+                "S",
+            ),
+        };
+        writeln!(content, "{}", color).unwrap();
+        for (index, &point) in shape.points.iter().enumerate() {
+            let point = point + shift;
+            let operator = if index == 0 { "m" } else { "l" };
+            writeln!(
+                content,
+                "{:.3} {:.3} {}",
+                pt(point.x),
+                pt(point.y),
+                operator
+            )
+            .unwrap();
+        }
+        writeln!(content, "h {}", operator).unwrap();
+    }
+    writeln!(content, "Q").unwrap();
+
+    if let Some(label) = page.label() {
+        writeln!(content, "0 0 0 RG {:.3} w", pt(MARK_WIDTH)).unwrap();
+        for (from, to) in alignment_marks(page) {
+            writeln!(
+                content,
+                "{:.3} {:.3} m {:.3} {:.3} l S",
+                pt(from.x),
+                pt(from.y),
+                pt(to.x),
+                pt(to.y)
+            )
+            .unwrap();
+        }
+        let position =
+            page.offset + Vec2::new(MARK_LENGTH * 2.0, page.area.size().y + PAGE_MARGIN / 2.0);
+        writeln!(
+            content,
+            "0 0 0 rg BT /F1 8 Tf {:.3} {:.3} Td ({}) Tj ET",
+            pt(position.x),
+            pt(position.y),
+            label
+        )
+        .unwrap();
+    }
+    content
+}
+
+/// A minimal PDF: one content stream per page and the standard Helvetica
+/// font for the page labels
+fn pdf_document(shapes: &[Shape], pages: &[Page]) -> Vec<u8> {
+    let pt = |mm: f32| mm / MM_PER_POINT;
+    // Objects: 1 catalog, 2 page tree, 3 font, then a page and its content
+    // for each page
+    let mut objects: Vec<String> = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            (0..pages.len())
+                .map(|index| format!("{} 0 R", 4 + index * 2))
+                .collect::<Vec<_>>()
+                .join(" "),
+            pages.len()
+        ),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_string(),
+    ];
+    for (index, page) in pages.iter().enumerate() {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.3} {:.3}] /Contents {} 0 R /Resources << /Font << /F1 3 0 R >> >> >>",
+            pt(page.size.x),
+            pt(page.size.y),
+            5 + index * 2
+        ));
+        let content = pdf_page_content(shapes, page);
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            content.len(),
+            content
+        ));
+    }
+
+    let mut pdf = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        write!(pdf, "{} 0 obj\n{}\nendobj\n", index + 1, object).unwrap();
+    }
+    let xref_offset = pdf.len();
+    write!(pdf, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).unwrap();
+    for offset in offsets {
+        writeln!(pdf, "{:010} 00000 n ", offset).unwrap();
+    }
+    write!(
+        pdf,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref_offset
+    )
+    .unwrap();
+    pdf.into_bytes()
+}
+
+fn write_file(path: &str, contents: &[u8]) -> wasmtime::Result<()> {
+    std::fs::write(path, contents)
+        .map_err(|err| wasmtime::Error::msg(format!("cannot write \"{}\": {}", path, err)))
+}
+
+/// Writes the track drawing at 1:1 scale to `path` (the format comes from
+/// the extension, `.svg` or `.pdf`), on a single page or tiled on pages of
+/// the given size.
+///
+/// SVG has no pages: tiles are written to separate files named after
+/// `path` with their row and column. Returns the written paths.
+pub fn export_track(
+    track: &Track,
+    path: &str,
+    page_size: Option<PageSize>,
+) -> wasmtime::Result<Vec<String>> {
+    let mut shapes = track_shapes(track);
+    let pages = match page_size {
+        Some(page_size) => tile_pages(&shapes, page_size),
+        None => {
+            shapes.insert(0, floor_shape(track));
+            let size = track.size() * 1000.0;
+            vec![Page {
+                size,
+                offset: Vec2::ZERO,
+                area: Rect::from_corners(Vec2::ZERO, size),
+                tile: None,
+            }]
+        }
+    };
+
+    let extension = std::path::Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase());
+    match extension.as_deref() {
+        Some("pdf") => {
+            write_file(path, &pdf_document(&shapes, &pages))?;
+            Ok(vec![path.to_string()])
+        }
+        Some("svg") => {
+            let stem = &path[..path.len() - ".svg".len()];
+            pages
+                .iter()
+                .map(|page| {
+                    let page_path = match page.tile {
+                        Some((row, column)) => format!("{}-{}-{}.svg", stem, row + 1, column + 1),
+                        None => path.to_string(),
+                    };
+                    write_file(&page_path, svg_document(&shapes, page).as_bytes())?;
+                    Ok(page_path)
+                })
+                .collect()
+        }
+        _ => Err(wasmtime::Error::msg(format!(
+            "cannot export to \"{}\": the file extension must be .svg or .pdf",
+            path
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::track::TrackSegment;
+
+    fn rect_shape(min: Vec2, max: Vec2) -> Shape {
+        Shape {
+            points: vec![min, Vec2::new(max.x, min.y), max, Vec2::new(min.x, max.y)],
+            paint: Paint::Fill(BLACK),
+        }
+    }
+
+    #[test]
+    fn segment_clipping() {
+        let rect = Rect::new(0.0, 0.0, 10.0, 10.0);
+        // Inside, across and ending on an edge
+        assert!(segment_touches(
+            Vec2::new(2.0, 2.0),
+            Vec2::new(8.0, 3.0),
+            rect
+        ));
+        assert!(segment_touches(
+            Vec2::new(-5.0, 5.0),
+            Vec2::new(15.0, 6.0),
+            rect
+        ));
+        assert!(segment_touches(
+            Vec2::new(-5.0, 5.0),
+            Vec2::new(0.0, 5.0),
+            rect
+        ));
+        // Parallel to an edge, outside
+        assert!(!segment_touches(
+            Vec2::new(-5.0, 11.0),
+            Vec2::new(15.0, 11.0),
+            rect
+        ));
+        assert!(!segment_touches(
+            Vec2::new(12.0, -5.0),
+            Vec2::new(12.0, 15.0),
+            rect
+        ));
+        // Passing by a corner
+        assert!(!segment_touches(
+            Vec2::new(8.0, 13.0),
+            Vec2::new(13.0, 8.0),
+            rect
+        ));
+        // Stopping short of the rect
+        assert!(!segment_touches(
+            Vec2::new(-5.0, 5.0),
+            Vec2::new(-1.0, 5.0),
+            rect
+        ));
+    }
+
+    #[test]
+    fn tiles_of_a_straight_track() {
+        // 200 mm wide and 2000 mm long with the tips, 240 x 2040 mm with the
+        // margin: 1 x 11 landscape A4 pages (2 x 8 portrait ones)
+        let track = Track::new(vec![
+            TrackSegment::start(),
+            TrackSegment::straight(1.0),
+            TrackSegment::end(),
+        ]);
+        let pages = tile_pages(&track_shapes(&track), PageSize::A4);
+        assert_eq!(pages.len(), 11);
+        assert!(
+            pages
+                .iter()
+                .all(|page| page.size == Vec2::new(297.0, 210.0))
+        );
+    }
+
+    #[test]
+    fn empty_tiles_are_left_out() {
+        // An L along the bottom and left borders of a 4 x 3 portrait A4 grid
+        let shapes = [
+            rect_shape(Vec2::ZERO, Vec2::new(600.0, 10.0)),
+            rect_shape(Vec2::ZERO, Vec2::new(10.0, 600.0)),
+        ];
+        let pages = tile_pages(&shapes, PageSize::A4);
+        let mut tiles: Vec<(usize, usize)> = pages.iter().filter_map(|page| page.tile).collect();
+        tiles.sort();
+        assert_eq!(tiles, vec![(0, 0), (1, 0), (2, 0), (2, 1), (2, 2), (2, 3)]);
+        assert!(
+            pages
+                .iter()
+                .all(|page| page.size == Vec2::new(210.0, 297.0))
+        );
+    }
+
+    #[test]
+    fn pdf_cross_reference_offsets() {
+        let shapes = [rect_shape(Vec2::ZERO, Vec2::new(500.0, 140.0))];
+        let pages = tile_pages(&shapes, PageSize::A4);
+        assert_eq!(pages.len(), 2);
+        let pdf = String::from_utf8(pdf_document(&shapes, &pages)).unwrap();
+
+        let startxref = pdf.rfind("startxref\n").unwrap() + "startxref\n".len();
+        let xref_offset: usize = pdf[startxref..].lines().next().unwrap().parse().unwrap();
+        let mut lines = pdf[xref_offset..].lines();
+        assert_eq!(lines.next(), Some("xref"));
+        // Catalog, page tree, font and a page and its content per page
+        assert_eq!(lines.next(), Some("0 8"));
+        assert_eq!(lines.next(), Some("0000000000 65535 f "));
+        for object in 1..8 {
+            let entry = lines.next().unwrap();
+            assert_eq!(entry.len(), 19, "bad entry {:?}", entry);
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(
+                pdf[offset..].starts_with(&format!("{} 0 obj\n", object)),
+                "object {} is not at {}",
+                object,
+                offset
+            );
+        }
+        assert_eq!(lines.next(), Some("trailer"));
+    }
+}
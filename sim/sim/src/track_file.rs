@@ -0,0 +1,997 @@
+//! Declarative track descriptions, stored as RON files.
+//!
+//! A track file looks like this (lengths in meters, angles in degrees):
+//!
+//! ```ron
+//! (
+//!     segments: [
+//!         Start,
+//!         Straight(length: 2.0),
+//!         NinetyDegTurn(side: Right, half_length: 0.5),
+//!         CircleTurn(side: Left, angle: 120.0, radius: 1.0),
+//!         End,
+//!     ],
+//! )
+//! ```
+//!
+//! Straight segments and circle turns can be interrupted by gaps, e.g.
+//! `Straight(length: 1.0, gaps: (line: 0.1, gap: 0.05))`.
+//!
+//! Any segment but the tips can change its line width (in meters) or become
+//! inverted (a white line on a black band), e.g.
+//! `Straight(length: 1.0, line: (width: 0.03, inverted: true))`.
+//!
+//! Any segment but the tips can have side markers (bars in the line color
+//! beside the line, as in Robotrace rules) at its start and end, e.g.
+//! `CircleTurn(side: Left, angle: 90.0, radius: 0.5, markers: (start_left: true))`.
+//!
+//! Any segment but the tips can have a surface with less or more grip for
+//! the wheels: `Dusty`, `Slippery`, `HighGrip` or a custom friction
+//! coefficient, e.g. `CircleTurn(side: Right, angle: 90.0, radius: 0.4, surface: Slippery)`
+//! or `Straight(length: 1.0, surface: Friction(0.6))`.
+//!
+//! Tracks are flat unless a straight becomes a ramp with a `rise` (in
+//! meters, negative going down), e.g. `Straight(length: 1.0, rise: 0.1)`, or
+//! a segment starts with a height `step` (like the seam between two tiles),
+//! e.g. `NinetyDegTurn(side: Left, half_length: 0.3, step: 0.002)`.
+//!
+//! Where the path deliberately crosses itself one of the two passes must be
+//! a `Crossing(length: 0.4)` segment (a straight that is allowed to overlap).
+//!
+//! `laps: 3` makes the track a closed loop: the segments (without `Start` and
+//! `End`) must join back to the first one, where the start/finish line is.
+//!
+//! Static obstacles stand on the track at a distance `along` the center line
+//! of a segment and `offset` meters to its left (right when negative); a bot
+//! touching one is disqualified:
+//!
+//! ```ron
+//! obstacles: [
+//!     (segment: 2, along: 0.5, offset: 0.06, shape: Box(width: 0.05, length: 0.05, height: 0.1)),
+//!     (segment: 4, along: 0.2, shape: Cylinder(radius: 0.03, height: 0.1)),
+//! ],
+//! ```
+//!
+//! The floor size and the origin are computed from the segments; they can
+//! still be set by hand with `size: (5.2, 7.2)` and `origin: (x: 0.4, y: -2.5)`.
+//! `direction: 2.0` rotates the whole track around its start.
+
+use bevy::{
+    math::{Vec2, Vec3},
+    text::cosmic_text::Angle,
+};
+use executor::wasmtime;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    track::{
+        GapPattern, LineStyle, Obstacle, ObstacleShape, SideMarkers, Surface, TRACK_HALF_WIDTH,
+        Track, TrackSegment,
+    },
+    utils::Side,
+};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct OriginDescription {
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct GapsDescription {
+    pub line: f32,
+    pub gap: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SegmentDescription {
+    Start,
+    End,
+    Straight {
+        length: f32,
+        #[serde(default, skip_serializing_if = "is_zero")]
+        rise: f32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        gaps: Option<GapsDescription>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        line: Option<LineDescription>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        markers: Option<MarkersDescription>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        surface: Option<SurfaceDescription>,
+        #[serde(default, skip_serializing_if = "is_zero")]
+        step: f32,
+    },
+    Crossing {
+        length: f32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        line: Option<LineDescription>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        markers: Option<MarkersDescription>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        surface: Option<SurfaceDescription>,
+        #[serde(default, skip_serializing_if = "is_zero")]
+        step: f32,
+    },
+    NinetyDegTurn {
+        side: Side,
+        half_length: f32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        line: Option<LineDescription>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        markers: Option<MarkersDescription>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        surface: Option<SurfaceDescription>,
+        #[serde(default, skip_serializing_if = "is_zero")]
+        step: f32,
+    },
+    CircleTurn {
+        side: Side,
+        angle: f32,
+        radius: f32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        gaps: Option<GapsDescription>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        line: Option<LineDescription>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        markers: Option<MarkersDescription>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        surface: Option<SurfaceDescription>,
+        #[serde(default, skip_serializing_if = "is_zero")]
+        step: f32,
+    },
+}
+
+/// Line width (in meters) and colors, when different from the default
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct LineDescription {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<f32>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub inverted: bool,
+}
+
+impl LineDescription {
+    fn from_style(style: LineStyle) -> Option<Self> {
+        let default = LineStyle::default();
+        if style == default {
+            return None;
+        }
+        Some(Self {
+            width: (style.half_width != default.half_width).then_some(style.half_width * 2.0),
+            inverted: style.inverted,
+        })
+    }
+}
+
+/// Side markers at the start and at the end of a segment
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct MarkersDescription {
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub start_left: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub start_right: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub end_left: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub end_right: bool,
+}
+
+impl MarkersDescription {
+    fn from_markers(markers: SideMarkers) -> Option<Self> {
+        (!markers.is_empty()).then_some(Self {
+            start_left: markers.start_left,
+            start_right: markers.start_right,
+            end_left: markers.end_left,
+            end_right: markers.end_right,
+        })
+    }
+}
+
+fn with_markers(segment: TrackSegment, markers: Option<MarkersDescription>) -> TrackSegment {
+    match markers {
+        Some(markers) => segment.with_markers(SideMarkers {
+            start_left: markers.start_left,
+            start_right: markers.start_right,
+            end_left: markers.end_left,
+            end_right: markers.end_right,
+        }),
+        None => segment,
+    }
+}
+
+/// The surface under the wheels, when different from the normal one
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum SurfaceDescription {
+    Normal,
+    Dusty,
+    Slippery,
+    HighGrip,
+    Friction(f32),
+}
+
+impl SurfaceDescription {
+    fn from_surface(surface: Surface) -> Option<Self> {
+        match surface {
+            Surface::Normal => None,
+            Surface::Dusty => Some(Self::Dusty),
+            Surface::Slippery => Some(Self::Slippery),
+            Surface::HighGrip => Some(Self::HighGrip),
+            Surface::Friction(friction) => Some(Self::Friction(friction)),
+        }
+    }
+
+    fn to_surface(self) -> Surface {
+        match self {
+            Self::Normal => Surface::Normal,
+            Self::Dusty => Surface::Dusty,
+            Self::Slippery => Surface::Slippery,
+            Self::HighGrip => Surface::HighGrip,
+            Self::Friction(friction) => Surface::Friction(friction),
+        }
+    }
+}
+
+fn validate_surface(surface: Option<SurfaceDescription>) -> Result<(), String> {
+    match surface {
+        Some(SurfaceDescription::Friction(friction))
+            if !(friction.is_finite() && friction >= 0.0) =>
+        {
+            Err(format!(
+                "surface friction must be a non negative number (found {})",
+                friction
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn validate_line(line: Option<LineDescription>) -> Result<(), String> {
+    match line.and_then(|line| line.width) {
+        Some(width) if !(width.is_finite() && width > 0.0 && width <= TRACK_HALF_WIDTH * 2.0) => {
+            Err(format!(
+                "line width must be positive and at most the track width {} (found {})",
+                TRACK_HALF_WIDTH * 2.0,
+                width
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn with_line(segment: TrackSegment, line: Option<LineDescription>) -> TrackSegment {
+    match line {
+        Some(line) => {
+            let default = LineStyle::default();
+            segment.with_line_style(LineStyle {
+                half_width: line.width.map_or(default.half_width, |width| width / 2.0),
+                inverted: line.inverted,
+            })
+        }
+        None => segment,
+    }
+}
+
+impl GapsDescription {
+    fn from_pattern(pattern: GapPattern) -> Self {
+        Self {
+            line: pattern.line,
+            gap: pattern.gap,
+        }
+    }
+}
+
+fn validate_gaps(gaps: Option<GapsDescription>) -> Result<(), String> {
+    match gaps {
+        Some(gaps) if !(gaps.line.is_finite() && gaps.line > 0.0) => Err(format!(
+            "gaps line must be a positive number (found {})",
+            gaps.line
+        )),
+        Some(gaps) if !(gaps.gap.is_finite() && gaps.gap > 0.0) => Err(format!(
+            "gaps gap must be a positive number (found {})",
+            gaps.gap
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn with_gaps(segment: TrackSegment, gaps: Option<GapsDescription>) -> TrackSegment {
+    match gaps {
+        Some(gaps) => segment.with_gaps(GapPattern::new(gaps.line, gaps.gap)),
+        None => segment,
+    }
+}
+
+impl SegmentDescription {
+    fn name(&self) -> &'static str {
+        match self {
+            SegmentDescription::Start => "start",
+            SegmentDescription::End => "end",
+            SegmentDescription::Straight { .. } => "straight",
+            SegmentDescription::Crossing { .. } => "crossing",
+            SegmentDescription::NinetyDegTurn { .. } => "90deg turn",
+            SegmentDescription::CircleTurn { .. } => "circle turn",
+        }
+    }
+
+    fn surface(&self) -> Option<SurfaceDescription> {
+        match *self {
+            SegmentDescription::Start | SegmentDescription::End => None,
+            SegmentDescription::Straight { surface, .. }
+            | SegmentDescription::Crossing { surface, .. }
+            | SegmentDescription::NinetyDegTurn { surface, .. }
+            | SegmentDescription::CircleTurn { surface, .. } => surface,
+        }
+    }
+
+    fn step(&self) -> f32 {
+        match *self {
+            SegmentDescription::Start | SegmentDescription::End => 0.0,
+            SegmentDescription::Straight { step, .. }
+            | SegmentDescription::Crossing { step, .. }
+            | SegmentDescription::NinetyDegTurn { step, .. }
+            | SegmentDescription::CircleTurn { step, .. } => step,
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        fn positive(name: &str, value: f32) -> Result<(), String> {
+            if value.is_finite() && value > 0.0 {
+                Ok(())
+            } else {
+                Err(format!(
+                    "{} must be a positive number (found {})",
+                    name, value
+                ))
+            }
+        }
+
+        validate_surface(self.surface())?;
+        if !self.step().is_finite() {
+            return Err(format!("step must be a number (found {})", self.step()));
+        }
+        match *self {
+            SegmentDescription::Start | SegmentDescription::End => Ok(()),
+            SegmentDescription::Straight {
+                length,
+                rise,
+                gaps,
+                line,
+                ..
+            } => {
+                positive("length", length)?;
+                if !(rise.is_finite() && rise.abs() < length) {
+                    return Err(format!(
+                        "rise must be a number smaller than the length (found {})",
+                        rise
+                    ));
+                }
+                validate_line(line)?;
+                validate_gaps(gaps)
+            }
+            SegmentDescription::Crossing { length, line, .. } => {
+                positive("length", length)?;
+                validate_line(line)
+            }
+            SegmentDescription::NinetyDegTurn {
+                half_length, line, ..
+            } => {
+                positive("half_length", half_length)?;
+                validate_line(line)
+            }
+            SegmentDescription::CircleTurn {
+                angle,
+                radius,
+                gaps,
+                line,
+                ..
+            } => {
+                validate_line(line)?;
+                validate_gaps(gaps)?;
+                positive("radius", radius)?;
+                positive("angle", angle)?;
+                if angle > 360.0 {
+                    return Err(format!(
+                        "angle must be at most 360 degrees (found {})",
+                        angle
+                    ));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn to_segment(&self) -> TrackSegment {
+        let segment = match *self {
+            SegmentDescription::Start => TrackSegment::start(),
+            SegmentDescription::End => TrackSegment::end(),
+            SegmentDescription::Straight {
+                length,
+                rise,
+                gaps,
+                line,
+                markers,
+                ..
+            } => with_markers(
+                with_line(
+                    with_gaps(TrackSegment::straight(length).with_rise(rise), gaps),
+                    line,
+                ),
+                markers,
+            ),
+            SegmentDescription::Crossing {
+                length,
+                line,
+                markers,
+                ..
+            } => with_markers(with_line(TrackSegment::crossing(length), line), markers),
+            SegmentDescription::NinetyDegTurn {
+                side,
+                half_length,
+                line,
+                markers,
+                ..
+            } => with_markers(
+                with_line(TrackSegment::ninety_deg_turn(half_length, side), line),
+                markers,
+            ),
+            SegmentDescription::CircleTurn {
+                side,
+                angle,
+                radius,
+                gaps,
+                line,
+                markers,
+                ..
+            } => with_markers(
+                with_line(
+                    with_gaps(
+                        TrackSegment::cyrcle_turn(radius, Angle::from_degrees(angle), side),
+                        gaps,
+                    ),
+                    line,
+                ),
+                markers,
+            ),
+        };
+        let segment = segment.with_step(self.step());
+        match self.surface() {
+            Some(surface) => segment.with_surface(surface.to_surface()),
+            None => segment,
+        }
+    }
+
+    fn from_segment(segment: &TrackSegment) -> Self {
+        let line = LineDescription::from_style(segment.line_style());
+        let markers = MarkersDescription::from_markers(segment.markers());
+        let surface = SurfaceDescription::from_surface(segment.surface());
+        match *segment {
+            TrackSegment::Start => SegmentDescription::Start,
+            TrackSegment::End => SegmentDescription::End,
+            TrackSegment::Straight(data) => SegmentDescription::Straight {
+                length: data.length,
+                rise: data.rise,
+                gaps: data.gaps.map(GapsDescription::from_pattern),
+                line,
+                markers,
+                surface,
+                step: segment.step(),
+            },
+            TrackSegment::Crossing(data) => SegmentDescription::Crossing {
+                length: data.length,
+                line,
+                markers,
+                surface,
+                step: segment.step(),
+            },
+            TrackSegment::NinetyDegTurn(data) => SegmentDescription::NinetyDegTurn {
+                side: data.side,
+                half_length: data.line_half_length,
+                line,
+                markers,
+                surface,
+                step: segment.step(),
+            },
+            TrackSegment::CyrcleTurn(data) => SegmentDescription::CircleTurn {
+                side: data.side,
+                angle: data.angle.to_degrees(),
+                radius: data.radius,
+                gaps: data.gaps.map(GapsDescription::from_pattern),
+                line,
+                markers,
+                surface,
+                step: segment.step(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum ObstacleShapeDescription {
+    Box {
+        width: f32,
+        length: f32,
+        height: f32,
+    },
+    Cylinder {
+        radius: f32,
+        height: f32,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ObstacleDescription {
+    pub segment: usize,
+    pub along: f32,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub offset: f32,
+    pub shape: ObstacleShapeDescription,
+}
+
+impl ObstacleDescription {
+    fn from_obstacle(obstacle: &Obstacle) -> Self {
+        Self {
+            segment: obstacle.segment,
+            along: obstacle.along,
+            offset: obstacle.offset,
+            shape: match obstacle.shape {
+                ObstacleShape::Box { size } => ObstacleShapeDescription::Box {
+                    width: size.x,
+                    length: size.y,
+                    height: size.z,
+                },
+                ObstacleShape::Cylinder { radius, height } => {
+                    ObstacleShapeDescription::Cylinder { radius, height }
+                }
+            },
+        }
+    }
+
+    fn to_obstacle(self) -> Obstacle {
+        Obstacle {
+            segment: self.segment,
+            along: self.along,
+            offset: self.offset,
+            shape: match self.shape {
+                ObstacleShapeDescription::Box {
+                    width,
+                    length,
+                    height,
+                } => ObstacleShape::Box {
+                    size: Vec3::new(width, length, height),
+                },
+                ObstacleShapeDescription::Cylinder { radius, height } => {
+                    ObstacleShape::Cylinder { radius, height }
+                }
+            },
+        }
+    }
+
+    fn validate(&self, segments: &[SegmentDescription]) -> Result<(), String> {
+        let Some(segment) = segments.get(self.segment) else {
+            return Err(format!(
+                "segment {} does not exist (the track has {})",
+                self.segment,
+                segments.len()
+            ));
+        };
+        let length = segment.to_segment().length();
+        if !(self.along.is_finite() && (0.0..=length).contains(&self.along)) {
+            return Err(format!(
+                "along must be between 0 and the segment length {} (found {})",
+                length, self.along
+            ));
+        }
+        if !self.offset.is_finite() {
+            return Err(format!("offset must be a number (found {})", self.offset));
+        }
+        let sizes = match self.shape {
+            ObstacleShapeDescription::Box {
+                width,
+                length,
+                height,
+            } => vec![("width", width), ("length", length), ("height", height)],
+            ObstacleShapeDescription::Cylinder { radius, height } => {
+                vec![("radius", radius), ("height", height)]
+            }
+        };
+        for (name, value) in sizes {
+            if !(value.is_finite() && value > 0.0) {
+                return Err(format!(
+                    "{} must be a positive number (found {})",
+                    name, value
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The serializable description of a [`Track`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackFile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<(f32, f32)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<OriginDescription>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub direction: f32,
+    /// Makes the track a closed loop raced for this number of laps
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub laps: Option<usize>,
+    pub segments: Vec<SegmentDescription>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub obstacles: Vec<ObstacleDescription>,
+}
+
+impl TrackFile {
+    pub fn from_track(track: &Track) -> Self {
+        Self {
+            size: track.size_override().map(|size| (size.x, size.y)),
+            origin: track.origin_override().map(|origin| OriginDescription {
+                x: origin.x,
+                y: origin.y,
+            }),
+            direction: track.direction().to_degrees(),
+            laps: track.laps(),
+            segments: track
+                .segments()
+                .iter()
+                .map(SegmentDescription::from_segment)
+                .collect(),
+            obstacles: track
+                .obstacles()
+                .iter()
+                .map(ObstacleDescription::from_obstacle)
+                .collect(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some((width, height)) = self.size
+            && !(width.is_finite() && width > 0.0 && height.is_finite() && height > 0.0)
+        {
+            return Err(format!(
+                "track size must be positive (found {} x {})",
+                width, height
+            ));
+        }
+        if let Some(origin) = self.origin
+            && !(origin.x.is_finite() && origin.y.is_finite())
+        {
+            return Err("track origin must be made of finite numbers".to_string());
+        }
+        if !self.direction.is_finite() {
+            return Err("track direction must be a finite number".to_string());
+        }
+
+        if let Some(laps) = self.laps {
+            if laps == 0 {
+                return Err("a closed loop must have at least one lap".to_string());
+            }
+            if self.segments.is_empty() {
+                return Err("a closed loop must have at least one segment".to_string());
+            }
+        } else {
+            match self.segments.first() {
+                Some(SegmentDescription::Start) => {}
+                _ => return Err("the first segment must be Start".to_string()),
+            }
+            match self.segments.last() {
+                Some(SegmentDescription::End) if self.segments.len() > 1 => {}
+                _ => return Err("the last segment must be End".to_string()),
+            }
+        }
+
+        let last_index = self.segments.len() - 1;
+        for (index, segment) in self.segments.iter().enumerate() {
+            match segment {
+                SegmentDescription::Start | SegmentDescription::End if self.laps.is_some() => {
+                    return Err(format!(
+                        "segment {}: Start and End are not allowed in closed loops",
+                        index
+                    ));
+                }
+                SegmentDescription::Start if index != 0 => {
+                    return Err(format!(
+                        "segment {}: Start is allowed only as the first segment",
+                        index
+                    ));
+                }
+                SegmentDescription::End if index != last_index => {
+                    return Err(format!(
+                        "segment {}: End is allowed only as the last segment",
+                        index
+                    ));
+                }
+                _ => {}
+            }
+            segment
+                .validate()
+                .map_err(|err| format!("segment {} ({}): {}", index, segment.name(), err))?;
+        }
+        for (index, obstacle) in self.obstacles.iter().enumerate() {
+            obstacle
+                .validate(&self.segments)
+                .map_err(|err| format!("obstacle {}: {}", index, err))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn to_track(&self) -> Result<Track, String> {
+        self.validate()?;
+        let mut track = Track::new(
+            self.segments
+                .iter()
+                .map(SegmentDescription::to_segment)
+                .collect(),
+        )
+        .with_direction(Angle::from_degrees(self.direction));
+        if let Some((width, height)) = self.size {
+            track = track.with_size(Vec2::new(width, height));
+        }
+        if let Some(origin) = self.origin {
+            track = track.with_origin(Vec2::new(origin.x, origin.y));
+        }
+        if let Some(laps) = self.laps {
+            track = track.with_laps(laps);
+        }
+        if !self.obstacles.is_empty() {
+            track = track.with_obstacles(
+                self.obstacles
+                    .iter()
+                    .map(|obstacle| obstacle.to_obstacle())
+                    .collect(),
+            );
+        }
+        Ok(track)
+    }
+}
+
+fn is_zero(value: &f32) -> bool {
+    *value == 0.0
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+/// Optional fields are written without `Some(...)`
+fn ron_options() -> ron::Options {
+    ron::Options::default().with_default_extension(ron::extensions::Extensions::IMPLICIT_SOME)
+}
+
+pub fn load_track_from_file(path: &str) -> wasmtime::Result<Track> {
+    let text = std::fs::read_to_string(path).map_err(|err| {
+        wasmtime::Error::msg(format!("cannot read track file \"{}\": {}", path, err))
+    })?;
+    let track_file: TrackFile = ron_options().from_str(&text).map_err(|err| {
+        wasmtime::Error::msg(format!("cannot parse track file \"{}\": {}", path, err))
+    })?;
+    track_file
+        .to_track()
+        .map_err(|err| wasmtime::Error::msg(format!("invalid track file \"{}\": {}", path, err)))
+}
+
+pub fn save_track_to_file(track: &Track, path: &str) -> wasmtime::Result<()> {
+    let text = ron_options()
+        .to_string_pretty(
+            &TrackFile::from_track(track),
+            ron::ser::PrettyConfig::default(),
+        )
+        .map_err(|err| wasmtime::Error::msg(format!("cannot serialize track: {}", err)))?;
+    std::fs::write(path, text).map_err(|err| {
+        wasmtime::Error::msg(format!("cannot write track file \"{}\": {}", path, err))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRACK: &str = r#"(
+        segments: [
+            Start,
+            Straight(length: 1.0, rise: 0.1, gaps: (line: 0.1, gap: 0.05)),
+            NinetyDegTurn(side: Right, half_length: 0.5, surface: Slippery),
+            CircleTurn(side: Left, angle: 90.0, radius: 0.5, line: (width: 0.03, inverted: true), markers: (start_left: true)),
+            Crossing(length: 0.4, step: 0.002, surface: Friction(0.6)),
+            End,
+        ],
+        obstacles: [
+            (segment: 1, along: 0.5, offset: 0.06, shape: Box(width: 0.05, length: 0.05, height: 0.1)),
+            (segment: 3, along: 0.2, shape: Cylinder(radius: 0.03, height: 0.1)),
+        ],
+        direction: 30.0,
+    )"#;
+
+    fn parse(text: &str) -> TrackFile {
+        ron_options().from_str(text).unwrap()
+    }
+
+    fn validation_error(text: &str) -> String {
+        parse(text).validate().unwrap_err()
+    }
+
+    /// A valid open track made of one segment between the tips
+    fn open_track(segment: &str) -> String {
+        format!("(segments: [Start, {}, End])", segment)
+    }
+
+    #[test]
+    fn ron_round_trip() {
+        let track = parse(TRACK).to_track().unwrap();
+        let text = ron_options()
+            .to_string_pretty(
+                &TrackFile::from_track(&track),
+                ron::ser::PrettyConfig::default(),
+            )
+            .unwrap();
+        let reloaded = parse(&text).to_track().unwrap();
+
+        assert_eq!(reloaded.segments(), track.segments());
+        assert_eq!(reloaded.obstacles(), track.obstacles());
+        assert_eq!(reloaded.laps(), track.laps());
+        assert!((reloaded.direction().to_degrees() - 30.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn closed_loop_round_trip() {
+        let text = "(laps: 3, segments: [CircleTurn(side: Left, angle: 360.0, radius: 0.5)])";
+        let track = parse(text).to_track().unwrap();
+        let reloaded = TrackFile::from_track(&track).to_track().unwrap();
+
+        assert_eq!(reloaded.laps(), Some(3));
+        assert_eq!(reloaded.segments(), track.segments());
+    }
+
+    #[test]
+    fn track_errors() {
+        for (text, error) in [
+            (
+                "(size: (0.0, 2.0), segments: [Start, End])",
+                "track size must be positive",
+            ),
+            (
+                "(origin: (x: NaN, y: 0.0), segments: [Start, End])",
+                "track origin must be made of finite numbers",
+            ),
+            (
+                "(direction: inf, segments: [Start, End])",
+                "track direction must be a finite number",
+            ),
+            (
+                "(laps: 0, segments: [Straight(length: 1.0)])",
+                "a closed loop must have at least one lap",
+            ),
+            (
+                "(laps: 1, segments: [])",
+                "a closed loop must have at least one segment",
+            ),
+            (
+                "(segments: [Straight(length: 1.0), End])",
+                "the first segment must be Start",
+            ),
+            (
+                "(segments: [Start, Straight(length: 1.0)])",
+                "the last segment must be End",
+            ),
+            ("(segments: [Start])", "the last segment must be End"),
+            (
+                "(laps: 1, segments: [Start, Straight(length: 1.0)])",
+                "segment 0: Start and End are not allowed in closed loops",
+            ),
+            (
+                "(segments: [Start, Start, End])",
+                "segment 1: Start is allowed only as the first segment",
+            ),
+            (
+                "(segments: [Start, End, End])",
+                "segment 1: End is allowed only as the last segment",
+            ),
+        ] {
+            assert!(
+                validation_error(text).contains(error),
+                "{} should fail with \"{}\"",
+                text,
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn segment_errors() {
+        for (segment, error) in [
+            (
+                "Straight(length: 0.0)",
+                "segment 1 (straight): length must be a positive number",
+            ),
+            (
+                "Straight(length: 1.0, rise: 1.0)",
+                "rise must be a number smaller than the length",
+            ),
+            (
+                "Straight(length: 1.0, gaps: (line: 0.0, gap: 0.1))",
+                "gaps line must be a positive number",
+            ),
+            (
+                "Straight(length: 1.0, gaps: (line: 0.1, gap: -0.1))",
+                "gaps gap must be a positive number",
+            ),
+            (
+                "Straight(length: 1.0, line: (width: 1.0))",
+                "line width must be positive and at most the track width",
+            ),
+            (
+                "Straight(length: 1.0, surface: Friction(-0.5))",
+                "surface friction must be a non negative number",
+            ),
+            ("Straight(length: 1.0, step: NaN)", "step must be a number"),
+            (
+                "Crossing(length: -1.0)",
+                "segment 1 (crossing): length must be a positive number",
+            ),
+            (
+                "NinetyDegTurn(side: Left, half_length: 0.0)",
+                "half_length must be a positive number",
+            ),
+            (
+                "CircleTurn(side: Left, angle: 90.0, radius: 0.0)",
+                "radius must be a positive number",
+            ),
+            (
+                "CircleTurn(side: Left, angle: 0.0, radius: 0.5)",
+                "angle must be a positive number",
+            ),
+            (
+                "CircleTurn(side: Left, angle: 400.0, radius: 0.5)",
+                "angle must be at most 360 degrees",
+            ),
+        ] {
+            assert!(
+                validation_error(&open_track(segment)).contains(error),
+                "{} should fail with \"{}\"",
+                segment,
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn obstacle_errors() {
+        for (obstacle, error) in [
+            (
+                "(segment: 5, along: 0.5, shape: Box(width: 0.05, length: 0.05, height: 0.1))",
+                "obstacle 0: segment 5 does not exist (the track has 3)",
+            ),
+            (
+                "(segment: 1, along: 2.0, shape: Box(width: 0.05, length: 0.05, height: 0.1))",
+                "along must be between 0 and the segment length",
+            ),
+            (
+                "(segment: 1, along: 0.5, offset: NaN, shape: Cylinder(radius: 0.03, height: 0.1))",
+                "offset must be a number",
+            ),
+            (
+                "(segment: 1, along: 0.5, shape: Box(width: 0.05, length: 0.0, height: 0.1))",
+                "length must be a positive number",
+            ),
+            (
+                "(segment: 1, along: 0.5, shape: Cylinder(radius: 0.03, height: -0.1))",
+                "height must be a positive number",
+            ),
+        ] {
+            let text = format!(
+                "(segments: [Start, Straight(length: 1.0), End], obstacles: [{}])",
+                obstacle
+            );
+            assert!(
+                validation_error(&text).contains(error),
+                "{} should fail with \"{}\"",
+                obstacle,
+                error
+            );
+        }
+    }
+}
@@ -0,0 +1,283 @@
+//! Procedural generation of random tracks.
+//!
+//! A track is grown one piece at a time from the start, alternating
+//! straights and turns: every candidate piece is kept only if the track
+//! (closed by its end tip) still passes [`check_track`]. When no piece fits
+//! the generation starts over, drawing from the same random sequence, so a
+//! seed always gives the same track.
+
+use bevy::{math::Vec2, text::cosmic_text::Angle};
+use executor::wasmtime;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    track::{TRACK_HALF_WIDTH, Track, TrackSegment},
+    track_check::{check_track, segment_on_floor},
+    utils::Side,
+};
+
+/// Candidate pieces tried before giving up on the current layout
+const PIECE_ATTEMPTS: usize = 20;
+/// Layouts tried before giving up
+const LAYOUT_ATTEMPTS: usize = 200;
+/// Longest track generated (every piece checks the whole track, so longer
+/// ones take too long)
+const MAX_LENGTH: f32 = 100.0;
+/// Largest floor side (a track of the longest length fits on it from the
+/// center in any direction)
+const MAX_FLOOR_SIZE: f32 = MAX_LENGTH * 2.0;
+const MIN_STRAIGHT_LENGTH: f32 = 0.2;
+const MAX_STRAIGHT_LENGTH: f32 = 1.5;
+const MIN_TURN_ANGLE: f32 = 20.0;
+/// Turn radii are drawn between the minimum radius and this many times it
+const MAX_RADIUS_FACTOR: f32 = 3.0;
+
+#[derive(Clone, Copy, clap::Args)]
+pub struct TrackGeneratorParams {
+    /// Random track: center line length in meters (start and end excluded)
+    #[clap(long = "random-length", default_value = "15.0")]
+    pub length: f32,
+    /// Random track: minimum turn radius in meters
+    #[clap(long = "random-min-radius", default_value = "0.3")]
+    pub min_radius: f32,
+    /// Random track: share of 90deg corners among turns (0 to 1)
+    #[clap(long = "random-corners", default_value = "0.3")]
+    pub corner_share: f32,
+    /// Random track: maximum arc angle in degrees
+    #[clap(long = "random-max-angle", default_value = "180.0")]
+    pub max_turn_angle: f32,
+    /// Random track: floor width in meters
+    #[clap(long = "random-width", default_value = "8.0")]
+    pub floor_width: f32,
+    /// Random track: floor height in meters
+    #[clap(long = "random-height", default_value = "8.0")]
+    pub floor_height: f32,
+}
+
+impl TrackGeneratorParams {
+    fn validate(&self) -> Result<(), String> {
+        for (name, value) in [
+            ("length", self.length),
+            ("minimum radius", self.min_radius),
+            ("corner share", self.corner_share),
+            ("maximum turn angle", self.max_turn_angle),
+            ("floor width", self.floor_width),
+            ("floor height", self.floor_height),
+        ] {
+            if !value.is_finite() {
+                return Err(format!("{} must be a finite number (got {})", name, value));
+            }
+        }
+        if self.length <= 0.0 || self.length > MAX_LENGTH {
+            return Err(format!(
+                "length must be positive and at most {} (got {})",
+                MAX_LENGTH, self.length
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.corner_share) {
+            return Err(format!(
+                "corner share must be between 0 and 1 (got {})",
+                self.corner_share
+            ));
+        }
+        if self.max_turn_angle < MIN_TURN_ANGLE || self.max_turn_angle > 360.0 {
+            return Err(format!(
+                "maximum turn angle must be between {} and 360 degrees (got {})",
+                MIN_TURN_ANGLE, self.max_turn_angle
+            ));
+        }
+        if self.floor_width <= 0.0
+            || self.floor_height <= 0.0
+            || self.floor_width > MAX_FLOOR_SIZE
+            || self.floor_height > MAX_FLOOR_SIZE
+        {
+            return Err(format!(
+                "floor size must be positive and at most {} (got {} x {})",
+                MAX_FLOOR_SIZE, self.floor_width, self.floor_height
+            ));
+        }
+        if self.min_radius <= TRACK_HALF_WIDTH {
+            return Err(format!(
+                "minimum radius must be larger than the track half width {} (got {})",
+                TRACK_HALF_WIDTH, self.min_radius
+            ));
+        }
+        // The largest turns drawn must fit on the floor
+        let max_radius = self.floor_width.min(self.floor_height) / 2.0 / MAX_RADIUS_FACTOR;
+        if self.min_radius > max_radius {
+            return Err(format!(
+                "minimum radius must be at most {} on a {} x {} floor (got {})",
+                max_radius, self.floor_width, self.floor_height, self.min_radius
+            ));
+        }
+        Ok(())
+    }
+
+    fn floor_size(&self) -> Vec2 {
+        Vec2::new(self.floor_width, self.floor_height)
+    }
+}
+
+fn random_side(rng: &mut ChaCha8Rng) -> Side {
+    if rng.random_bool(0.5) {
+        Side::Left
+    } else {
+        Side::Right
+    }
+}
+
+fn random_piece(rng: &mut ChaCha8Rng, params: &TrackGeneratorParams, turn: bool) -> TrackSegment {
+    if !turn {
+        return TrackSegment::straight(rng.random_range(MIN_STRAIGHT_LENGTH..=MAX_STRAIGHT_LENGTH));
+    }
+    let radius = rng.random_range(params.min_radius..=params.min_radius * MAX_RADIUS_FACTOR);
+    let side = random_side(rng);
+    if rng.random_bool(params.corner_share as f64) {
+        TrackSegment::ninety_deg_turn(radius, side)
+    } else {
+        let angle = rng.random_range(MIN_TURN_ANGLE..=params.max_turn_angle);
+        TrackSegment::cyrcle_turn(radius, Angle::from_degrees(angle), side)
+    }
+}
+
+fn build_candidate(pieces: &[TrackSegment], params: &TrackGeneratorParams) -> Track {
+    let mut segments = Vec::with_capacity(pieces.len() + 2);
+    segments.push(TrackSegment::start());
+    segments.extend_from_slice(pieces);
+    segments.push(TrackSegment::end());
+    Track::new(segments).with_size(params.floor_size())
+}
+
+fn generate_layout(rng: &mut ChaCha8Rng, params: &TrackGeneratorParams) -> Option<Track> {
+    let mut pieces: Vec<TrackSegment> = Vec::new();
+    let mut length = 0.0;
+    while length < params.length {
+        let turn = pieces.len() % 2 == 1;
+        let piece = (0..PIECE_ATTEMPTS).find_map(|_| {
+            let piece = random_piece(rng, params, turn);
+            pieces.push(piece);
+            // The piece is last but for the end tip: test it alone first,
+            // the full check is much slower
+            let candidate = build_candidate(&pieces, params);
+            let fits =
+                segment_on_floor(&candidate, pieces.len()) && check_track(&candidate).is_empty();
+            pieces.pop();
+            fits.then_some(piece)
+        })?;
+        length += piece.length();
+        pieces.push(piece);
+    }
+    Some(build_candidate(&pieces, params))
+}
+
+/// Generates a random open track: the same seed and parameters always give
+/// the same track
+pub fn generate_track(seed: u64, params: &TrackGeneratorParams) -> wasmtime::Result<Track> {
+    params
+        .validate()
+        .map_err(|err| wasmtime::Error::msg(format!("invalid random track parameters: {}", err)))?;
+
+    // ChaCha gives the same sequence on every platform and rand version
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    (0..LAYOUT_ATTEMPTS)
+        .find_map(|_| generate_layout(&mut rng, params))
+        .ok_or_else(|| {
+            wasmtime::Error::msg(format!(
+                "cannot generate a random track with seed {} (try a larger floor or a shorter track)",
+                seed
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params() -> TrackGeneratorParams {
+        TrackGeneratorParams {
+            length: 6.0,
+            min_radius: 0.3,
+            corner_share: 0.3,
+            max_turn_angle: 180.0,
+            floor_width: 6.0,
+            floor_height: 6.0,
+        }
+    }
+
+    #[test]
+    fn same_seed_same_track() {
+        let first = generate_track(7, &params()).unwrap();
+        let second = generate_track(7, &params()).unwrap();
+        assert_eq!(first.segments(), second.segments());
+
+        let other = generate_track(8, &params()).unwrap();
+        assert_ne!(first.segments(), other.segments());
+    }
+
+    #[test]
+    fn generated_track_passes_checks() {
+        let track = generate_track(1, &params()).unwrap();
+        assert!(check_track(&track).is_empty());
+        let length: f32 = track.segments().iter().map(TrackSegment::length).sum();
+        assert!(length >= params().length);
+    }
+
+    #[test]
+    fn invalid_parameters() {
+        for params in [
+            TrackGeneratorParams {
+                length: 0.0,
+                ..params()
+            },
+            TrackGeneratorParams {
+                min_radius: TRACK_HALF_WIDTH,
+                ..params()
+            },
+            TrackGeneratorParams {
+                corner_share: 1.5,
+                ..params()
+            },
+            TrackGeneratorParams {
+                max_turn_angle: 400.0,
+                ..params()
+            },
+            TrackGeneratorParams {
+                floor_width: 0.0,
+                ..params()
+            },
+            TrackGeneratorParams {
+                length: f32::NAN,
+                ..params()
+            },
+            TrackGeneratorParams {
+                min_radius: f32::NAN,
+                ..params()
+            },
+            TrackGeneratorParams {
+                length: MAX_LENGTH * 2.0,
+                ..params()
+            },
+            TrackGeneratorParams {
+                floor_width: f32::INFINITY,
+                ..params()
+            },
+            TrackGeneratorParams {
+                min_radius: 3e38,
+                ..params()
+            },
+            TrackGeneratorParams {
+                min_radius: 50.0,
+                floor_width: 8.0,
+                floor_height: 8.0,
+                ..params()
+            },
+            TrackGeneratorParams {
+                floor_height: MAX_FLOOR_SIZE * 2.0,
+                ..params()
+            },
+        ] {
+            assert!(generate_track(0, &params).is_err());
+        }
+    }
+}
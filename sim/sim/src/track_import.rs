@@ -0,0 +1,928 @@
+//! Imports a track from a center line drawn elsewhere: the paths,
+//! polylines, polygons and lines of an SVG file or a plain list of points
+//! (one `x y` pair per line, `#` starts a comment).
+//!
+//! The longest line of the file is resampled and covered from its start with
+//! the longest straights and circle turns that stay within the tolerance;
+//! vertices turning by about 90deg become `NinetyDegTurn` segments. Open
+//! lines get the start and end tips on their first and last half meter,
+//! closed ones become closed loops.
+//!
+//! SVG transforms are not applied and the y axis is flipped, so that the
+//! track looks like the drawing.
+
+use std::{
+    f32::consts::{FRAC_PI_2, PI, TAU},
+    path::Path,
+};
+
+use bevy::{math::Vec2, text::cosmic_text::Angle};
+use executor::wasmtime;
+
+use crate::{
+    track::{SegmentTransform, Track, TrackSegment},
+    utils::{Side, rotate_vec2},
+};
+
+/// Distance between the resampled points of the center line
+const SAMPLE_STEP: f32 = 0.01;
+/// SVG curves are flattened in pieces at most this long
+const CURVE_STEP: f32 = 0.01;
+/// Default meters per unit of SVG files (drawn in millimeters)
+const SVG_SCALE: f32 = 0.001;
+/// Default meters per unit of point lists
+const POINTS_SCALE: f32 = 1.0;
+/// Turns with a larger radius are not tried (a straight fits anyway)
+const MAX_TURN_RADIUS: f32 = 100.0;
+/// Pieces follow the line direction within the tolerance divided by this
+/// (in meters per radian)
+const DIRECTION_ERROR_WEIGHT: f32 = 0.1;
+/// Samples tried past the last fitting one before settling on a piece
+const FIT_LOOKAHEAD: usize = 25;
+/// Straights are preferred to turns that are at most this much longer
+const STRAIGHT_PREFERENCE: f32 = 0.9;
+/// Vertices turning within this many degrees of 90deg are corners
+const CORNER_ANGLE_TOLERANCE: f32 = 15.0;
+/// Maximum half length of the legs of 90deg corners
+const MAX_CORNER_HALF_LENGTH: f32 = 0.2;
+
+#[derive(Clone, Copy, clap::Args)]
+pub struct TrackImportParams {
+    /// Meters per input unit (defaults to 0.001 for SVG files, 1 for point lists)
+    #[clap(long)]
+    pub scale: Option<f32>,
+    /// Maximum distance in meters between the fitted segments and the input line
+    #[clap(long, default_value = "0.005")]
+    pub tolerance: f32,
+    /// Laps raced when the input line is closed
+    #[clap(long, default_value = "3")]
+    pub laps: usize,
+}
+
+impl TrackImportParams {
+    fn validate(&self) -> Result<(), String> {
+        if let Some(scale) = self.scale
+            && !(scale.is_finite() && scale > 0.0)
+        {
+            return Err(format!("scale must be positive (got {})", scale));
+        }
+        if !(self.tolerance.is_finite() && self.tolerance > 0.0) {
+            return Err(format!(
+                "tolerance must be positive (got {})",
+                self.tolerance
+            ));
+        }
+        if self.laps == 0 {
+            return Err("a closed loop must have at least one lap".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// A fitted track and how far it is from the input line
+pub struct ImportedTrack {
+    pub track: Track,
+    /// Largest distance of the input line from the track center line
+    pub max_error: f32,
+    /// Root mean square distance of the input line from the track center line
+    pub rms_error: f32,
+    /// Distance between the end and the start of closed loops
+    pub closing_gap: Option<f32>,
+}
+
+/// A line in meters
+struct Polyline {
+    points: Vec<Vec2>,
+    closed: bool,
+}
+
+impl Polyline {
+    fn length(&self) -> f32 {
+        self.points
+            .windows(2)
+            .map(|pair| pair[0].distance(pair[1]))
+            .sum()
+    }
+}
+
+/// Reads the numbers of SVG path data
+struct PathData<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> PathData<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            bytes: text.as_bytes(),
+            pos: 0,
+        }
+    }
+
+    fn skip_separators(&mut self) {
+        while self
+            .bytes
+            .get(self.pos)
+            .is_some_and(|&b| b.is_ascii_whitespace() || b == b',')
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn at_end(&mut self) -> bool {
+        self.skip_separators();
+        self.pos >= self.bytes.len()
+    }
+
+    fn command(&mut self) -> Option<u8> {
+        self.skip_separators();
+        let command = *self.bytes.get(self.pos)?;
+        if command.is_ascii_alphabetic() {
+            self.pos += 1;
+            Some(command)
+        } else {
+            None
+        }
+    }
+
+    fn number(&mut self) -> Result<f32, String> {
+        self.skip_separators();
+        let bytes = self.bytes;
+        let start = self.pos;
+        let mut pos = start;
+        if matches!(bytes.get(pos), Some(b'+' | b'-')) {
+            pos += 1;
+        }
+        let mut has_dot = false;
+        let mut has_digits = false;
+        while let Some(&b) = bytes.get(pos) {
+            if b.is_ascii_digit() {
+                has_digits = true;
+            } else if b == b'.' && !has_dot {
+                has_dot = true;
+            } else {
+                break;
+            }
+            pos += 1;
+        }
+        if has_digits && matches!(bytes.get(pos), Some(b'e' | b'E')) {
+            let mut exponent = pos + 1;
+            if matches!(bytes.get(exponent), Some(b'+' | b'-')) {
+                exponent += 1;
+            }
+            if bytes.get(exponent).is_some_and(u8::is_ascii_digit) {
+                pos = exponent;
+                while bytes.get(pos).is_some_and(u8::is_ascii_digit) {
+                    pos += 1;
+                }
+            }
+        }
+        self.pos = pos;
+        std::str::from_utf8(&bytes[start..pos])
+            .ok()
+            .and_then(parse_finite)
+            .ok_or_else(|| format!("invalid number in path data at offset {}", start))
+    }
+
+    /// Arc flags can be written without separators (`a10 10 0 0110 10`)
+    fn flag(&mut self) -> Result<bool, String> {
+        self.skip_separators();
+        let flag = match self.bytes.get(self.pos) {
+            Some(b'0') => false,
+            Some(b'1') => true,
+            _ => {
+                return Err(format!(
+                    "invalid arc flag in path data at offset {}",
+                    self.pos
+                ));
+            }
+        };
+        self.pos += 1;
+        Ok(flag)
+    }
+
+    fn point(&mut self) -> Result<Vec2, String> {
+        Ok(Vec2::new(self.number()?, self.number()?))
+    }
+}
+
+fn cubic_point(p0: Vec2, p1: Vec2, p2: Vec2, p3: Vec2, t: f32) -> Vec2 {
+    let u = 1.0 - t;
+    p0 * (u * u * u) + p1 * (3.0 * u * u * t) + p2 * (3.0 * u * t * t) + p3 * (t * t * t)
+}
+
+fn quadratic_point(p0: Vec2, p1: Vec2, p2: Vec2, t: f32) -> Vec2 {
+    let u = 1.0 - t;
+    p0 * (u * u) + p1 * (2.0 * u * t) + p2 * (t * t)
+}
+
+/// Points of an SVG elliptical arc (the start excluded), converted from the
+/// endpoint to the center parameterization
+fn arc_points(
+    from: Vec2,
+    radii: Vec2,
+    rotation: f32,
+    large_arc: bool,
+    sweep: bool,
+    to: Vec2,
+    pieces_for: impl Fn(f32) -> usize,
+) -> Vec<Vec2> {
+    let (mut rx, mut ry) = (radii.x.abs(), radii.y.abs());
+    if from == to {
+        return Vec::new();
+    }
+    if rx == 0.0 || ry == 0.0 {
+        return vec![to];
+    }
+    let phi = rotation.to_radians();
+    let half = rotate_vec2((from - to) / 2.0, -phi);
+    let lambda = (half.x / rx).powi(2) + (half.y / ry).powi(2);
+    if lambda > 1.0 {
+        rx *= lambda.sqrt();
+        ry *= lambda.sqrt();
+    }
+    let numerator = rx * rx * ry * ry - rx * rx * half.y * half.y - ry * ry * half.x * half.x;
+    let denominator = rx * rx * half.y * half.y + ry * ry * half.x * half.x;
+    let mut factor = (numerator / denominator).max(0.0).sqrt();
+    if large_arc == sweep {
+        factor = -factor;
+    }
+    let center_local = Vec2::new(factor * rx * half.y / ry, -factor * ry * half.x / rx);
+    let center = rotate_vec2(center_local, phi) + (from + to) / 2.0;
+
+    let start = Vec2::new(
+        (half.x - center_local.x) / rx,
+        (half.y - center_local.y) / ry,
+    );
+    let end = Vec2::new(
+        (-half.x - center_local.x) / rx,
+        (-half.y - center_local.y) / ry,
+    );
+    let start_angle = start.y.atan2(start.x);
+    let mut sweep_angle = end.y.atan2(end.x) - start_angle;
+    if sweep && sweep_angle < 0.0 {
+        sweep_angle += TAU;
+    } else if !sweep && sweep_angle > 0.0 {
+        sweep_angle -= TAU;
+    }
+
+    let count = pieces_for(sweep_angle.abs() * rx.max(ry));
+    let mut points: Vec<Vec2> = (1..count)
+        .map(|i| {
+            let angle = start_angle + sweep_angle * i as f32 / count as f32;
+            center + rotate_vec2(Vec2::new(rx * angle.cos(), ry * angle.sin()), phi)
+        })
+        .collect();
+    points.push(to);
+    points
+}
+
+/// Flattens SVG path data into one line per subpath
+fn parse_path_data(text: &str, scale: f32) -> Result<Vec<Polyline>, String> {
+    let pieces_for = |length: f32| ((length * scale / CURVE_STEP).ceil() as usize).clamp(1, 1000);
+    let to_meters = |point: Vec2| Vec2::new(point.x, -point.y) * scale;
+
+    let mut data = PathData::new(text);
+    let mut lines = Vec::new();
+    let mut points: Vec<Vec2> = Vec::new();
+    let mut flush = |points: &mut Vec<Vec2>, closed: bool| {
+        if points.len() >= 2 {
+            lines.push(Polyline {
+                points: points.iter().copied().map(to_meters).collect(),
+                closed,
+            });
+        }
+        points.clear();
+    };
+
+    let mut current = Vec2::ZERO;
+    let mut subpath_start = Vec2::ZERO;
+    let mut previous: Option<u8> = None;
+    // Control points reflected by the smooth curve commands
+    let mut cubic_control: Option<Vec2> = None;
+    let mut quadratic_control: Option<Vec2> = None;
+    while !data.at_end() {
+        let command = match (data.command(), previous) {
+            (Some(command), _) => command,
+            // Coordinates after a move are implicit line commands
+            (None, Some(b'M')) => b'L',
+            (None, Some(b'm')) => b'l',
+            (None, Some(b'Z' | b'z') | None) => {
+                return Err("path data must start with a move command".to_string());
+            }
+            (None, Some(command)) => command,
+        };
+        let relative = command.is_ascii_lowercase();
+        let base = if relative { current } else { Vec2::ZERO };
+        let command_upper = command.to_ascii_uppercase();
+        if !matches!(command_upper, b'M' | b'Z') && points.is_empty() {
+            points.push(current);
+        }
+
+        let mut next_cubic_control = None;
+        let mut next_quadratic_control = None;
+        match command_upper {
+            b'M' => {
+                flush(&mut points, false);
+                current = base + data.point()?;
+                subpath_start = current;
+                points.push(current);
+            }
+            b'L' => {
+                current = base + data.point()?;
+                points.push(current);
+            }
+            b'H' => {
+                current.x = base.x + data.number()?;
+                points.push(current);
+            }
+            b'V' => {
+                current.y = base.y + data.number()?;
+                points.push(current);
+            }
+            b'C' | b'S' => {
+                let control1 = if command_upper == b'C' {
+                    base + data.point()?
+                } else {
+                    cubic_control.map_or(current, |control| current * 2.0 - control)
+                };
+                let control2 = base + data.point()?;
+                let end = base + data.point()?;
+                let count = pieces_for(
+                    current.distance(control1)
+                        + control1.distance(control2)
+                        + control2.distance(end),
+                );
+                points.extend((1..=count).map(|i| {
+                    cubic_point(current, control1, control2, end, i as f32 / count as f32)
+                }));
+                current = end;
+                next_cubic_control = Some(control2);
+            }
+            b'Q' | b'T' => {
+                let control = if command_upper == b'Q' {
+                    base + data.point()?
+                } else {
+                    quadratic_control.map_or(current, |control| current * 2.0 - control)
+                };
+                let end = base + data.point()?;
+                let count = pieces_for(current.distance(control) + control.distance(end));
+                points.extend(
+                    (1..=count)
+                        .map(|i| quadratic_point(current, control, end, i as f32 / count as f32)),
+                );
+                current = end;
+                next_quadratic_control = Some(control);
+            }
+            b'A' => {
+                let radii = Vec2::new(data.number()?, data.number()?);
+                let rotation = data.number()?;
+                let large_arc = data.flag()?;
+                let sweep = data.flag()?;
+                let end = base + data.point()?;
+                points.extend(arc_points(
+                    current, radii, rotation, large_arc, sweep, end, pieces_for,
+                ));
+                current = end;
+            }
+            b'Z' => {
+                if points.last() != Some(&subpath_start) {
+                    points.push(subpath_start);
+                }
+                flush(&mut points, true);
+                current = subpath_start;
+            }
+            _ => {
+                return Err(format!("unsupported path command '{}'", command as char));
+            }
+        }
+        cubic_control = next_cubic_control;
+        quadratic_control = next_quadratic_control;
+        previous = Some(command);
+    }
+    flush(&mut points, false);
+    Ok(lines)
+}
+
+/// Parses a coordinate, rejecting `nan` and `inf` (and overflowing ones)
+fn parse_finite(text: &str) -> Option<f32> {
+    text.parse().ok().filter(|value: &f32| value.is_finite())
+}
+
+fn parse_numbers(text: &str) -> Result<Vec<f32>, String> {
+    text.split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|item| !item.is_empty())
+        .map(|item| parse_finite(item).ok_or_else(|| format!("invalid number \"{}\"", item)))
+        .collect()
+}
+
+/// The value of the attribute `name` of an XML tag
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    tag.match_indices(name).find_map(|(index, _)| {
+        if !tag[..index].ends_with(char::is_whitespace) {
+            return None;
+        }
+        let rest = tag[index + name.len()..].trim_start().strip_prefix('=')?;
+        let rest = rest.trim_start();
+        let quote = rest.chars().next().filter(|&c| c == '"' || c == '\'')?;
+        let rest = &rest[1..];
+        rest.find(quote).map(|end| &rest[..end])
+    })
+}
+
+fn parse_svg(text: &str, scale: f32) -> Result<Vec<Polyline>, String> {
+    let mut lines = Vec::new();
+    for tag in text.split('<').skip(1) {
+        let tag = tag.split('>').next().unwrap_or_default();
+        let name = tag
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default();
+        let number = |name: &str| -> Result<f32, String> {
+            attribute(tag, name).map_or(Ok(0.0), |value| {
+                parse_finite(value.trim()).ok_or_else(|| format!("invalid {} \"{}\"", name, value))
+            })
+        };
+        match name {
+            "path" => {
+                if let Some(data) = attribute(tag, "d") {
+                    lines.extend(parse_path_data(data, scale)?);
+                }
+            }
+            "polyline" | "polygon" => {
+                let numbers = parse_numbers(attribute(tag, "points").unwrap_or_default())?;
+                let mut points: Vec<Vec2> = numbers
+                    .chunks_exact(2)
+                    .map(|pair| Vec2::new(pair[0], -pair[1]) * scale)
+                    .collect();
+                let closed = name == "polygon";
+                if closed && let Some(&first) = points.first() {
+                    points.push(first);
+                }
+                lines.push(Polyline { points, closed });
+            }
+            "line" => lines.push(Polyline {
+                points: vec![
+                    Vec2::new(number("x1")?, -number("y1")?) * scale,
+                    Vec2::new(number("x2")?, -number("y2")?) * scale,
+                ],
+                closed: false,
+            }),
+            _ => {}
+        }
+    }
+    Ok(lines)
+}
+
+fn parse_point_list(text: &str, scale: f32) -> Result<Vec<Polyline>, String> {
+    let mut points = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default();
+        let numbers = parse_numbers(line).map_err(|err| format!("line {}: {}", index + 1, err))?;
+        match numbers[..] {
+            [] => {}
+            [x, y] => points.push(Vec2::new(x, y) * scale),
+            _ => {
+                return Err(format!(
+                    "line {}: expected two coordinates, found {}",
+                    index + 1,
+                    numbers.len()
+                ));
+            }
+        }
+    }
+    Ok(vec![Polyline {
+        points,
+        closed: false,
+    }])
+}
+
+/// Drops repeated points and closes lines whose ends meet
+fn clean_line(line: Polyline, tolerance: f32) -> Polyline {
+    let mut points: Vec<Vec2> = Vec::with_capacity(line.points.len());
+    for point in line.points {
+        if points
+            .last()
+            .is_none_or(|last: &Vec2| last.distance(point) > 1e-6)
+        {
+            points.push(point);
+        }
+    }
+    let closed = line.closed
+        || (points.len() > 2 && points[0].distance(points[points.len() - 1]) <= tolerance);
+    if closed && let Some(&first) = points.first() {
+        let last = points.len() - 1;
+        points[last] = first;
+    }
+    Polyline { points, closed }
+}
+
+/// Points every [`SAMPLE_STEP`] along the line (both ends included)
+fn resample(points: &[Vec2]) -> Vec<Vec2> {
+    let mut samples = vec![points[0]];
+    // Distance walked since the last sample
+    let mut walked = 0.0;
+    for pair in points.windows(2) {
+        let (from, to) = (pair[0], pair[1]);
+        let length = from.distance(to);
+        let mut at = SAMPLE_STEP - walked;
+        while at <= length {
+            samples.push(from.lerp(to, at / length));
+            at += SAMPLE_STEP;
+        }
+        walked = length - (at - SAMPLE_STEP);
+    }
+    let last = points[points.len() - 1];
+    if walked > SAMPLE_STEP * 0.5 {
+        samples.push(last);
+    } else if let Some(sample) = samples.last_mut() {
+        *sample = last;
+    }
+    samples
+}
+
+/// Brings an angle in radians between -PI and PI
+fn wrap_angle(angle: f32) -> f32 {
+    (angle + PI).rem_euclid(TAU) - PI
+}
+
+/// The track direction (as in [`SegmentTransform`], in radians) of a vector
+fn direction_of(vector: Vec2) -> f32 {
+    (-vector.x).atan2(vector.y)
+}
+
+/// Line directions at each sample, smoothed over the neighbours and
+/// unwrapped so that they change continuously
+fn sample_directions(samples: &[Vec2], closed: bool) -> Vec<f32> {
+    const SPAN: usize = 2;
+    let count = samples.len();
+    // Closed lines repeat the first sample at the end
+    let period = count - 1;
+    let mut directions: Vec<f32> = Vec::with_capacity(count);
+    for i in 0..count {
+        let (before, after) = if closed {
+            (
+                samples[(i + period - SPAN % period) % period],
+                samples[(i + SPAN) % period],
+            )
+        } else {
+            (
+                samples[i.saturating_sub(SPAN)],
+                samples[(i + SPAN).min(count - 1)],
+            )
+        };
+        let direction = direction_of(after - before);
+        directions.push(match directions.last() {
+            Some(&previous) => previous + wrap_angle(direction - previous),
+            None => direction,
+        });
+    }
+    directions
+}
+
+/// The corners of the line (vertices turning by about 90deg), as distance
+/// along the line and turn side
+fn find_corners(points: &[Vec2]) -> Vec<(f32, Side)> {
+    let mut corners = Vec::new();
+    let mut distance = 0.0;
+    for (index, triple) in points.windows(3).enumerate() {
+        distance += points[index].distance(points[index + 1]);
+        let turn = (triple[1] - triple[0]).angle_to(triple[2] - triple[1]);
+        if (turn.abs().to_degrees() - 90.0).abs() <= CORNER_ANGLE_TOLERANCE {
+            let side = if turn > 0.0 { Side::Left } else { Side::Right };
+            corners.push((distance, side));
+        }
+    }
+    corners
+}
+
+/// A straight or a turn changing the direction by `turn` radians along
+/// `length` meters, with the direction change it actually makes
+fn piece(length: f32, turn: f32) -> (TrackSegment, f32) {
+    if turn.abs() * MAX_TURN_RADIUS <= length {
+        return (TrackSegment::straight(length), 0.0);
+    }
+    let side = if turn > 0.0 { Side::Left } else { Side::Right };
+    (
+        TrackSegment::cyrcle_turn(length / turn.abs(), Angle::from_radians(turn.abs()), side),
+        turn,
+    )
+}
+
+/// Covers `samples` with straights and turns, starting along `direction`;
+/// returns them and the direction at the end.
+///
+/// Every turn changes the direction as much as the line does along it, so
+/// the track direction does not drift away from the line one. A piece fits
+/// when it follows the line directions and, laid from its first sample,
+/// stays within the tolerance from the others.
+fn fit_section(
+    mut direction: f32,
+    samples: &[Vec2],
+    directions: &[f32],
+    tolerance: f32,
+) -> (Vec<TrackSegment>, f32) {
+    let max_direction_error = tolerance / DIRECTION_ERROR_WEIGHT;
+    let mut segments = Vec::new();
+    let mut start = 0;
+    while start + 1 < samples.len() {
+        let origin = SegmentTransform::new(samples[start], Angle::from_radians(direction));
+        // The track can start off the line direction (after 90deg corners)
+        let start_error = (directions[start] - direction).abs();
+        // The longest fitting straight and turn, with their direction change
+        let mut fits: [Option<(usize, TrackSegment, f32)>; 2] = [None, None];
+        let mut length = 0.0;
+        let mut end = start + 1;
+        let mut last_fit = end;
+        while end < samples.len() && end <= last_fit + FIT_LOOKAHEAD {
+            length += samples[end - 1].distance(samples[end]);
+            for (kind, turn) in [(0, 0.0), (1, directions[end] - direction)] {
+                let mut walked = 0.0;
+                let follows_line = (start + 1..=end).all(|i| {
+                    walked += samples[i - 1].distance(samples[i]);
+                    (direction + turn * walked / length - directions[i]).abs()
+                        <= max_direction_error + start_error * (1.0 - walked / length)
+                });
+                if !follows_line {
+                    continue;
+                }
+                let (segment, turn) = piece(length, turn);
+                if samples[start..=end]
+                    .iter()
+                    .all(|&sample| segment.project(origin, sample).1 <= tolerance)
+                {
+                    fits[kind] = Some((end, segment, turn));
+                    last_fit = end;
+                }
+            }
+            end += 1;
+        }
+
+        let (end, segment, turn) = match fits {
+            [Some(straight), Some(turn)]
+                if (straight.0 - start) as f32 >= (turn.0 - start) as f32 * STRAIGHT_PREFERENCE =>
+            {
+                Some(straight)
+            }
+            [_, Some(turn)] => Some(turn),
+            [straight, None] => straight,
+        }
+        .unwrap_or_else(|| {
+            // The line bends too sharply: follow it to the next sample
+            let (segment, turn) = piece(
+                samples[start].distance(samples[start + 1]),
+                directions[start + 1] - direction,
+            );
+            (start + 1, segment, turn)
+        });
+        segments.push(segment);
+        direction += turn;
+        start = end;
+    }
+    (segments, direction)
+}
+
+fn fit_line(line: &Polyline, params: &TrackImportParams) -> Result<ImportedTrack, String> {
+    let samples = resample(&line.points);
+    if samples.len() < 4 {
+        return Err("the line is too short".to_string());
+    }
+    let directions = sample_directions(&samples, line.closed);
+    let last_index = samples.len() - 1;
+    let index_at = |distance: f32| ((distance / SAMPLE_STEP).round() as usize).min(last_index);
+    let total_length = last_index as f32 * SAMPLE_STEP;
+
+    let tip_length = TrackSegment::start().length();
+    let mut segments = Vec::new();
+    let (start_direction, begin, finish) = if line.closed {
+        (directions[0], 0.0, total_length)
+    } else {
+        if total_length < tip_length * 2.0 + SAMPLE_STEP * 2.0 {
+            return Err(format!(
+                "the line is too short ({:.2} m, the start and end tips alone take {:.2} m)",
+                total_length,
+                tip_length * 2.0
+            ));
+        }
+        segments.push(TrackSegment::start());
+        (directions[0], tip_length, total_length - tip_length)
+    };
+
+    // Each corner takes up to half of the line on each side of it
+    let corners: Vec<(f32, Side)> = find_corners(&line.points)
+        .into_iter()
+        .filter(|&(distance, _)| distance > begin && distance < finish)
+        .collect();
+    let mut corner_legs = Vec::new();
+    for (index, &(distance, side)) in corners.iter().enumerate() {
+        let before = index.checked_sub(1).map_or(begin, |i| corners[i].0);
+        let after = corners.get(index + 1).map_or(finish, |corner| corner.0);
+        let half_length = MAX_CORNER_HALF_LENGTH
+            .min((distance - before) / 2.0)
+            .min((after - distance) / 2.0);
+        if half_length >= SAMPLE_STEP * 2.0 {
+            corner_legs.push((distance, half_length, side));
+        }
+    }
+
+    let mut direction = start_direction;
+    let mut from = begin;
+    let sections = corner_legs
+        .iter()
+        .map(|&(distance, half_length, side)| (distance - half_length, Some((half_length, side))))
+        .chain(std::iter::once((finish, None)));
+    for (to, corner) in sections {
+        let range = index_at(from)..=index_at(to);
+        let (section, end_direction) = fit_section(
+            direction,
+            &samples[range.clone()],
+            &directions[range],
+            params.tolerance,
+        );
+        segments.extend(section);
+        direction = end_direction;
+        if let Some((half_length, side)) = corner {
+            segments.push(TrackSegment::ninety_deg_turn(half_length, side));
+            direction += FRAC_PI_2 * side.sign();
+            from = to + half_length * 2.0;
+        }
+    }
+    if !line.closed {
+        segments.push(TrackSegment::end());
+    }
+
+    // Distance of every sample from the whole fitted center line
+    let start_origin = SegmentTransform::new(samples[0], Angle::from_radians(start_direction));
+    let mut segment_origin = start_origin;
+    let placed: Vec<(TrackSegment, SegmentTransform)> = segments
+        .iter()
+        .map(|&segment| {
+            let current = segment_origin;
+            segment_origin = segment.compute_next_origin(segment_origin);
+            (segment, current)
+        })
+        .collect();
+    let errors: Vec<f32> = samples
+        .iter()
+        .map(|&sample| {
+            placed
+                .iter()
+                .map(|(segment, origin)| segment.project(*origin, sample).1)
+                .fold(f32::INFINITY, f32::min)
+        })
+        .collect();
+    let max_error = errors.iter().copied().fold(0.0, f32::max);
+    let rms_error =
+        (errors.iter().map(|error| error * error).sum::<f32>() / errors.len() as f32).sqrt();
+
+    let mut track =
+        Track::new(segments).with_direction(Angle::from_radians(wrap_angle(start_direction)));
+    if line.closed {
+        track = track.with_laps(params.laps);
+    }
+    Ok(ImportedTrack {
+        track,
+        max_error,
+        rms_error,
+        closing_gap: line
+            .closed
+            .then(|| segment_origin.position().distance(start_origin.position())),
+    })
+}
+
+/// Imports the longest line of an SVG file (`.svg`) or a point list (any
+/// other extension) as a track
+pub fn import_track(path: &str, params: &TrackImportParams) -> wasmtime::Result<ImportedTrack> {
+    params
+        .validate()
+        .map_err(|err| wasmtime::Error::msg(format!("invalid import parameters: {}", err)))?;
+    let text = std::fs::read_to_string(path)
+        .map_err(|err| wasmtime::Error::msg(format!("cannot read \"{}\": {}", path, err)))?;
+
+    let is_svg = Path::new(path)
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("svg"));
+    let lines = if is_svg {
+        parse_svg(&text, params.scale.unwrap_or(SVG_SCALE))
+    } else {
+        parse_point_list(&text, params.scale.unwrap_or(POINTS_SCALE))
+    }
+    .map_err(|err| wasmtime::Error::msg(format!("cannot parse \"{}\": {}", path, err)))?;
+
+    let line = lines
+        .into_iter()
+        .map(|line| clean_line(line, params.tolerance))
+        .filter(|line| line.points.len() >= 2)
+        .max_by(|a, b| a.length().total_cmp(&b.length()))
+        .ok_or_else(|| wasmtime::Error::msg(format!("no line found in \"{}\"", path)))?;
+    fit_line(&line, params)
+        .map_err(|err| wasmtime::Error::msg(format!("cannot import \"{}\": {}", path, err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params() -> TrackImportParams {
+        TrackImportParams {
+            scale: None,
+            tolerance: 0.005,
+            laps: 3,
+        }
+    }
+
+    #[test]
+    fn fit_circle() {
+        let radius = 1.0;
+        let points = (0..720)
+            .map(|i| Vec2::from_angle(i as f32 / 720.0 * TAU) * radius)
+            .collect();
+        let imported = fit_line(
+            &Polyline {
+                points,
+                closed: true,
+            },
+            &params(),
+        )
+        .unwrap();
+
+        assert_eq!(imported.track.laps(), Some(3));
+        assert!(imported.max_error <= params().tolerance * 2.0);
+        assert!(imported.closing_gap.unwrap() <= params().tolerance * 2.0);
+        let length: f32 = imported
+            .track
+            .segments()
+            .iter()
+            .map(TrackSegment::length)
+            .sum();
+        assert!((length - TAU * radius).abs() < 0.05);
+    }
+
+    #[test]
+    fn fit_rectangle() {
+        // Drawn from the middle of a side, the start is not a corner
+        let corners = [
+            Vec2::new(1.0, 0.0),
+            Vec2::new(2.0, 0.0),
+            Vec2::new(2.0, 1.0),
+            Vec2::new(0.0, 1.0),
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1.0, 0.0),
+        ];
+        let imported = fit_line(
+            &Polyline {
+                points: corners.to_vec(),
+                closed: true,
+            },
+            &params(),
+        )
+        .unwrap();
+
+        let corners = imported
+            .track
+            .segments()
+            .iter()
+            .filter(|segment| matches!(segment, TrackSegment::NinetyDegTurn(_)))
+            .count();
+        assert_eq!(corners, 4);
+        assert!(imported.max_error <= params().tolerance * 2.0);
+    }
+
+    #[test]
+    fn fit_open_line() {
+        let imported = fit_line(
+            &Polyline {
+                points: vec![Vec2::ZERO, Vec2::new(3.0, 0.0)],
+                closed: false,
+            },
+            &params(),
+        )
+        .unwrap();
+
+        let segments = imported.track.segments();
+        assert_eq!(segments.first(), Some(&TrackSegment::start()));
+        assert_eq!(segments.last(), Some(&TrackSegment::end()));
+        assert!(
+            segments[1..segments.len() - 1]
+                .iter()
+                .all(|segment| matches!(segment, TrackSegment::Straight(_)))
+        );
+        assert!(imported.max_error <= params().tolerance);
+        assert_eq!(imported.track.laps(), None);
+    }
+
+    #[test]
+    fn non_finite_coordinates() {
+        for text in ["0 0\nnan nan\n1 0\n", "0 0\n1 0\n2 inf\n", "0 0\n1e39 0\n"] {
+            let err = parse_point_list(text, 1.0).err().unwrap();
+            assert!(err.starts_with("line "), "unexpected error {}", err);
+        }
+        assert_eq!(
+            parse_point_list("0 0\nnan nan\n", 1.0).err().unwrap(),
+            "line 2: invalid number \"nan\""
+        );
+        assert!(parse_svg(r#"<line x1="0" y1="0" x2="inf" y2="1"/>"#, 1.0).is_err());
+        assert!(parse_svg(r#"<polyline points="0,0 NaN,1"/>"#, 1.0).is_err());
+        assert!(parse_svg(r#"<path d="M 0 0 L 1e39 0"/>"#, 1.0).is_err());
+    }
+}
@@ -1,152 +1,152 @@
-use bevy::{math::Vec2, text::cosmic_text::Angle};
+use bevy::text::cosmic_text::Angle;
+use executor::wasmtime;
 
 use crate::{
     TrackId,
-    track::{SegmentTransform, Track, TrackSegment},
+    track::{Track, TrackSegment},
+    track_generator::{TrackGeneratorParams, generate_track},
     utils::Side,
 };
 
 fn build_line_track() -> Track {
-    Track::new(
-        v2(2.0, 6.0),
-        origin(0.0, -1.75, 2.0),
-        vec![start(), straight(3.0), end()],
-    )
+    Track::new(vec![start(), straight(3.0), end()]).with_direction(Angle::from_degrees(2.0))
 }
 
 fn build_angle_track() -> Track {
-    Track::new(
-        v2(3.0, 5.5),
-        origin(-0.5, -1.75, 0.0),
-        vec![
-            start(),
-            straight(1.5),
-            t90(RIGHT, 0.5),
-            t90(LEFT, 0.5),
-            straight(0.5),
-            end(),
-        ],
-    )
+    Track::new(vec![
+        start(),
+        straight(1.5),
+        t90(RIGHT, 0.5),
+        t90(LEFT, 0.5),
+        straight(0.5),
+        end(),
+    ])
 }
 
 fn build_turn_track() -> Track {
-    Track::new(
-        v2(4.0, 4.5),
-        origin(-1.0, -1.25, 0.0),
-        vec![
-            start(),
-            straight(1.0),
-            turn(135.0, RIGHT, 0.75),
-            straight(0.25),
-            turn(180.0, LEFT, 0.5),
-            straight(0.25),
-            end(),
-        ],
-    )
+    Track::new(vec![
+        start(),
+        straight(1.0),
+        turn(135.0, RIGHT, 0.75),
+        straight(0.25),
+        turn(180.0, LEFT, 0.5),
+        straight(0.25),
+        end(),
+    ])
 }
 
 fn build_simple_track() -> Track {
-    Track::new(
-        v2(5.2, 7.2),
-        origin(0.4, -2.5, 0.0),
-        vec![
-            start(),
-            straight(2.0),
-            t90(RIGHT, 0.5),
-            turn(120.0, LEFT, 1.0),
-            t90(LEFT, 1.0),
-            turn(60.0, RIGHT, 2.0),
-            end(),
-        ],
-    )
+    Track::new(vec![
+        start(),
+        straight(2.0),
+        t90(RIGHT, 0.5),
+        turn(120.0, LEFT, 1.0),
+        t90(LEFT, 1.0),
+        turn(60.0, RIGHT, 2.0),
+        end(),
+    ])
 }
 
 fn build_race_track() -> Track {
-    Track::new(
-        v2(6.25, 7.0),
-        origin(-2.525, -2.75, 0.0),
-        vec![
-            start(),
-            straight(1.5),
-            turn(100.0, RIGHT, 1.5),
-            turn(150.0, LEFT, 0.5),
-            straight(0.5),
-            turn(140.0, RIGHT, 0.7),
-            straight(1.8),
-            turn(90.0, RIGHT, 1.0),
-            turn(45.0, RIGHT, 0.5),
-            straight(4.0),
-            turn(45.0, LEFT, 1.5),
-            turn(90.0, LEFT, 0.5),
-            straight(1.0),
-            turn(90.0, LEFT, 2.0),
-            straight(0.5),
-            // straight(2.0),
-            // t90(RIGHT, 0.25),
-            // t90(RIGHT, 0.25),
-            // turn(90.0, LEFT, 2.5),
-            // turn(90.0, LEFT, 2.25),
-            // turn(90.0, LEFT, 2.0),
-            // turn(90.0, LEFT, 1.75),
-            // turn(90.0, LEFT, 1.5),
-            // turn(180.0, LEFT, 0.25),
-            // turn(90.0, RIGHT, 1.0),
-            // turn(90.0, RIGHT, 1.25),
-            // turn(90.0, RIGHT, 1.5),
-            // turn(90.0, RIGHT, 1.75),
-            // turn(90.0, RIGHT, 2.0),
-            // straight(0.25),
-            // turn(90.0, LEFT, 0.5),
-            // straight(0.25),
-            // turn(180.0, RIGHT, 0.25),
-            // straight(0.75),
-            // t90(LEFT, 0.25),
-            // t90(RIGHT, 0.25),
-            // t90(LEFT, 0.25),
-            // t90(RIGHT, 0.25),
-            // t90(LEFT, 0.25),
-            // t90(RIGHT, 0.25),
-            // t90(RIGHT, 0.25),
-            // t90(LEFT, 0.25),
-            // straight(1.75),
-            // turn(180.0, LEFT, 0.55),
-            // turn(45.0, LEFT, 0.25),
-            // turn(90.0, RIGHT, 0.25),
-            // turn(90.0, LEFT, 0.25),
-            // turn(90.0, RIGHT, 0.25),
-            // turn(90.0, LEFT, 0.25),
-            // turn(90.0, RIGHT, 0.25),
-            // turn(90.0, LEFT, 0.25),
-            // turn(90.0, RIGHT, 0.25),
-            // turn(90.0, LEFT, 0.25),
-            // turn(90.0, RIGHT, 0.25),
-            // turn(90.0, LEFT, 0.25),
-            // turn(90.0, RIGHT, 0.25),
-            // turn(225.0, LEFT, 0.25),
-            // straight(0.75),
-            // turn(135.0, RIGHT, 0.25),
-            // straight(0.75),
-            // turn(45.0, RIGHT, 0.25),
-            // straight(0.5),
-            // turn(90.0, RIGHT, 0.25),
-            // straight(0.75),
-            end(),
-        ],
-    )
+    Track::new(vec![
+        start(),
+        straight(1.5),
+        turn(100.0, RIGHT, 1.5),
+        turn(150.0, LEFT, 0.5),
+        straight(0.5),
+        turn(140.0, RIGHT, 0.7),
+        straight(1.8),
+        turn(90.0, RIGHT, 1.0),
+        turn(45.0, RIGHT, 0.5),
+        straight(4.0),
+        turn(45.0, LEFT, 1.5),
+        turn(90.0, LEFT, 0.5),
+        straight(1.0),
+        turn(90.0, LEFT, 2.0),
+        straight(0.5),
+        // straight(2.0),
+        // t90(RIGHT, 0.25),
+        // t90(RIGHT, 0.25),
+        // turn(90.0, LEFT, 2.5),
+        // turn(90.0, LEFT, 2.25),
+        // turn(90.0, LEFT, 2.0),
+        // turn(90.0, LEFT, 1.75),
+        // turn(90.0, LEFT, 1.5),
+        // turn(180.0, LEFT, 0.25),
+        // turn(90.0, RIGHT, 1.0),
+        // turn(90.0, RIGHT, 1.25),
+        // turn(90.0, RIGHT, 1.5),
+        // turn(90.0, RIGHT, 1.75),
+        // turn(90.0, RIGHT, 2.0),
+        // straight(0.25),
+        // turn(90.0, LEFT, 0.5),
+        // straight(0.25),
+        // turn(180.0, RIGHT, 0.25),
+        // straight(0.75),
+        // t90(LEFT, 0.25),
+        // t90(RIGHT, 0.25),
+        // t90(LEFT, 0.25),
+        // t90(RIGHT, 0.25),
+        // t90(LEFT, 0.25),
+        // t90(RIGHT, 0.25),
+        // t90(RIGHT, 0.25),
+        // t90(LEFT, 0.25),
+        // straight(1.75),
+        // turn(180.0, LEFT, 0.55),
+        // turn(45.0, LEFT, 0.25),
+        // turn(90.0, RIGHT, 0.25),
+        // turn(90.0, LEFT, 0.25),
+        // turn(90.0, RIGHT, 0.25),
+        // turn(90.0, LEFT, 0.25),
+        // turn(90.0, RIGHT, 0.25),
+        // turn(90.0, LEFT, 0.25),
+        // turn(90.0, RIGHT, 0.25),
+        // turn(90.0, LEFT, 0.25),
+        // turn(90.0, RIGHT, 0.25),
+        // turn(90.0, LEFT, 0.25),
+        // turn(90.0, RIGHT, 0.25),
+        // turn(225.0, LEFT, 0.25),
+        // straight(0.75),
+        // turn(135.0, RIGHT, 0.25),
+        // straight(0.75),
+        // turn(45.0, RIGHT, 0.25),
+        // straight(0.5),
+        // turn(90.0, RIGHT, 0.25),
+        // straight(0.75),
+        end(),
+    ])
 }
 
-pub fn build_track(id: TrackId) -> Track {
-    match id {
+fn build_loop_track() -> Track {
+    Track::new(vec![
+        straight(1.5),
+        turn(90.0, LEFT, 0.75),
+        straight(0.5),
+        turn(90.0, LEFT, 0.75),
+        straight(1.5),
+        turn(90.0, LEFT, 0.75),
+        straight(0.5),
+        turn(90.0, LEFT, 0.75),
+    ])
+    .with_laps(3)
+}
+
+/// Builds a built-in track (`seed` and `random` are used only by the random
+/// track)
+pub fn build_track(
+    id: TrackId,
+    seed: u64,
+    random: &TrackGeneratorParams,
+) -> wasmtime::Result<Track> {
+    Ok(match id {
         TrackId::Line => build_line_track(),
         TrackId::Angle => build_angle_track(),
         TrackId::Turn => build_turn_track(),
         TrackId::Simple => build_simple_track(),
         TrackId::Race => build_race_track(),
-    }
-}
-
-fn v2(x: f32, y: f32) -> Vec2 {
-    Vec2::new(x, y)
+        TrackId::Loop => build_loop_track(),
+        TrackId::Random => generate_track(seed, random)?,
+    })
 }
 
 fn start() -> TrackSegment {
@@ -169,9 +169,5 @@ fn turn(angle: f32, side: Side, radius: f32) -> TrackSegment {
     TrackSegment::cyrcle_turn(radius, Angle::from_degrees(angle), side)
 }
 
-fn origin(x: f32, y: f32, angle: f32) -> SegmentTransform {
-    SegmentTransform::new(Vec2::new(x, y), Angle::from_degrees(angle))
-}
-
 const LEFT: Side = Side::Left;
 const RIGHT: Side = Side::Right;
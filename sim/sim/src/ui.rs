@@ -21,6 +21,7 @@ use crate::{
     app_builder::{AppType, BotConfigWrapper},
     runner::{BotExecutionData, run_bot_from_file},
     track::Track,
+    ui_editor::editor_gui_setup,
     ui_runner::runner_gui_setup,
     ui_test::test_gui_setup,
 };
@@ -79,7 +80,9 @@ impl Plugin for GuiSetupPlugin {
             app.add_plugins(CameraSetupPlugin);
             app.add_plugins(common_gui_setup);
 
-            if has_physics {
+            if let AppType::Editor(output) = &self.app_type {
+                editor_gui_setup(app, output.clone());
+            } else if has_physics {
                 app.add_plugins(test_gui_setup);
             } else {
                 let visualizer_data =
@@ -390,6 +393,16 @@ const HELP_TEXT: &str = "
 | `arrow keys` / `W`  `A`  `S`  `D` | | move bot forward/side |
 |              | *PWM sliders* | adjust pwms for forward/side commands |
 
+## Track editor
+
+| **Keypress** | **Icon** | **Command** |
+| ------------ | -------- | ----------- |
+|              | *click on track* / *click on segment name* | select segment |
+|              | \u{e145} *append* + *segment kind* | append segment |
+|              | \u{e145} *insert* + *segment kind* | insert segment before the selected one |
+| `delete`     | \u{e92e} | delete selected segment |
+| `ctrl` + `S` | \u{e161} | save track file |
+
 ## Record player
 
 | **Keypress** | **Icon** | **Command** |
@@ -0,0 +1,698 @@
+use bevy::{
+    app::{App, AppExit},
+    picking::{
+        events::{Click, Pointer},
+        mesh_picking::MeshPickingPlugin,
+        pointer::PointerButton,
+    },
+    prelude::*,
+    text::cosmic_text::Angle,
+};
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+use bevy_panorbit_camera::PanOrbitCamera;
+use egui_material_icons::icons::{
+    ICON_ADD, ICON_DELETE, ICON_EXIT_TO_APP, ICON_HELP, ICON_SAVE, ICON_ZOOM_IN, ICON_ZOOM_OUT,
+};
+
+use crate::{
+    track::{
+        Obstacle, SideMarkers, Surface, Track, TrackAssets, TrackRoot, TrackSegment, setup_track,
+    },
+    track_check::check_track,
+    track_file::{TrackFile, save_track_to_file},
+    ui::{HelpState, camera_buttons, help_dialog, icon_button, keyboard_camera_control, rl, rlc},
+    utils::{EntityFeatures, Side},
+};
+
+const NEW_STRAIGHT_LENGTH: f32 = 0.5;
+const NEW_TURN_RADIUS: f32 = 0.5;
+const NEW_TURN_ANGLE: f32 = 90.0;
+/// Largest height step (in meters) the editor allows at a segment start
+const MAX_STEP: f32 = 0.02;
+
+pub fn editor_gui_setup(app: &mut App, output: String) {
+    let track = app.world().resource::<Track>();
+    let segments = track.segments().to_vec();
+    let obstacles = track.obstacles().to_vec();
+    app.add_plugins(MeshPickingPlugin)
+        .add_systems(EguiPrimaryContextPass, editor_gui_update)
+        .add_systems(Update, update_track_preview)
+        .add_observer(select_clicked_segment)
+        .insert_resource(EditorGuiState::new(output, segments, obstacles));
+}
+
+#[derive(Resource)]
+struct EditorGuiState {
+    base_text_size: f32,
+    help_state: HelpState,
+    /// Path of the track file written on save
+    output: String,
+    segments: Vec<TrackSegment>,
+    /// Obstacles of the track, following their segments through the edits
+    obstacles: Vec<Obstacle>,
+    selected: Option<usize>,
+    /// The preview must be rebuilt
+    changed: bool,
+    /// Only the highlight of the selected segment must be moved
+    selection_changed: bool,
+    /// Segments changed since the last save
+    unsaved: bool,
+    /// Validation errors and geometry warnings of the current segments
+    issues: Vec<String>,
+    status_message: Option<(String, egui::Color32)>,
+}
+
+impl EditorGuiState {
+    fn new(output: String, segments: Vec<TrackSegment>, obstacles: Vec<Obstacle>) -> Self {
+        Self {
+            base_text_size: 8.0,
+            help_state: HelpState::new(),
+            output,
+            segments,
+            obstacles,
+            selected: None,
+            changed: true,
+            selection_changed: false,
+            unsaved: false,
+            issues: Vec::new(),
+            status_message: None,
+        }
+    }
+
+    fn edited(&mut self) {
+        self.changed = true;
+        self.unsaved = true;
+        self.status_message = None;
+    }
+
+    fn select(&mut self, selected: Option<usize>) {
+        if selected != self.selected {
+            self.selected = selected;
+            self.selection_changed = true;
+        }
+    }
+
+    /// Appends a segment (before the end tip of open tracks)
+    fn append(&mut self, segment: TrackSegment) {
+        let index = match self.segments.last() {
+            Some(TrackSegment::End) => self.segments.len() - 1,
+            _ => self.segments.len(),
+        };
+        self.insert_at(index, segment);
+    }
+
+    /// Inserts a segment before the selected one (after it if it is the
+    /// start tip), or appends it when nothing is selected
+    fn insert(&mut self, segment: TrackSegment) {
+        match self.selected {
+            Some(index) if self.segments[index] == TrackSegment::Start => {
+                self.insert_at(index + 1, segment)
+            }
+            Some(index) => self.insert_at(index, segment),
+            None => self.append(segment),
+        }
+    }
+
+    fn insert_at(&mut self, index: usize, segment: TrackSegment) {
+        self.segments.insert(index, segment);
+        for obstacle in &mut self.obstacles {
+            if obstacle.segment >= index {
+                obstacle.segment += 1;
+            }
+        }
+        self.selected = Some(index);
+        self.edited();
+    }
+
+    fn delete_selected(&mut self) {
+        if let Some(index) = self.selected.take() {
+            self.segments.remove(index);
+            // The obstacles of the deleted segment go with it
+            self.obstacles.retain(|obstacle| obstacle.segment != index);
+            for obstacle in &mut self.obstacles {
+                if obstacle.segment > index {
+                    obstacle.segment -= 1;
+                }
+            }
+            if index < self.segments.len() {
+                self.selected = Some(index);
+            }
+            self.edited();
+        }
+    }
+}
+
+fn segment_summary(segment: &TrackSegment) -> String {
+    match segment {
+        TrackSegment::Start => "start".to_string(),
+        TrackSegment::End => "end".to_string(),
+        TrackSegment::Straight(data) => format!("straight {:.2} m", data.length),
+        TrackSegment::Crossing(data) => format!("crossing {:.2} m", data.length),
+        TrackSegment::NinetyDegTurn(data) => {
+            format!("90deg turn {} {:.2} m", data.side, data.line_half_length)
+        }
+        TrackSegment::CyrcleTurn(data) => format!(
+            "turn {} {:.0}deg r {:.2} m",
+            data.side,
+            data.angle.to_degrees(),
+            data.radius
+        ),
+    }
+}
+
+fn length_editor(ui: &mut egui::Ui, label: &str, value: &mut f32, size: f32) -> bool {
+    ui.horizontal(|ui| {
+        rl(ui, label, size);
+        ui.add(
+            egui::DragValue::new(value)
+                .speed(0.01)
+                .range(0.05..=20.0)
+                .suffix(" m"),
+        )
+        .changed()
+    })
+    .inner
+}
+
+/// Edits a height change (steps and ramp rises), shown in millimeters
+fn height_editor(ui: &mut egui::Ui, label: &str, value: &mut f32, limit: f32, size: f32) -> bool {
+    ui.horizontal(|ui| {
+        rl(ui, label, size);
+        let mut millimeters = *value * 1000.0;
+        let changed = ui
+            .add(
+                egui::DragValue::new(&mut millimeters)
+                    .speed(0.5)
+                    .range(-limit * 1000.0..=limit * 1000.0)
+                    .suffix(" mm"),
+            )
+            .changed();
+        if changed {
+            *value = millimeters / 1000.0;
+        }
+        changed
+    })
+    .inner
+}
+
+fn side_editor(ui: &mut egui::Ui, side: &mut Side, size: f32) -> bool {
+    ui.horizontal(|ui| {
+        rl(ui, "Side", size);
+        let left = ui.selectable_value(side, Side::Left, "left").changed();
+        let right = ui.selectable_value(side, Side::Right, "right").changed();
+        left || right
+    })
+    .inner
+}
+
+fn markers_editor(ui: &mut egui::Ui, markers: &mut SideMarkers, size: f32) -> bool {
+    let mut changed = false;
+    for (label, left, right) in [
+        (
+            "Start markers",
+            &mut markers.start_left,
+            &mut markers.start_right,
+        ),
+        ("End markers", &mut markers.end_left, &mut markers.end_right),
+    ] {
+        ui.horizontal(|ui| {
+            rl(ui, label, size);
+            changed |= ui.checkbox(left, "left").changed();
+            changed |= ui.checkbox(right, "right").changed();
+        });
+    }
+    changed
+}
+
+fn surface_editor(ui: &mut egui::Ui, surface: &mut Surface, size: f32) -> bool {
+    ui.horizontal(|ui| {
+        rl(ui, "Surface", size);
+        let mut changed = false;
+        for (value, label) in [
+            (Surface::Normal, "normal"),
+            (Surface::Dusty, "dusty"),
+            (Surface::Slippery, "slippery"),
+            (Surface::HighGrip, "high grip"),
+        ] {
+            changed |= ui.selectable_value(surface, value, label).changed();
+        }
+        let custom = matches!(surface, Surface::Friction(_));
+        if ui.selectable_label(custom, "custom").clicked() && !custom {
+            *surface = Surface::Friction(surface.friction());
+            changed = true;
+        }
+        if let Surface::Friction(friction) = surface {
+            changed |= ui
+                .add(egui::DragValue::new(friction).speed(0.01).range(0.0..=2.0))
+                .changed();
+        }
+        changed
+    })
+    .inner
+}
+
+/// Shows the parameters of a segment, returns true if any was changed
+fn segment_editor(ui: &mut egui::Ui, segment: &mut TrackSegment, size: f32) -> bool {
+    let is_straight = matches!(segment, TrackSegment::Straight(_));
+    match segment {
+        TrackSegment::Start | TrackSegment::End => {
+            rl(ui, "No parameters", size);
+            false
+        }
+        TrackSegment::Straight(data) | TrackSegment::Crossing(data) => {
+            let length = length_editor(ui, "Length", &mut data.length, size);
+            let rise =
+                is_straight && height_editor(ui, "Rise", &mut data.rise, data.length * 0.5, size);
+            let step = height_editor(ui, "Step", &mut data.step, MAX_STEP, size);
+            let markers = markers_editor(ui, &mut data.markers, size);
+            let surface = surface_editor(ui, &mut data.surface, size);
+            length || rise || step || markers || surface
+        }
+        TrackSegment::NinetyDegTurn(data) => {
+            let length = length_editor(ui, "Half length", &mut data.line_half_length, size);
+            let side = side_editor(ui, &mut data.side, size);
+            let step = height_editor(ui, "Step", &mut data.step, MAX_STEP, size);
+            let markers = markers_editor(ui, &mut data.markers, size);
+            let surface = surface_editor(ui, &mut data.surface, size);
+            length || side || step || markers || surface
+        }
+        TrackSegment::CyrcleTurn(data) => {
+            let radius = length_editor(ui, "Radius", &mut data.radius, size);
+            let mut degrees = data.angle.to_degrees();
+            let angle = ui
+                .horizontal(|ui| {
+                    rl(ui, "Angle", size);
+                    ui.add(
+                        egui::DragValue::new(&mut degrees)
+                            .speed(1.0)
+                            .range(1.0..=360.0)
+                            .suffix(" deg"),
+                    )
+                    .changed()
+                })
+                .inner;
+            if angle {
+                data.angle = Angle::from_degrees(degrees);
+            }
+            let side = side_editor(ui, &mut data.side, size);
+            let step = height_editor(ui, "Step", &mut data.step, MAX_STEP, size);
+            let markers = markers_editor(ui, &mut data.markers, size);
+            let surface = surface_editor(ui, &mut data.surface, size);
+            radius || angle || side || step || markers || surface
+        }
+    }
+}
+
+fn new_segment_buttons(ui: &mut egui::Ui, size: f32) -> Option<TrackSegment> {
+    let mut segment = None;
+    if ui
+        .button(egui::RichText::new("straight").size(size))
+        .clicked()
+    {
+        segment = Some(TrackSegment::straight(NEW_STRAIGHT_LENGTH));
+    }
+    if ui.button(egui::RichText::new("turn").size(size)).clicked() {
+        segment = Some(TrackSegment::cyrcle_turn(
+            NEW_TURN_RADIUS,
+            Angle::from_degrees(NEW_TURN_ANGLE),
+            Side::Left,
+        ));
+    }
+    if ui.button(egui::RichText::new("90deg").size(size)).clicked() {
+        segment = Some(TrackSegment::ninety_deg_turn(NEW_TURN_RADIUS, Side::Left));
+    }
+    segment
+}
+
+fn editor_gui_update(
+    mut contexts: EguiContexts,
+    mut gui_state: ResMut<EditorGuiState>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut exit: EventWriter<AppExit>,
+    track: Res<Track>,
+    time: Res<Time>,
+    mut camera: Query<(&mut PanOrbitCamera, &Transform)>,
+) -> Result {
+    let ctx = contexts.ctx_mut()?;
+    let (mut po_camera, po_transform) = camera.single_mut()?;
+
+    let base_text_size = gui_state.base_text_size;
+    help_dialog(ctx, &mut gui_state.help_state, base_text_size);
+
+    egui::TopBottomPanel::bottom("bottom_panel")
+        .resizable(false)
+        .default_height(gui_state.base_text_size * 1.8)
+        .show_separator_line(false)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                let size = gui_state.base_text_size * 4.0;
+                if icon_button(ui, ICON_HELP, size).clicked()
+                    || keyboard_input.just_pressed(KeyCode::Slash)
+                    || keyboard_input.just_pressed(KeyCode::F1)
+                {
+                    gui_state.help_state.is_open = true;
+                }
+                ui.separator();
+
+                let ctrl =
+                    keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+                if icon_button(ui, ICON_SAVE, size).clicked()
+                    || (ctrl && keyboard_input.just_pressed(KeyCode::KeyS))
+                {
+                    let result = TrackFile::from_track(&track)
+                        .validate()
+                        .map_err(|err| format!("cannot save an invalid track: {}", err))
+                        .and_then(|_| {
+                            save_track_to_file(&track, &gui_state.output)
+                                .map_err(|err| err.to_string())
+                        });
+                    gui_state.status_message = Some(match result {
+                        Ok(()) => {
+                            gui_state.unsaved = false;
+                            (
+                                format!("track saved to \"{}\"", gui_state.output),
+                                egui::Color32::GREEN,
+                            )
+                        }
+                        Err(err) => (err, egui::Color32::RED),
+                    });
+                }
+                ui.separator();
+
+                match &gui_state.status_message {
+                    Some((message, color)) => {
+                        rlc(ui, message, size * 0.5, *color);
+                    }
+                    None if gui_state.unsaved => {
+                        rlc(ui, "unsaved changes", size * 0.5, egui::Color32::YELLOW);
+                    }
+                    None => {}
+                }
+
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if icon_button(ui, ICON_EXIT_TO_APP, size).clicked()
+                        || keyboard_input.just_released(KeyCode::KeyQ)
+                    {
+                        exit.write(AppExit::Success);
+                    }
+                    if icon_button(ui, ICON_ZOOM_IN, size).clicked() {
+                        gui_state.base_text_size += 1.0;
+                        gui_state.base_text_size = gui_state.base_text_size.max(3.0);
+                    }
+                    if icon_button(ui, ICON_ZOOM_OUT, size).clicked() {
+                        gui_state.base_text_size -= 1.0;
+                    }
+                });
+            });
+        });
+
+    let cb_size = gui_state.base_text_size * 3.0;
+    egui::SidePanel::left("left_panel")
+        .resizable(false)
+        .default_width(cb_size * 3.0)
+        .show_separator_line(false)
+        .show(ctx, |ui| {
+            camera_buttons(
+                ui,
+                gui_state.base_text_size,
+                po_camera.as_mut(),
+                &keyboard_input,
+                &track,
+            );
+        });
+
+    egui::SidePanel::right("right_panel")
+        .resizable(false)
+        .default_width(gui_state.base_text_size * 30.0)
+        .show_separator_line(false)
+        .show(ctx, |ui| {
+            let size = gui_state.base_text_size * 1.5;
+            ui.vertical_centered(|ui| {
+                rl(ui, "Segments", gui_state.base_text_size);
+            });
+            ui.separator();
+
+            egui::ScrollArea::vertical()
+                .max_height(ui.available_height() * 0.4)
+                .show(ui, |ui| {
+                    let mut clicked = None;
+                    for (index, segment) in gui_state.segments.iter().enumerate() {
+                        let text = format!("{:3}  {}", index, segment_summary(segment));
+                        if ui
+                            .selectable_label(
+                                gui_state.selected == Some(index),
+                                egui::RichText::new(text).size(size),
+                            )
+                            .clicked()
+                        {
+                            clicked = Some(index);
+                        }
+                    }
+                    if let Some(index) = clicked {
+                        let selected = if gui_state.selected == Some(index) {
+                            None
+                        } else {
+                            Some(index)
+                        };
+                        gui_state.select(selected);
+                    }
+                });
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                icon_button(ui, ICON_ADD, size * 1.5);
+                rl(ui, "append", size);
+                if let Some(segment) = new_segment_buttons(ui, size) {
+                    gui_state.append(segment);
+                }
+            });
+            if gui_state.selected.is_some() {
+                ui.horizontal(|ui| {
+                    icon_button(ui, ICON_ADD, size * 1.5);
+                    rl(ui, "insert", size);
+                    if let Some(segment) = new_segment_buttons(ui, size) {
+                        gui_state.insert(segment);
+                    }
+                });
+            }
+            ui.separator();
+
+            if let Some(index) = gui_state.selected {
+                ui.horizontal(|ui| {
+                    rl(ui, format!("Segment {}", index), gui_state.base_text_size);
+                    if icon_button(ui, ICON_DELETE, size * 1.5).clicked()
+                        || (keyboard_input.just_pressed(KeyCode::Delete)
+                            && !ui.ctx().wants_keyboard_input())
+                    {
+                        gui_state.delete_selected();
+                    }
+                });
+            }
+            if let Some(index) = gui_state.selected {
+                let mut segment = gui_state.segments[index];
+                if segment_editor(ui, &mut segment, size) {
+                    gui_state.segments[index] = segment;
+                    gui_state.edited();
+                }
+                ui.separator();
+            }
+
+            rl(ui, "Checks", gui_state.base_text_size);
+            if gui_state.issues.is_empty() {
+                rlc(ui, "track ok", size, egui::Color32::GREEN);
+            }
+            for issue in &gui_state.issues {
+                rlc(ui, issue, size, egui::Color32::YELLOW);
+            }
+        });
+
+    keyboard_camera_control(
+        &mut po_camera,
+        po_transform,
+        &keyboard_input,
+        time.delta_secs(),
+    );
+
+    Ok(())
+}
+
+/// Marks the highlight of the selected segment
+#[derive(Component)]
+struct SelectionHighlight;
+
+/// Rebuilds the track (and its entities) from the edited segments, or just
+/// moves the highlight when only the selection changed
+fn update_track_preview(
+    mut commands: Commands,
+    mut gui_state: ResMut<EditorGuiState>,
+    mut track: ResMut<Track>,
+    roots: Query<Entity, With<TrackRoot>>,
+    highlights: Query<Entity, With<SelectionHighlight>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let track_root = if gui_state.changed {
+        rebuild_track_preview(
+            &mut commands,
+            &mut gui_state,
+            &mut track,
+            &roots,
+            &mut meshes,
+            &mut materials,
+        )
+    } else if gui_state.selection_changed {
+        for highlight in highlights.iter() {
+            commands.entity(highlight).despawn();
+        }
+        match roots.iter().next() {
+            Some(track_root) => track_root,
+            None => return,
+        }
+    } else {
+        return;
+    };
+    gui_state.changed = false;
+    gui_state.selection_changed = false;
+
+    if let Some(index) = gui_state.selected
+        && let Some(transform) = track.segment_world_transform(index)
+    {
+        commands.spawn((
+            SelectionHighlight,
+            ChildOf(track_root),
+            transform.with_translation(transform.translation + Vec3::Z * 0.002),
+            Mesh3d(meshes.add(track.segments()[index].background_mesh())),
+            MeshMaterial3d(materials.add(Color::srgba(1.0, 0.6, 0.0, 0.5))),
+        ));
+    }
+}
+
+/// Replaces the track with the edited segments and respawns its entities,
+/// returns the new track root
+fn rebuild_track_preview(
+    commands: &mut Commands,
+    gui_state: &mut EditorGuiState,
+    track: &mut Track,
+    roots: &Query<Entity, With<TrackRoot>>,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+) -> Entity {
+    *track = track
+        .clone()
+        .with_segments(gui_state.segments.clone())
+        .with_obstacles(gui_state.obstacles.clone());
+    gui_state.issues = TrackFile::from_track(track)
+        .validate()
+        .err()
+        .into_iter()
+        .chain(check_track(track).iter().map(|issue| issue.to_string()))
+        .collect();
+
+    for root in roots.iter() {
+        commands.entity(root).despawn();
+    }
+    let assets = TrackAssets::new(track, meshes, materials);
+    let track_root = commands.spawn((TrackRoot, Transform::default())).id();
+    // The bottom layer draws the floor only, the segments go on a second one
+    for is_bottom in [true, false] {
+        setup_track(
+            commands,
+            track_root,
+            EntityFeatures::Visualization,
+            track,
+            is_bottom,
+            Some(&assets),
+        );
+    }
+    commands.insert_resource(assets);
+    track_root
+}
+
+/// Selects the segment under the pointer (clicking away from the track
+/// clears the selection)
+fn select_clicked_segment(
+    trigger: Trigger<Pointer<Click>>,
+    mut contexts: EguiContexts,
+    track: Res<Track>,
+    mut gui_state: ResMut<EditorGuiState>,
+) {
+    if trigger.event().button != PointerButton::Primary {
+        return;
+    }
+    if let Ok(ctx) = contexts.ctx_mut()
+        && ctx.is_pointer_over_area()
+    {
+        return;
+    }
+    if let Some(position) = trigger.event().hit.position {
+        gui_state.select(track.segment_at(position.truncate()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::track::ObstacleShape;
+
+    fn obstacle(segment: usize) -> Obstacle {
+        Obstacle {
+            segment,
+            along: 0.1,
+            offset: 0.0,
+            shape: ObstacleShape::Cylinder {
+                radius: 0.02,
+                height: 0.05,
+            },
+        }
+    }
+
+    fn editor() -> EditorGuiState {
+        EditorGuiState::new(
+            String::new(),
+            vec![
+                TrackSegment::start(),
+                TrackSegment::straight(0.5),
+                TrackSegment::straight(0.6),
+                TrackSegment::straight(0.7),
+                TrackSegment::end(),
+            ],
+            vec![obstacle(1), obstacle(3)],
+        )
+    }
+
+    fn obstacle_segments(editor: &EditorGuiState) -> Vec<usize> {
+        editor
+            .obstacles
+            .iter()
+            .map(|obstacle| obstacle.segment)
+            .collect()
+    }
+
+    #[test]
+    fn insert_moves_the_following_obstacles() {
+        let mut editor = editor();
+        editor.insert_at(3, TrackSegment::straight(0.2));
+        assert_eq!(obstacle_segments(&editor), [1, 4]);
+        assert_eq!(editor.segments[4], TrackSegment::straight(0.7));
+
+        // Inserting before a segment moves its obstacles along with it
+        editor.insert_at(1, TrackSegment::straight(0.2));
+        assert_eq!(obstacle_segments(&editor), [2, 5]);
+        assert_eq!(editor.selected, Some(1));
+    }
+
+    #[test]
+    fn delete_drops_the_obstacles_of_the_segment() {
+        let mut editor = editor();
+        editor.select(Some(1));
+        editor.delete_selected();
+        assert_eq!(obstacle_segments(&editor), [2]);
+        assert_eq!(editor.segments[2], TrackSegment::straight(0.7));
+        assert_eq!(editor.selected, Some(1));
+
+        // A segment without obstacles only shifts the following ones
+        editor.delete_selected();
+        assert_eq!(obstacle_segments(&editor), [1]);
+        assert_eq!(editor.segments[1], TrackSegment::straight(0.7));
+    }
+}
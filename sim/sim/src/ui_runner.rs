@@ -23,15 +23,16 @@ use egui_material_icons::icons::{
     ICON_HELP, ICON_PAUSE, ICON_PLAY_ARROW, ICON_SKIP_NEXT, ICON_SKIP_PREVIOUS, ICON_ZOOM_IN,
     ICON_ZOOM_OUT,
 };
-use execution_data::BotStatus;
+use execution_data::{BotStatus, LapInfo};
 use executor::wasmtime;
 
 use crate::{
     app_builder::VisualizerData,
     bot::vis::BotAssets,
+    par_time::ParTime,
     runner::BotExecutionData,
     server::start_server,
-    track::Track,
+    track::{Track, TrackAssets},
     ui::{
         HelpState, camera_buttons, error_dialog, help_dialog, icon_button, keyboard_camera_control,
         process_new_bot, rl,
@@ -153,7 +154,7 @@ impl RunnerGuiState {
         track: &Track,
         bot_vis: &Query<(Entity, &mut BotVisualization)>,
         bot_assets: &BotAssets,
-        meshes: &mut Assets<Mesh>,
+        track_assets: &TrackAssets,
         materials: &mut Assets<StandardMaterial>,
     ) {
         let mut new_bots = Vec::new();
@@ -187,7 +188,13 @@ impl RunnerGuiState {
 
             println!("new bot (steps {})", bot.data.body_data.steps.len());
             spawn_bot_visualization(
-                commands, track, bot.data, bot.config, bot_assets, meshes, materials,
+                commands,
+                track,
+                bot.data,
+                bot.config,
+                bot_assets,
+                track_assets,
+                materials,
             );
         }
     }
@@ -204,7 +211,7 @@ fn runner_gui_update(
     track: Res<Track>,
     time: Res<Time>,
     bot_assets: Res<BotAssets>,
-    mut meshes: ResMut<Assets<Mesh>>,
+    track_assets: Res<TrackAssets>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) -> Result {
     let ctx = contexts.ctx_mut()?;
@@ -223,7 +230,7 @@ fn runner_gui_update(
                 &track,
                 &bot_vis,
                 &bot_assets,
-                &mut meshes,
+                &track_assets,
                 &mut materials,
             );
         }
@@ -233,7 +240,7 @@ fn runner_gui_update(
             &track,
             &bot_vis,
             &bot_assets,
-            &mut meshes,
+            &track_assets,
             &mut materials,
         );
     }
@@ -431,6 +438,24 @@ fn runner_gui_update(
                                 bot.config.color_secondary.b,
                             ),
                             bot.bot_activity.status_at_time(gui_state.play_time_sec),
+                            BotProgress {
+                                count: match track.laps() {
+                                    Some(laps) => ProgressCount::Laps(
+                                        bot.bot_activity.lap_info_at_time(gui_state.play_time_sec),
+                                        laps,
+                                    ),
+                                    None => ProgressCount::Checkpoints(
+                                        bot.bot_activity
+                                            .checkpoints_at_time(gui_state.play_time_sec),
+                                        track.checkpoint_count(),
+                                    ),
+                                },
+                                distance: bot
+                                    .bot_activity
+                                    .progress_at_time(gui_state.play_time_sec),
+                                race_length: track.race_length(),
+                                par_time: bot.par_time,
+                            },
                             gui_state.base_text_size,
                         ) {
                             gui_state.as_mut().bot_with_pending_remove = Some(BotName {
@@ -559,6 +584,7 @@ impl BotstatusExt for BotStatus {
             BotStatus::Racing { .. } => Color32::WHITE,
             BotStatus::EndedAt { .. } => Color32::GREEN,
             BotStatus::OutAt { .. } => Color32::RED,
+            BotStatus::CollidedAt { .. } => Color32::ORANGE,
         }
     }
 
@@ -567,26 +593,91 @@ impl BotstatusExt for BotStatus {
     }
 }
 
+/// What a bot has covered of the track so far
+struct BotProgress {
+    count: ProgressCount,
+    /// Distance covered along the track (in meters)
+    distance: f32,
+    race_length: f32,
+    /// Reference times of the bot configuration on the track
+    par_time: Option<ParTime>,
+}
+
+enum ProgressCount {
+    /// Lap info and the total number of laps of a closed loop
+    Laps(LapInfo, usize),
+    /// Checkpoints reached and the number of checkpoints of an open track
+    Checkpoints(usize, usize),
+}
+
 fn bot_status(
     ui: &mut Ui,
     name: &str,
     c1: Color32,
     c2: Color32,
     status: BotStatus,
+    progress: BotProgress,
     base_text_size: f32,
 ) -> bool {
     let mut response = false;
-    ui.horizontal(|ui| {
-        response = bot_name(ui, name, c1, c2, base_text_size);
+    ui.vertical(|ui| {
+        ui.horizontal(|ui| {
+            response = bot_name(ui, name, c1, c2, base_text_size);
+
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                ui.label(
+                    egui::RichText::new(format!("{:.2}", status.timer()))
+                        .color(status.color())
+                        .strong()
+                        .size(base_text_size * 3.0),
+                );
+            });
+        });
 
-        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+        let format_lap = |lap_secs: Option<f32>| match lap_secs {
+            Some(lap_secs) => format!("{:.2}", lap_secs),
+            None => "-".to_string(),
+        };
+        let progress_text = match progress.count {
+            ProgressCount::Laps(lap_info, laps) => format!(
+                "laps {}/{}   last {}   best {}",
+                lap_info.completed_laps,
+                laps,
+                format_lap(lap_info.last_lap_secs),
+                format_lap(lap_info.best_lap_secs),
+            ),
+            ProgressCount::Checkpoints(reached, count) => {
+                format!("checkpoints {}/{}", reached.min(count), count)
+            }
+        };
+        ui.label(
+            egui::RichText::new(progress_text)
+                .color(status.color())
+                .size(base_text_size * 1.5),
+        );
+        ui.label(
+            egui::RichText::new(format!(
+                "progress {:.2}/{:.2} m ({:.0}%)",
+                progress.distance.min(progress.race_length),
+                progress.race_length,
+                (progress.distance / progress.race_length).min(1.0) * 100.0,
+            ))
+            .color(status.color())
+            .size(base_text_size * 1.5),
+        );
+        if let Some(par_time) = progress.par_time {
+            let par_text = match par_time.lap_secs {
+                Some(lap_secs) => {
+                    format!("par {:.2}   par lap {:.2}", par_time.race_secs, lap_secs)
+                }
+                None => format!("par {:.2}", par_time.race_secs),
+            };
             ui.label(
-                egui::RichText::new(format!("{:.2}", status.timer()))
-                    .color(status.color())
-                    .strong()
-                    .size(base_text_size * 3.0),
+                egui::RichText::new(par_text)
+                    .color(Color32::GRAY)
+                    .size(base_text_size * 1.5),
             );
-        });
+        }
     });
     response
 }
@@ -83,6 +83,8 @@ fn test_gui_update(
 
                 let sensors_color = if sensors.is_over_track_end {
                     egui::Color32::WHITE
+                } else if sensors.is_hitting_obstacle {
+                    egui::Color32::ORANGE
                 } else if sensors.is_out_of_track {
                     egui::Color32::RED
                 } else {
@@ -1,10 +1,11 @@
 use bevy::ecs::resource::Resource;
 use bevy::math::{EulerRot, Vec2, Vec3};
 use bevy::transform::components::GlobalTransform;
-use execution_data::MotorDriversDutyCycles;
+use execution_data::{MotorDriversDutyCycles, MotorDriversIdleModes, MotorIdleMode};
 use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Side {
     Left,
     Right,
@@ -36,6 +37,15 @@ pub trait GetBySide<T: Copy> {
     fn get_by_side(&self, side: Side) -> T;
 }
 
+impl GetBySide<MotorIdleMode> for MotorDriversIdleModes {
+    fn get_by_side(&self, side: Side) -> MotorIdleMode {
+        match side {
+            Side::Left => self.left,
+            Side::Right => self.right,
+        }
+    }
+}
+
 impl GetBySide<i16> for MotorDriversDutyCycles {
     fn get_by_side(&self, side: Side) -> i16 {
         match side {
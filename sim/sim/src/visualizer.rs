@@ -6,7 +6,6 @@ use bevy::{
     },
     math::Quat,
     pbr::StandardMaterial,
-    render::mesh::Mesh,
     transform::components::Transform,
 };
 use execution_data::{
@@ -16,7 +15,8 @@ use executor::wasm_bindings::exports::robot::Configuration;
 
 use crate::{
     bot::vis::{BotAssets, spawn_bot_body, spawn_bot_wheel},
-    track::{Track, setup_track},
+    par_time::{ParTime, par_time},
+    track::{Track, TrackAssets, setup_track},
     ui_runner::RunnerGuiState,
     utils::EntityFeatures,
 };
@@ -26,6 +26,7 @@ pub struct BotVisualization {
     pub config: Configuration,
     pub bot_activity: ActivityData,
     pub bot_final_status: BotFinalStatus,
+    pub par_time: Option<ParTime>,
 }
 
 const VIS_LAYER_Z_STEP: f32 = 0.7;
@@ -42,13 +43,14 @@ pub fn spawn_bot_visualization(
     data: ExecutionData,
     configuration: Configuration,
     bot_assets: &BotAssets,
-    meshes: &mut Assets<Mesh>,
+    track_assets: &TrackAssets,
     materials: &mut Assets<StandardMaterial>,
 ) {
     let root_component = BotVisualization {
         config: configuration.clone(),
-        bot_activity: data.activity_data,
         bot_final_status: data.activity_data.final_status(),
+        par_time: par_time(track, &configuration),
+        bot_activity: data.activity_data,
     };
     let root_transform = root_component.build_transform(0);
     let track_root = commands.spawn((root_component, root_transform)).id();
@@ -59,8 +61,7 @@ pub fn spawn_bot_visualization(
         EntityFeatures::Visualization,
         track,
         false,
-        meshes,
-        materials,
+        Some(track_assets),
     );
 
     let bot = spawn_bot_body(